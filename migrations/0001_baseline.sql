@@ -0,0 +1,25 @@
+-- tables inherited from geekbot, only created when starting from an empty database
+CREATE TABLE IF NOT EXISTS Quote (
+    id BIGINT NOT NULL AUTO_INCREMENT PRIMARY KEY,
+    quote TEXT NOT NULL,
+    number BIGINT NOT NULL
+) DEFAULT CHARSET = utf8mb4 COLLATE = utf8mb4_unicode_ci;
+
+CREATE TABLE IF NOT EXISTS Connerie (
+    id BIGINT NOT NULL AUTO_INCREMENT PRIMARY KEY,
+    value TEXT NOT NULL,
+    author VARCHAR(255) NULL
+) DEFAULT CHARSET = utf8mb4 COLLATE = utf8mb4_unicode_ci;
+
+CREATE TABLE IF NOT EXISTS Skandite (
+    id BIGINT NOT NULL AUTO_INCREMENT PRIMARY KEY,
+    url VARCHAR(2048) NOT NULL,
+    postedDate DATETIME NOT NULL,
+    author VARCHAR(255) NOT NULL,
+    count BIGINT NOT NULL
+) DEFAULT CHARSET = utf8mb4 COLLATE = utf8mb4_unicode_ci;
+
+CREATE TABLE IF NOT EXISTS RSSFeed (
+    id BIGINT NOT NULL AUTO_INCREMENT PRIMARY KEY,
+    guid VARCHAR(255) NOT NULL
+) DEFAULT CHARSET = utf8mb4 COLLATE = utf8mb4_unicode_ci;
@@ -0,0 +1,12 @@
+CREATE TABLE ScheduledJob (
+    id BIGINT NOT NULL AUTO_INCREMENT PRIMARY KEY,
+    -- recurring jobs are registered at startup and identified by their name
+    name VARCHAR(255) NULL UNIQUE,
+    kind VARCHAR(64) NOT NULL,
+    payload TEXT NOT NULL,
+    -- cron expression, null for jobs that only run once
+    schedule VARCHAR(255) NULL,
+    nextRun DATETIME NOT NULL,
+    lastRun DATETIME NULL,
+    INDEX ScheduledJob_nextRun (nextRun)
+) DEFAULT CHARSET = utf8mb4 COLLATE = utf8mb4_unicode_ci;
@@ -0,0 +1,15 @@
+CREATE TABLE FeedSubscription (
+    id BIGINT NOT NULL AUTO_INCREMENT PRIMARY KEY,
+    guildId BIGINT UNSIGNED NULL,
+    channelId BIGINT UNSIGNED NOT NULL,
+    url VARCHAR(512) NOT NULL,
+    title VARCHAR(255) NOT NULL,
+    createdBy BIGINT UNSIGNED NOT NULL,
+    createdAt DATETIME NOT NULL,
+    UNIQUE INDEX FeedSubscription_channel_url (channelId, url)
+) DEFAULT CHARSET = utf8mb4 COLLATE = utf8mb4_unicode_ci;
+
+-- seen entries are now tracked per subscription, legacy rows keep a null subscription
+ALTER TABLE RSSFeed
+    ADD COLUMN subscriptionId BIGINT NULL,
+    ADD INDEX RSSFeed_subscription_guid (subscriptionId, guid);
@@ -0,0 +1,5 @@
+-- filter rules are stored one per line, a rule between slashes is a regex
+ALTER TABLE FeedSubscription
+    ADD COLUMN includeFilter TEXT NULL,
+    ADD COLUMN excludeFilter TEXT NULL,
+    ADD COLUMN template VARCHAR(1000) NULL;
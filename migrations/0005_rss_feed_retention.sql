@@ -0,0 +1,5 @@
+-- seen entries keep when they were first seen and when they were published, so that they can be pruned
+ALTER TABLE RSSFeed
+    ADD COLUMN firstSeen DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    ADD COLUMN publishedDate DATETIME NULL,
+    ADD INDEX RSSFeed_subscription_first_seen (subscriptionId, firstSeen);
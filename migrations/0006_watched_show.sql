@@ -0,0 +1,18 @@
+-- TV shows followed by a user (notified by direct message) or by a channel
+CREATE TABLE WatchedShow (
+    id BIGINT NOT NULL AUTO_INCREMENT PRIMARY KEY,
+    showId BIGINT UNSIGNED NOT NULL,
+    showName VARCHAR(255) NOT NULL,
+    userId BIGINT UNSIGNED NULL,
+    channelId BIGINT UNSIGNED NULL,
+    nextEpisodeId BIGINT UNSIGNED NULL,
+    nextEpisodeTitle VARCHAR(255) NULL,
+    nextAirstamp DATETIME NULL,
+    lastNotifiedEpisodeId BIGINT UNSIGNED NULL,
+    refreshedAt DATETIME NOT NULL,
+    createdAt DATETIME NOT NULL,
+    INDEX WatchedShow_show (showId),
+    INDEX WatchedShow_user (userId),
+    INDEX WatchedShow_channel (channelId),
+    INDEX WatchedShow_next_airstamp (nextAirstamp)
+) DEFAULT CHARSET = utf8mb4 COLLATE = utf8mb4_unicode_ci;
@@ -0,0 +1,16 @@
+-- who said a quote, where and when, and who added it
+ALTER TABLE Quote
+    ADD COLUMN authorId BIGINT UNSIGNED NULL,
+    ADD COLUMN authorName VARCHAR(255) NULL,
+    ADD COLUMN guildId BIGINT UNSIGNED NULL,
+    ADD COLUMN channelId BIGINT UNSIGNED NULL,
+    ADD COLUMN messageId BIGINT UNSIGNED NULL,
+    ADD COLUMN addedBy BIGINT UNSIGNED NULL,
+    ADD COLUMN quotedAt DATETIME NULL,
+    ADD COLUMN createdAt DATETIME NULL;
+
+-- legacy quotes were stored as "<author name> content"
+UPDATE Quote
+SET authorName = SUBSTRING(quote, 2, LOCATE('> ', quote) - 2),
+    quote = SUBSTRING(quote, LOCATE('> ', quote) + 2)
+WHERE quote LIKE '<%> %' AND LOCATE('> ', quote) > 2;
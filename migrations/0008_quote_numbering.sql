@@ -0,0 +1,26 @@
+-- quotes are soft deleted so that their number is never given to another quote
+ALTER TABLE Quote
+    ADD COLUMN deletedAt DATETIME NULL,
+    ADD COLUMN deletedBy BIGINT UNSIGNED NULL,
+    ADD COLUMN editedAt DATETIME NULL,
+    ADD COLUMN editedBy BIGINT UNSIGNED NULL;
+
+-- quotes that got the same number through concurrent additions get new numbers after the last one
+UPDATE Quote q
+JOIN (
+    SELECT d.id, (SELECT MAX(number) FROM Quote) + ROW_NUMBER() OVER (ORDER BY d.id) AS newNumber
+    FROM Quote d
+    WHERE EXISTS (SELECT 1 FROM Quote o WHERE o.number = d.number AND o.id < d.id)
+) duplicate ON duplicate.id = q.id
+SET q.number = duplicate.newNumber;
+
+ALTER TABLE Quote ADD UNIQUE INDEX Quote_number (number);
+
+-- last number given in each scope, incremented atomically when a quote is added
+CREATE TABLE QuoteSequence (
+    scope BIGINT UNSIGNED NOT NULL PRIMARY KEY,
+    lastNumber BIGINT NOT NULL
+) DEFAULT CHARSET = utf8mb4 COLLATE = utf8mb4_unicode_ci;
+
+INSERT INTO QuoteSequence (scope, lastNumber)
+SELECT 0, COALESCE(MAX(number), 0) FROM Quote;
@@ -0,0 +1,22 @@
+-- quotes belong to the book of the guild they were added in, legacy quotes without guild go to book 0
+-- until they are adopted by the guild configured as legacy_quote_guild_id
+ALTER TABLE Quote ADD COLUMN bookId BIGINT UNSIGNED NOT NULL DEFAULT 0;
+UPDATE Quote SET bookId = COALESCE(guildId, 0);
+
+ALTER TABLE Quote
+    DROP INDEX Quote_number,
+    ADD UNIQUE INDEX Quote_book_number (bookId, number);
+
+-- numbers are now given per book, the existing ones are kept and every book continues after the
+-- last global number so that the legacy quotes can still be adopted by a guild without conflict
+INSERT INTO QuoteSequence (scope, lastNumber)
+SELECT DISTINCT bookId, (SELECT MAX(number) FROM Quote) FROM Quote WHERE bookId <> 0;
+
+-- a guild using the quote book of another guild, which shared it
+CREATE TABLE QuoteBookLink (
+    guildId BIGINT UNSIGNED NOT NULL PRIMARY KEY,
+    bookId BIGINT UNSIGNED NOT NULL,
+    createdBy BIGINT UNSIGNED NOT NULL,
+    createdAt DATETIME NOT NULL,
+    INDEX QuoteBookLink_book (bookId)
+) DEFAULT CHARSET = utf8mb4 COLLATE = utf8mb4_unicode_ci;
@@ -0,0 +1,5 @@
+-- the quotes inherited from geekbot may use another collation, utf8mb4_unicode_ci makes the search
+-- insensitive to case and accents
+ALTER TABLE Quote CONVERT TO CHARACTER SET utf8mb4 COLLATE utf8mb4_unicode_ci;
+
+ALTER TABLE Quote ADD FULLTEXT INDEX Quote_fulltext (authorName, quote);
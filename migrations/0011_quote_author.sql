@@ -0,0 +1,2 @@
+-- quotes are listed and counted by the member quoted
+ALTER TABLE Quote ADD INDEX Quote_book_author (bookId, authorId);
@@ -0,0 +1,8 @@
+-- votes of the members on the quotes displayed by the bot, 1 for 👍 and -1 for 👎
+CREATE TABLE QuoteVote (
+    quoteId BIGINT NOT NULL,
+    userId BIGINT UNSIGNED NOT NULL,
+    value TINYINT NOT NULL,
+    votedAt DATETIME NOT NULL,
+    PRIMARY KEY (quoteId, userId)
+) DEFAULT CHARSET = utf8mb4 COLLATE = utf8mb4_unicode_ci;
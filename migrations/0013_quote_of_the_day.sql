@@ -0,0 +1,23 @@
+-- channel of a guild receiving a quote every day at a local time
+CREATE TABLE QuoteOfTheDay (
+    guildId BIGINT UNSIGNED NOT NULL PRIMARY KEY,
+    channelId BIGINT UNSIGNED NOT NULL,
+    postTime TIME NOT NULL,
+    timezone VARCHAR(64) NOT NULL,
+    onThisDay BOOLEAN NOT NULL DEFAULT FALSE,
+    -- quotes are not posted twice in a cycle, a new cycle starts when they have all been posted
+    cycle BIGINT NOT NULL DEFAULT 0,
+    createdBy BIGINT UNSIGNED NOT NULL,
+    createdAt DATETIME NOT NULL
+) DEFAULT CHARSET = utf8mb4 COLLATE = utf8mb4_unicode_ci;
+
+-- a single post per guild and local date, so that a restart never posts twice
+CREATE TABLE QuoteOfTheDayPost (
+    guildId BIGINT UNSIGNED NOT NULL,
+    postedOn DATE NOT NULL,
+    quoteId BIGINT NOT NULL,
+    cycle BIGINT NOT NULL,
+    postedAt DATETIME NOT NULL,
+    PRIMARY KEY (guildId, postedOn),
+    INDEX QuoteOfTheDayPost_cycle (guildId, cycle, quoteId)
+) DEFAULT CHARSET = utf8mb4 COLLATE = utf8mb4_unicode_ci;
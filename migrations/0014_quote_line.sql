@@ -0,0 +1,13 @@
+-- the messages of a quote capturing a conversation, in order
+CREATE TABLE QuoteLine (
+    id BIGINT NOT NULL AUTO_INCREMENT PRIMARY KEY,
+    quoteId BIGINT NOT NULL,
+    position INT NOT NULL,
+    authorId BIGINT UNSIGNED NOT NULL,
+    authorName VARCHAR(255) NOT NULL,
+    messageId BIGINT UNSIGNED NOT NULL,
+    content TEXT NOT NULL,
+    postedAt DATETIME NULL,
+    UNIQUE INDEX QuoteLine_quote_position (quoteId, position),
+    INDEX QuoteLine_author (authorId)
+) DEFAULT CHARSET = utf8mb4 COLLATE = utf8mb4_unicode_ci;
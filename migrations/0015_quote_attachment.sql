@@ -0,0 +1,12 @@
+-- files of the quoted messages, archived in the quote_archive_dir directory since Discord links expire
+CREATE TABLE QuoteAttachment (
+    id BIGINT NOT NULL AUTO_INCREMENT PRIMARY KEY,
+    quoteId BIGINT NOT NULL,
+    messageId BIGINT UNSIGNED NOT NULL,
+    filename VARCHAR(255) NOT NULL,
+    contentType VARCHAR(255) NULL,
+    -- relative to the archive directory
+    path VARCHAR(1024) NOT NULL,
+    size BIGINT UNSIGNED NOT NULL,
+    INDEX QuoteAttachment_quote (quoteId)
+) DEFAULT CHARSET = utf8mb4 COLLATE = utf8mb4_unicode_ci;
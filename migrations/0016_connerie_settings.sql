@@ -0,0 +1,7 @@
+-- how passive replies are picked in a guild, the guilds without settings use connerie_reply_mode
+CREATE TABLE ConnerieSettings (
+    guildId BIGINT UNSIGNED NOT NULL PRIMARY KEY,
+    replyMode VARCHAR(16) NOT NULL,
+    updatedBy BIGINT UNSIGNED NOT NULL,
+    updatedAt DATETIME NOT NULL
+) DEFAULT CHARSET = utf8mb4 COLLATE = utf8mb4_unicode_ci;
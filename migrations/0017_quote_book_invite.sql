@@ -0,0 +1,8 @@
+-- a code given by a guild sharing its quote book, redeemed once by an admin of the guild joining it
+CREATE TABLE QuoteBookInvite (
+    code VARCHAR(16) NOT NULL PRIMARY KEY,
+    bookId BIGINT UNSIGNED NOT NULL,
+    createdBy BIGINT UNSIGNED NOT NULL,
+    createdAt DATETIME NOT NULL,
+    expiresAt DATETIME NOT NULL
+) DEFAULT CHARSET = utf8mb4 COLLATE = utf8mb4_unicode_ci;
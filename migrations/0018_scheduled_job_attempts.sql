@@ -0,0 +1,2 @@
+-- failed runs of a job that only runs once, it is retried later until it has failed too often
+ALTER TABLE ScheduledJob ADD COLUMN attempts INT NOT NULL DEFAULT 0;
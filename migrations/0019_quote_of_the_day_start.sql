@@ -0,0 +1,2 @@
+-- first local date posted, so that a configuration made after the posting time waits for the next day
+ALTER TABLE QuoteOfTheDay ADD COLUMN startsOn DATE NULL;
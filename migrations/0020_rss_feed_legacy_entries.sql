@@ -0,0 +1,3 @@
+-- entries seen before the subscriptions were tracked globally, no subscription reads them and pruning
+-- only goes through subscriptions, so they would be kept forever
+DELETE FROM RSSFeed WHERE subscriptionId IS NULL;
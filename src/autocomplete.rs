@@ -0,0 +1,98 @@
+use crate::interactions::StateStore;
+use crate::output::truncate;
+use serenity::model::application::interaction::application_command::CommandDataOption;
+use serenity::model::id::UserId;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Discord refuses more suggestions than this
+pub const MAX_CHOICES: usize = 25;
+const CHOICE_MAX_LENGTH: usize = 100;
+const DEBOUNCE_DELAY: Duration = Duration::from_millis(300);
+const CACHE_TTL: Duration = Duration::from_secs(60);
+
+/// a suggestion for an option, as displayed name and submitted value
+pub type Choice = (String, String);
+
+/**
+ * build a suggestion, shortening the name and value to what Discord accepts
+ */
+pub fn choice(name: &str, value: &str) -> Choice {
+    (
+        truncate(name, CHOICE_MAX_LENGTH),
+        truncate(value, CHOICE_MAX_LENGTH),
+    )
+}
+
+/**
+ * find the option the user is currently typing, looking into sub commands
+ */
+pub fn focused_option(options: &[CommandDataOption]) -> Option<&CommandDataOption> {
+    options.iter().find_map(|option| {
+        if option.focused {
+            Some(option)
+        } else {
+            focused_option(&option.options)
+        }
+    })
+}
+
+/**
+ * the text the user is currently typing
+ */
+pub fn focused_value(options: &[CommandDataOption]) -> Option<&str> {
+    focused_option(options)
+        .and_then(|o| o.value.as_ref())
+        .and_then(|v| v.as_str())
+}
+
+/// debounces and caches autocomplete requests, Discord sends one for every key stroke
+pub struct Autocompleter {
+    generations: Mutex<HashMap<UserId, u64>>,
+    cache: StateStore<Vec<Choice>>,
+}
+
+impl Default for Autocompleter {
+    fn default() -> Self {
+        Autocompleter {
+            generations: Mutex::new(HashMap::new()),
+            cache: StateStore::new(CACHE_TTL),
+        }
+    }
+}
+
+impl Autocompleter {
+    /**
+     * wait a little and return false if the user typed something else in the meantime
+     */
+    pub async fn debounce(&self, user_id: UserId) -> bool {
+        let generation = {
+            let mut generations = self.generations.lock().unwrap();
+            let generation = generations.entry(user_id).or_insert(0);
+            *generation += 1;
+            *generation
+        };
+
+        tokio::time::sleep(DEBOUNCE_DELAY).await;
+
+        let generations = self.generations.lock().unwrap();
+        generations.get(&user_id) == Some(&generation)
+    }
+
+    pub fn cached(&self, key: &str) -> Option<Vec<Choice>> {
+        self.cache.get(key)
+    }
+
+    pub fn store(&self, key: &str, choices: Vec<Choice>) {
+        self.cache.set(key, choices);
+    }
+
+    /**
+     * drop the cached suggestions of a guild, e.g. when it starts using another quote book
+     */
+    pub fn forget_guild(&self, guild_id: u64) {
+        let prefix = format!("{}:", guild_id);
+        self.cache.remove_matching(|key| key.starts_with(&prefix));
+    }
+}
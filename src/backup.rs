@@ -0,0 +1,421 @@
+use crate::db::backup::export;
+use crate::db::backup::import;
+use crate::db::backup::ImportOptions;
+use crate::db::backup::ImportedIds;
+use crate::db::backup::Record;
+use crate::db::backup::Table;
+use crate::db::backup::TABLES;
+use anyhow::anyhow;
+use anyhow::Error;
+use serde_json::Value;
+use sqlx::MySqlPool;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+pub const USAGE: &str = r#"usage:
+    xzibot export <table|all> [--format jsonl|csv] [--output <path>]
+    xzibot import <table|all> <path> [--format jsonl|csv] [--map <field>=<column>]... [--book <id>] [--renumber] [--dry-run]
+
+tables: Quote, QuoteLine, QuoteAttachment, QuoteVote, Connerie, Skandite, FeedSubscription, RSSFeed
+with all, the path is a directory holding a file per table, named after the table
+the files of the quote attachments are not exported, copy the quote_archive_dir directory along
+lines, attachments, votes and seen entries imported without their quote or subscription must refer to an existing row
+--map renames a field of the imported records, e.g. --map content=quote
+--book puts the imported quotes in the given book
+--renumber gives new numbers to all the imported quotes, instead of keeping the free ones
+--dry-run reports what would be imported without changing anything"#;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    JsonLines,
+    Csv,
+}
+
+impl Format {
+    fn parse(name: &str) -> Option<Format> {
+        match name.to_lowercase().as_str() {
+            "jsonl" | "json" => Some(Format::JsonLines),
+            "csv" => Some(Format::Csv),
+            _ => None,
+        }
+    }
+
+    fn from_path(path: &Path) -> Option<Format> {
+        path.extension()
+            .and_then(|e| e.to_str())
+            .and_then(Format::parse)
+    }
+
+    fn extension(&self) -> &'static str {
+        match self {
+            Format::JsonLines => "jsonl",
+            Format::Csv => "csv",
+        }
+    }
+}
+
+/// the tables of a command, all of them meaning a file per table in a directory
+#[derive(Debug, PartialEq)]
+pub enum Tables {
+    One(Table),
+    All,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum BackupCommand {
+    Export {
+        tables: Tables,
+        format: Option<Format>,
+        output: Option<PathBuf>,
+    },
+    Import {
+        tables: Tables,
+        input: PathBuf,
+        format: Option<Format>,
+        /// field of the records -> column of the table
+        mapping: HashMap<String, String>,
+        book_id: Option<u64>,
+        renumber: bool,
+        dry_run: bool,
+    },
+}
+
+impl BackupCommand {
+    /**
+     * the command given on the command line, none when the bot should start
+     */
+    pub fn parse(args: &[String]) -> Option<Result<BackupCommand, Error>> {
+        let (command, args) = args.split_first()?;
+        if command != "export" && command != "import" {
+            return None;
+        }
+        Some(BackupCommand::parse_command(command, args))
+    }
+
+    fn parse_command(command: &str, args: &[String]) -> Result<BackupCommand, Error> {
+        let mut positional = Vec::new();
+        let mut format = None;
+        let mut output = None;
+        let mut mapping = HashMap::new();
+        let mut book_id = None;
+        let mut renumber = false;
+        let mut dry_run = false;
+
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .ok_or_else(|| anyhow!("missing value for {}", arg))
+            };
+            match arg.as_str() {
+                "--format" => {
+                    let name = value()?;
+                    format = Some(
+                        Format::parse(name).ok_or_else(|| anyhow!("unknown format {}", name))?,
+                    );
+                }
+                "--output" => output = Some(PathBuf::from(value()?)),
+                "--map" => {
+                    let pair = value()?;
+                    let (field, column) = pair.split_once('=').ok_or_else(|| {
+                        anyhow!("invalid mapping {}, expected field=column", pair)
+                    })?;
+                    mapping.insert(field.to_string(), column.to_string());
+                }
+                "--book" => book_id = Some(value()?.parse()?),
+                "--renumber" => renumber = true,
+                "--dry-run" => dry_run = true,
+                a if a.starts_with("--") => return Err(anyhow!("unknown option {}", a)),
+                a => positional.push(a.to_string()),
+            }
+        }
+
+        let tables = match positional.first() {
+            Some(name) if name.eq_ignore_ascii_case("all") => Tables::All,
+            Some(name) => {
+                Tables::One(Table::parse(name).ok_or_else(|| anyhow!("unknown table {}", name))?)
+            }
+            None => return Err(anyhow!("missing table")),
+        };
+        if command == "export" {
+            if positional.len() > 1 {
+                return Err(anyhow!("unexpected argument {}", positional[1]));
+            }
+            return Ok(BackupCommand::Export {
+                tables,
+                format,
+                output,
+            });
+        }
+
+        let input = match positional.as_slice() {
+            [_, input] => PathBuf::from(input),
+            [_] => return Err(anyhow!("missing file to import")),
+            _ => return Err(anyhow!("unexpected argument {}", positional[2])),
+        };
+        Ok(BackupCommand::Import {
+            tables,
+            input,
+            format,
+            mapping,
+            book_id,
+            renumber,
+            dry_run,
+        })
+    }
+
+    /**
+     * whether the command changes the database, exports and dry runs do not
+     */
+    pub fn writes(&self) -> bool {
+        matches!(self, BackupCommand::Import { dry_run: false, .. })
+    }
+
+    pub async fn run(self, pool: &MySqlPool) -> Result<(), Error> {
+        match self {
+            BackupCommand::Export {
+                tables: Tables::One(table),
+                format,
+                output,
+            } => {
+                let format = format
+                    .or_else(|| output.as_deref().and_then(Format::from_path))
+                    .unwrap_or(Format::JsonLines);
+                let records = export(pool, table).await?;
+                match output {
+                    Some(path) => write_records(fs::File::create(path)?, table, format, &records)?,
+                    None => write_records(io::stdout().lock(), table, format, &records)?,
+                }
+                eprintln!("{} : {} exported", table.name(), records.len());
+            }
+            BackupCommand::Export {
+                tables: Tables::All,
+                format,
+                output,
+            } => {
+                let dir =
+                    output.ok_or_else(|| anyhow!("--output is required to export all tables"))?;
+                let format = format.unwrap_or(Format::JsonLines);
+                fs::create_dir_all(&dir)?;
+                for table in TABLES {
+                    let records = export(pool, table).await?;
+                    let path = dir.join(format!("{}.{}", table.name(), format.extension()));
+                    write_records(fs::File::create(path)?, table, format, &records)?;
+                    eprintln!("{} : {} exported", table.name(), records.len());
+                }
+            }
+            BackupCommand::Import {
+                tables,
+                input,
+                format,
+                mapping,
+                book_id,
+                renumber,
+                dry_run,
+            } => {
+                let options = ImportOptions {
+                    book_id,
+                    renumber,
+                    dry_run,
+                };
+                let files = match tables {
+                    Tables::One(table) => vec![(table, input)],
+                    Tables::All => TABLES
+                        .into_iter()
+                        .filter_map(|t| {
+                            [Format::JsonLines, Format::Csv]
+                                .into_iter()
+                                .filter(|f| format.is_none() || format == Some(*f))
+                                .map(|f| input.join(format!("{}.{}", t.name(), f.extension())))
+                                .find(|p| p.exists())
+                                .map(|p| (t, p))
+                        })
+                        .collect(),
+                };
+                // the records of a table refer to the rows imported before them
+                let mut ids = ImportedIds::default();
+                for (table, path) in files {
+                    let format = format
+                        .or_else(|| Format::from_path(&path))
+                        .unwrap_or(Format::JsonLines);
+                    let records = read_records(&path, format)?
+                        .into_iter()
+                        .map(|r| map_fields(r, &mapping))
+                        .collect::<Vec<Record>>();
+                    let report = import(pool, table, &records, &options, &mut ids).await?;
+                    eprintln!(
+                        "{}{} : {} imported, {} duplicates, {} renumbered, {} rejected",
+                        if dry_run { "[dry run] " } else { "" },
+                        table.name(),
+                        report.imported,
+                        report.duplicates,
+                        report.renumbered,
+                        report.rejected.len()
+                    );
+                    for (line, reason) in report.rejected {
+                        eprintln!("    record {} : {}", line, reason);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/**
+ * rename the fields of a record, the other fields are kept as they are
+ */
+fn map_fields(record: Record, mapping: &HashMap<String, String>) -> Record {
+    record
+        .into_iter()
+        .map(|(field, value)| match mapping.get(&field) {
+            Some(column) => (column.clone(), value),
+            None => (field, value),
+        })
+        .collect()
+}
+
+fn write_records(
+    mut writer: impl Write,
+    table: Table,
+    format: Format,
+    records: &[Record],
+) -> Result<(), Error> {
+    match format {
+        Format::JsonLines => {
+            for record in records {
+                serde_json::to_writer(&mut writer, record)?;
+                writer.write_all(b"\n")?;
+            }
+        }
+        Format::Csv => {
+            let mut csv = csv::Writer::from_writer(writer);
+            csv.write_record(table.columns().iter().map(|c| c.name))?;
+            for record in records {
+                csv.write_record(table.columns().iter().map(|c| match record.get(c.name) {
+                    Some(Value::String(s)) => s.clone(),
+                    Some(Value::Null) | None => String::new(),
+                    Some(v) => v.to_string(),
+                }))?;
+            }
+            csv.flush()?;
+        }
+    }
+    Ok(())
+}
+
+fn read_records(path: &Path, format: Format) -> Result<Vec<Record>, Error> {
+    let file = fs::File::open(path)?;
+    let mut records = Vec::new();
+    match format {
+        Format::JsonLines => {
+            for (i, line) in BufReader::new(file).lines().enumerate() {
+                let line = line?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let record = serde_json::from_str(&line)
+                    .map_err(|e| anyhow!("{} line {} : {}", path.display(), i + 1, e))?;
+                records.push(record);
+            }
+        }
+        Format::Csv => {
+            let mut csv = csv::Reader::from_reader(file);
+            let headers = csv.headers()?.clone();
+            for row in csv.records() {
+                let row = row?;
+                records.push(
+                    headers
+                        .iter()
+                        .zip(row.iter())
+                        .map(|(h, v)| (h.to_string(), Value::String(v.to_string())))
+                        .collect(),
+                );
+            }
+        }
+    }
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BackupCommand, Format, Tables};
+    use crate::db::backup::Table;
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn parse_commands() {
+        assert!(BackupCommand::parse(&args("")).is_none());
+        assert!(BackupCommand::parse(&args("serve")).is_none());
+        assert_eq!(
+            BackupCommand::parse(&args("export all --output backup"))
+                .unwrap()
+                .unwrap(),
+            BackupCommand::Export {
+                tables: Tables::All,
+                format: None,
+                output: Some(PathBuf::from("backup")),
+            }
+        );
+        assert_eq!(
+            BackupCommand::parse(&args(
+                "import quote quotes.csv --map content=quote --book 42 --dry-run"
+            ))
+            .unwrap()
+            .unwrap(),
+            BackupCommand::Import {
+                tables: Tables::One(Table::Quote),
+                input: PathBuf::from("quotes.csv"),
+                format: None,
+                mapping: HashMap::from([("content".to_string(), "quote".to_string())]),
+                book_id: Some(42),
+                renumber: false,
+                dry_run: true,
+            }
+        );
+        assert!(BackupCommand::parse(&args("import quote"))
+            .unwrap()
+            .is_err());
+        assert!(BackupCommand::parse(&args("export nope")).unwrap().is_err());
+        assert!(BackupCommand::parse(&args("export quote --format xml"))
+            .unwrap()
+            .is_err());
+        assert_eq!(Format::parse("CSV"), Some(Format::Csv));
+
+        let import = BackupCommand::parse(&args("import all backup"))
+            .unwrap()
+            .unwrap();
+        assert!(import.writes());
+        let dry_run = BackupCommand::parse(&args("import all backup --dry-run"))
+            .unwrap()
+            .unwrap();
+        assert!(!dry_run.writes());
+    }
+
+    #[test]
+    fn csv_round_trip() {
+        let records: Vec<super::Record> = vec![serde_json::from_str(
+            r#"{"id": 1, "value": "une \"connerie\", sur\ndeux lignes", "author": null}"#,
+        )
+        .unwrap()];
+        let mut csv = Vec::new();
+        super::write_records(&mut csv, Table::Connerie, Format::Csv, &records).unwrap();
+
+        let path = std::env::temp_dir().join("xzibot_csv_round_trip.csv");
+        std::fs::write(&path, csv).unwrap();
+        let read = super::read_records(&path, Format::Csv).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(read.len(), 1);
+        assert_eq!(read[0]["id"], "1");
+        assert_eq!(read[0]["value"], "une \"connerie\", sur\ndeux lignes");
+        assert_eq!(read[0]["author"], "");
+    }
+}
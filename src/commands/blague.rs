@@ -1,8 +1,10 @@
 use crate::commands::SlashCommand;
+use crate::output::CommandResponse;
 use anyhow::Error;
 use serde::Deserialize;
 use serenity::async_trait;
 use serenity::builder::CreateApplicationCommand;
+use serenity::client::Context;
 use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
 
 #[derive(Deserialize)]
@@ -23,8 +25,9 @@ impl SlashCommand for BlagueCommand {
 
     async fn handle(
         &self,
+        _ctx: &Context,
         interaction: &ApplicationCommandInteraction,
-    ) -> Result<Option<String>, Error> {
+    ) -> Result<Option<CommandResponse>, Error> {
         if interaction.data.name != "blague" {
             return Ok(None);
         }
@@ -42,6 +45,6 @@ impl SlashCommand for BlagueCommand {
             lines.push(a);
         }
 
-        Ok(Some(lines.join("\n")))
+        Ok(Some(lines.join("\n").into()))
     }
 }
@@ -1,15 +1,29 @@
+use crate::commands::can_manage_channels;
+use crate::commands::feed::fetch_feed;
+use crate::commands::feed::mark_seen;
+use crate::commands::feed::subscribe;
+use crate::commands::feed::unseen_entries;
+use crate::commands::feed::MessageBuilder;
 use crate::commands::SlashCommand;
-use crate::db::rss::RssFeedEntry;
+use crate::db::feed_subscription::FeedSubscription;
+use crate::output::CommandResponse;
+use anyhow::anyhow;
 use anyhow::Error;
-use feed_rs::model::Entry;
-use feed_rs::parser;
 use serenity::async_trait;
 use serenity::builder::CreateApplicationCommand;
+use serenity::client::Context;
 use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
 use sqlx::MySqlPool;
 
 use std::sync::Arc;
 
+const BUZZ_FEED_URL: &str = "http://feeds.feedburner.com/jeanmarcmorandini/pExM?format=xml";
+const BUZZ_TITLE: &str = "EXCLU!";
+const NO_BUZZ: &str = "Plus d'exclus pour le moment :(";
+const NOT_SUBSCRIBED: &str =
+    "Ce salon ne suit pas encore les exclus, un membre qui peut gérer le salon doit d'abord utiliser /buzz.";
+
+/// the latest entry of a preset feed, the channel is subscribed to it when a member who can manage it uses the command
 pub struct BuzzCommand {
     pub db_pool: Arc<MySqlPool>,
 }
@@ -22,34 +36,62 @@ impl SlashCommand for BuzzCommand {
 
     async fn handle(
         &self,
+        _ctx: &Context,
         interaction: &ApplicationCommandInteraction,
-    ) -> Result<Option<String>, Error> {
+    ) -> Result<Option<CommandResponse>, Error> {
         if interaction.data.name != "buzz" {
             return Ok(None);
         }
 
-        let xml = ureq::get("http://feeds.feedburner.com/jeanmarcmorandini/pExM?format=xml")
-            .call()?
-            .into_string()?;
-        let feed = parser::parse(xml.as_bytes())?;
+        let feed = tokio::task::spawn_blocking(|| fetch_feed(BUZZ_FEED_URL)).await??;
 
-        let mut entry: Option<Entry> = None;
-        for e in feed.entries {
-            if !RssFeedEntry::exists_by_guid(&self.db_pool, &e.id).await? {
-                entry = Some(e);
-                break;
+        let subscription = FeedSubscription::find_by_channel_and_url(
+            &self.db_pool,
+            interaction.channel_id.0,
+            BUZZ_FEED_URL,
+        )
+        .await?;
+        let subscription = match subscription {
+            Some(s) => s,
+            // like /feed, only the members who can manage the channel subscribe it, and without
+            // subscription there is nothing to remember the entries already displayed
+            None if !can_manage_channels(interaction.member.as_ref()) => {
+                return Ok(Some(NOT_SUBSCRIBED.into()));
             }
-        }
+            None => {
+                // first use in this channel, every entry is now seen except the latest one that we display
+                subscribe(&self.db_pool, interaction, BUZZ_FEED_URL, BUZZ_TITLE, &feed).await?;
+                let subscription = FeedSubscription::find_by_channel_and_url(
+                    &self.db_pool,
+                    interaction.channel_id.0,
+                    BUZZ_FEED_URL,
+                )
+                .await?
+                .ok_or_else(|| anyhow!("missing subscription after subscribing"))?;
+                let message = match feed.entries.first() {
+                    Some(e) => MessageBuilder::new(&subscription)?.build(e),
+                    None => None,
+                };
+                return Ok(Some(match message {
+                    Some(m) => m.content.into(),
+                    None => NO_BUZZ.into(),
+                }));
+            }
+        };
 
-        match entry {
-            None => Ok(Some("Plus d'exclus pour le moment :(".to_string())),
-            Some(e) => match (e.title, e.links.get(0).map(|l| &l.href)) {
-                (Some(title), Some(link)) => {
-                    RssFeedEntry::save(&self.db_pool, &e.id).await?;
-                    Ok(Some(format!("**EXCLU!** {} - {}", title.content, link)))
-                }
-                _ => Ok(None),
-            },
+        // latest unseen entry accepted by the filters of the subscription, the rejected ones are skipped
+        let builder = MessageBuilder::new(&subscription)?;
+        for entry in unseen_entries(&self.db_pool, subscription.id, &feed)
+            .await?
+            .iter()
+            .rev()
+        {
+            mark_seen(&self.db_pool, subscription.id, entry).await?;
+            if let Some(message) = builder.build(entry) {
+                return Ok(Some(message.content.into()));
+            }
         }
+
+        Ok(Some(NO_BUZZ.into()))
     }
 }
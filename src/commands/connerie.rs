@@ -1,5 +1,20 @@
+use crate::autocomplete::choice;
+use crate::autocomplete::focused_value;
+use crate::autocomplete::Choice;
+use crate::autocomplete::MAX_CHOICES;
+use crate::commands::find_option;
+use crate::commands::is_guild_admin;
+use crate::commands::markov::seed_words;
+use crate::commands::markov::MarkovModel;
+use crate::commands::NOT_GUILD_ADMIN;
 use crate::db::connerie::Connerie;
+use crate::db::connerie_settings::ConnerieSettings;
+use crate::db::connerie_settings::ReplyMode;
+use crate::interactions::StateStore;
+use crate::output::CommandResponse;
 use crate::utils::extract_url;
+use crate::utils::relevance::RelevanceIndex;
+use crate::utils::text::frequent_words;
 use crate::MessageCommand;
 use crate::SlashCommand;
 use anyhow::anyhow;
@@ -10,20 +25,61 @@ use serenity::async_trait;
 use serenity::builder::CreateApplicationCommand;
 use serenity::client::Context;
 use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::model::application::interaction::autocomplete::AutocompleteInteraction;
 use serenity::model::prelude::command::CommandOptionType;
 use serenity::model::prelude::interaction::application_command::CommandDataOptionValue;
 use serenity::model::prelude::Message;
 use sqlx::MySqlPool;
 use std::sync::Arc;
+use std::time::Duration;
 
 const PROC_PERCENTAGE: u8 = 3;
 const MIN_RAND_TERMS_LENGTH: usize = 4;
+const VOCABULARY_KEY: &str = "vocabulary";
+const RELEVANCE_INDEX_KEY: &str = "relevance";
+/// how long the most frequent words and the relevance index of the corpus are kept before being computed again
+pub const CORPUS_CACHE_TTL: Duration = Duration::from_secs(3600);
 
 pub struct ConnerieCommand {
     pub bot_name: String,
     pub db_pool: Arc<MySqlPool>,
+    pub vocabulary: StateStore<Vec<String>>,
+    pub relevance_index: StateStore<Arc<RelevanceIndex>>,
+    pub markov: Arc<MarkovModel>,
+    /// how passive replies are picked in the guilds which did not choose
+    pub default_reply_mode: ReplyMode,
 }
 impl ConnerieCommand {
+    async fn vocabulary(&self) -> Result<Vec<String>, Error> {
+        if let Some(vocabulary) = self.vocabulary.get(VOCABULARY_KEY) {
+            return Ok(vocabulary);
+        }
+
+        let values = Connerie::values(&self.db_pool).await?;
+        let vocabulary = frequent_words(&values, MIN_RAND_TERMS_LENGTH);
+        self.vocabulary.set(VOCABULARY_KEY, vocabulary.clone());
+        Ok(vocabulary)
+    }
+
+    async fn relevance_index(&self) -> Result<Arc<RelevanceIndex>, Error> {
+        if let Some(index) = self.relevance_index.get(RELEVANCE_INDEX_KEY) {
+            return Ok(index);
+        }
+
+        let values = Connerie::values(&self.db_pool).await?;
+        let index = Arc::new(RelevanceIndex::new(values));
+        self.relevance_index.set(RELEVANCE_INDEX_KEY, index.clone());
+        Ok(index)
+    }
+
+    async fn reply_mode(&self, message: &Message) -> Result<ReplyMode, Error> {
+        let mode = match message.guild_id {
+            Some(guild_id) => ConnerieSettings::find_reply_mode(&self.db_pool, guild_id.0).await?,
+            None => None,
+        };
+        Ok(mode.unwrap_or(self.default_reply_mode))
+    }
+
     async fn should_trigger_save(&self, ctx: &Context, message: &Message) -> Result<bool, Error> {
         let trigger = message.content.chars().count() > 9
             && !message.mention_everyone
@@ -59,13 +115,39 @@ impl ConnerieCommand {
 impl MessageCommand for ConnerieCommand {
     async fn handle(&self, ctx: &Context, message: &Message) -> Result<Option<String>, Error> {
         if self.should_trigger_save(ctx, message).await? {
-            Connerie::insert(&self.db_pool, &message.author.name, &message.content).await?;
+            let id = Connerie::insert(&self.db_pool, &message.author.name, &message.content)
+                .await?
+                .last_insert_id() as i64;
+            // the connerie is stored anyway, the chain must not keep the bot from answering
+            if let Err(e) = self.markov.learn(&self.db_pool, id, &message.content).await {
+                println!("error while learning connerie {} : {}", id, e);
+            }
         }
 
-        if self.should_trigger_say(ctx, message).await? {
-            Connerie::random(&self.db_pool).await
-        } else {
-            Ok(None)
+        if !self.should_trigger_say(ctx, message).await? {
+            return Ok(None);
+        }
+        match self.reply_mode(message).await? {
+            ReplyMode::Random => Connerie::random(&self.db_pool).await,
+            ReplyMode::Relevant => {
+                let index = self.relevance_index().await?;
+                let reply = index
+                    .pick(&message.content, &mut rand::thread_rng())
+                    .map(String::from);
+                match reply {
+                    Some(reply) => Ok(Some(reply)),
+                    None => Connerie::random(&self.db_pool).await,
+                }
+            }
+            ReplyMode::Markov => {
+                // about a word of the message when the chain knows one
+                let seeds = seed_words(&message.content, MIN_RAND_TERMS_LENGTH);
+                let seeds: Vec<&str> = seeds.iter().map(String::as_str).collect();
+                match self.markov.generate(&self.db_pool, &seeds).await? {
+                    Some(sentence) => Ok(Some(sentence)),
+                    None => self.markov.generate(&self.db_pool, &[]).await,
+                }
+            }
         }
     }
 }
@@ -82,13 +164,15 @@ impl SlashCommand for ConnerieCommand {
                     .description("Que chercher ?")
                     .kind(CommandOptionType::String)
                     .required(true)
+                    .set_autocomplete(true)
             });
     }
 
     async fn handle(
         &self,
+        _ctx: &Context,
         interaction: &ApplicationCommandInteraction,
-    ) -> Result<Option<String>, Error> {
+    ) -> Result<Option<CommandResponse>, Error> {
         if interaction.data.name != "rand" {
             return Ok(None);
         }
@@ -96,7 +180,7 @@ impl SlashCommand for ConnerieCommand {
         let option = interaction
             .data
             .options
-            .get(0)
+            .first()
             .ok_or_else(|| anyhow!("missing terms option"))?
             .resolved
             .as_ref()
@@ -108,18 +192,122 @@ impl SlashCommand for ConnerieCommand {
         };
 
         if search_terms.chars().count() < MIN_RAND_TERMS_LENGTH {
-            return Ok(Some(format!(
-                "Requête trop courte, minimum {} caractères",
-                MIN_RAND_TERMS_LENGTH
-            )));
+            return Ok(Some(
+                format!(
+                    "Requête trop courte, minimum {} caractères",
+                    MIN_RAND_TERMS_LENGTH
+                )
+                .into(),
+            ));
         }
 
         let tokens: Vec<&str> = search_terms.split(' ').collect();
         let connerie = Connerie::search(&self.db_pool, &tokens[..]).await?;
         match connerie {
-            None => Ok(Some("Pas de résultat".to_string())),
-            Some(c) => Ok(Some(c)),
+            None => Ok(Some("Pas de résultat".into())),
+            Some(c) => Ok(Some(c.into())),
+        }
+    }
+
+    async fn autocomplete(
+        &self,
+        interaction: &AutocompleteInteraction,
+    ) -> Result<Option<Vec<Choice>>, Error> {
+        if interaction.data.name != "rand" {
+            return Ok(None);
+        }
+
+        // complete the last word being typed with the most frequent words of the corpus
+        let typed = focused_value(&interaction.data.options).unwrap_or_default();
+        let (start, last_word) = match typed.rfind(' ') {
+            Some(i) => (&typed[..=i], &typed[i + 1..]),
+            None => ("", typed),
+        };
+        let last_word = last_word.to_lowercase();
+
+        let choices = self
+            .vocabulary()
+            .await?
+            .iter()
+            .filter(|w| w.starts_with(&last_word))
+            .take(MAX_CHOICES)
+            .map(|w| {
+                let value = format!("{}{}", start, w);
+                choice(&value, &value)
+            })
+            .collect();
+        Ok(Some(choices))
+    }
+}
+
+/// chooses how the passive replies are picked in a guild
+pub struct ConnerieModeCommand {
+    pub db_pool: Arc<MySqlPool>,
+    pub default_reply_mode: ReplyMode,
+}
+
+#[async_trait]
+impl SlashCommand for ConnerieModeCommand {
+    fn register(&self, command: &mut CreateApplicationCommand) {
+        command
+            .name("connerie")
+            .description("Réglage des réponses spontanées")
+            .dm_permission(false)
+            .create_option(|option| {
+                option
+                    .name("mode")
+                    .description("comment choisir les réponses, le réglage actuel sinon")
+                    .kind(CommandOptionType::String);
+                for mode in ReplyMode::ALL {
+                    option.add_string_choice(mode.label(), mode.as_str());
+                }
+                option
+            });
+    }
+
+    async fn handle(
+        &self,
+        _ctx: &Context,
+        interaction: &ApplicationCommandInteraction,
+    ) -> Result<Option<CommandResponse>, Error> {
+        if interaction.data.name != "connerie" {
+            return Ok(None);
+        }
+
+        let guild_id = interaction
+            .guild_id
+            .ok_or_else(|| anyhow!("connerie settings are only available in a guild"))?
+            .0;
+        let mode = match find_option(&interaction.data.options, "mode") {
+            Some(CommandDataOptionValue::String(s)) => {
+                Some(ReplyMode::parse(s).ok_or_else(|| anyhow!("unknown reply mode {}", s))?)
+            }
+            _ => None,
+        };
+
+        let mode = match mode {
+            Some(mode) => mode,
+            None => {
+                let mode = ConnerieSettings::find_reply_mode(&self.db_pool, guild_id)
+                    .await?
+                    .unwrap_or(self.default_reply_mode);
+                return Ok(Some(
+                    format!("Les réponses spontanées sont choisies {}.", mode.label()).into(),
+                ));
+            }
+        };
+        if !is_guild_admin(interaction.member.as_ref()) {
+            return Ok(Some(NOT_GUILD_ADMIN.into()));
         }
+        ConnerieSettings::set_reply_mode(&self.db_pool, guild_id, mode, interaction.user.id.0)
+            .await?;
+        Ok(Some(
+            format!(
+                "Les réponses spontanées seront désormais choisies {}.",
+                mode.label()
+            )
+            .into(),
+        ))
     }
 }
 
@@ -1,8 +1,10 @@
 use crate::commands::SlashCommand;
+use crate::output::CommandResponse;
 use anyhow::Error;
 use rand::Rng;
 use serenity::async_trait;
 use serenity::builder::CreateApplicationCommand;
+use serenity::client::Context;
 use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
 
 pub struct EightBallCommand {}
@@ -15,8 +17,9 @@ impl SlashCommand for EightBallCommand {
 
     async fn handle(
         &self,
+        _ctx: &Context,
         interaction: &ApplicationCommandInteraction,
-    ) -> Result<Option<String>, Error> {
+    ) -> Result<Option<CommandResponse>, Error> {
         if interaction.data.name != "8ball" {
             return Ok(None);
         }
@@ -45,6 +48,6 @@ impl SlashCommand for EightBallCommand {
         ];
 
         let i = rand::thread_rng().gen_range(0..answers.len());
-        Ok(Some(format!("🎱 {} 🎱", answers[i])))
+        Ok(Some(format!("🎱 {} 🎱", answers[i]).into()))
     }
 }
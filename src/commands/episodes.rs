@@ -1,39 +1,290 @@
+use crate::autocomplete::choice;
+use crate::autocomplete::focused_value;
+use crate::autocomplete::Choice;
+use crate::commands::find_option;
 use crate::commands::SlashCommand;
+use crate::interactions::build_custom_id;
+use crate::interactions::parse_custom_id;
+use crate::output::reply_ephemeral;
+use crate::output::split_content;
+use crate::output::truncate;
+use crate::output::CommandResponse;
+use crate::output::MESSAGE_MAX_LENGTH;
+use crate::utils::text::strip_html;
+use crate::utils::tvmaze::build_episode_line;
+use crate::utils::tvmaze::fetch_show;
+use crate::utils::tvmaze::find_previous_and_next_episodes;
+use crate::utils::tvmaze::lookup_show;
+use crate::utils::tvmaze::search_shows;
+use crate::utils::tvmaze::ShowLookup;
+use crate::utils::tvmaze::TVMazeShow;
 use anyhow::anyhow;
 use anyhow::Error;
-use chrono::{DateTime, Utc};
-use chrono_humanize::HumanTime;
-use serde::Deserialize;
 use serenity::async_trait;
 use serenity::builder::CreateApplicationCommand;
+use serenity::builder::CreateComponents;
+use serenity::client::Context;
 use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::model::application::interaction::autocomplete::AutocompleteInteraction;
+use serenity::model::application::interaction::message_component::MessageComponentInteraction;
+use serenity::model::id::UserId;
 use serenity::model::prelude::command::CommandOptionType;
 use serenity::model::prelude::interaction::application_command::CommandDataOptionValue;
 
-#[derive(Deserialize)]
-struct TVMazeSearch {
-    name: String,
-    externals: TVMazeExternals,
-    #[serde(rename = "_embedded")]
-    embedded: TVMazeEmbedded,
+const MIN_AUTOCOMPLETE_LENGTH: usize = 2;
+const NEXT_PREFIX: &str = "next";
+const SHOW_PREFIX: &str = "show";
+/// Discord refuses select menus with more options than this
+const MAX_SELECT_OPTIONS: usize = 25;
+const SELECT_LABEL_MAX_LENGTH: usize = 100;
+
+/// what to display about a show, kept in the custom id of the select menu while the show is chosen
+enum ShowAction {
+    Next,
+    Info,
+    Season(u32),
+    Episode(u32, u32),
+}
+
+impl ShowAction {
+    fn to_args(&self) -> Vec<String> {
+        match self {
+            ShowAction::Next => vec!["next".to_string()],
+            ShowAction::Info => vec!["info".to_string()],
+            ShowAction::Season(season) => vec!["season".to_string(), season.to_string()],
+            ShowAction::Episode(season, episode) => vec![
+                "episode".to_string(),
+                season.to_string(),
+                episode.to_string(),
+            ],
+        }
+    }
+
+    fn from_args(args: &[&str]) -> Option<ShowAction> {
+        match args {
+            ["next"] => Some(ShowAction::Next),
+            ["info"] => Some(ShowAction::Info),
+            ["season", season] => Some(ShowAction::Season(season.parse().ok()?)),
+            ["episode", season, episode] => Some(ShowAction::Episode(
+                season.parse().ok()?,
+                episode.parse().ok()?,
+            )),
+            _ => None,
+        }
+    }
+}
+
+fn title_line(show: &TVMazeShow) -> String {
+    match &show.externals.imdb {
+        None => show.name.clone(),
+        Some(imdb) => format!("{} <http://www.imdb.com/title/{}>", show.name, imdb),
+    }
+}
+
+fn next_response(show: &TVMazeShow) -> CommandResponse {
+    let (previous, next) = find_previous_and_next_episodes(show.episodes());
+    let lines = [
+        title_line(show),
+        match next {
+            None => "Next Episode: N/A".to_string(),
+            Some(ep) => format!("Next Episode: {}", build_episode_line(ep)),
+        },
+        match previous {
+            None => "Previous Episode: N/A".to_string(),
+            Some(ep) => format!("Previous Episode: {}", build_episode_line(ep)),
+        },
+    ];
+    lines.join("\n").into()
+}
+
+fn info_response(show: &TVMazeShow) -> CommandResponse {
+    let mut lines = vec![title_line(show)];
+    if let Some(channel) = show.channel() {
+        match &channel.country {
+            Some(country) => lines.push(format!("Chaîne : {} ({})", channel.name, country.name)),
+            None => lines.push(format!("Chaîne : {}", channel.name)),
+        }
+    }
+    if let Some(status) = &show.status {
+        lines.push(format!("Statut : {}", status));
+    }
+    if let Some(schedule) = show.schedule.as_ref().filter(|s| !s.days.is_empty()) {
+        let mut line = format!("Diffusion : {}", schedule.days.join(", "));
+        if !schedule.time.is_empty() {
+            line.push_str(&format!(" à {}", schedule.time));
+        }
+        lines.push(line);
+    }
+    if let Some(premiered) = &show.premiered {
+        lines.push(format!("Première diffusion : {}", premiered));
+    }
+    if let Some(summary) = &show.summary {
+        lines.push(String::new());
+        lines.push(strip_html(summary));
+    }
+    lines.join("\n").into()
+}
+
+fn season_response(show: &TVMazeShow, season: u32) -> CommandResponse {
+    let episodes: Vec<String> = show
+        .episodes()
+        .iter()
+        .filter(|e| e.season == season)
+        .map(build_episode_line)
+        .collect();
+    if episodes.is_empty() {
+        return format!("Pas de saison {} pour {}", season, show.name).into();
+    }
+
+    let content = format!(
+        "**{}** saison {}\n{}",
+        show.name,
+        season,
+        episodes.join("\n")
+    );
+    CommandResponse::Pages(split_content(&content, MESSAGE_MAX_LENGTH))
+}
+
+fn episode_response(show: &TVMazeShow, season: u32, number: u32) -> CommandResponse {
+    let episode = show
+        .episodes()
+        .iter()
+        .find(|e| e.season == season && e.number == Some(number));
+    match episode {
+        None => format!(
+            "Pas d'épisode S{:02}E{:02} pour {}",
+            season, number, show.name
+        )
+        .into(),
+        Some(episode) => {
+            let summary = episode
+                .summary
+                .as_deref()
+                .map(strip_html)
+                .filter(|s| !s.is_empty())
+                .unwrap_or_else(|| "Pas de résumé.".to_string());
+            format!(
+                "**{}** {}\n\n{}",
+                show.name,
+                build_episode_line(episode),
+                summary
+            )
+            .into()
+        }
+    }
 }
 
-#[derive(Deserialize)]
-struct TVMazeExternals {
-    imdb: Option<String>,
+fn action_response(show: &TVMazeShow, action: &ShowAction) -> CommandResponse {
+    match action {
+        ShowAction::Next => next_response(show),
+        ShowAction::Info => info_response(show),
+        ShowAction::Season(season) => season_response(show, *season),
+        ShowAction::Episode(season, number) => episode_response(show, *season, *number),
+    }
 }
 
-#[derive(Deserialize)]
-struct TVMazeEmbedded {
-    episodes: Vec<TVMazeEpisode>,
+/**
+ * a select menu to choose between the shows matching a search
+ */
+fn select_show(
+    prefix: &str,
+    owner: UserId,
+    action: &ShowAction,
+    shows: &[TVMazeShow],
+) -> CommandResponse {
+    let owner = owner.0.to_string();
+    let mut args = vec![owner.as_str()];
+    let action_args = action.to_args();
+    args.extend(action_args.iter().map(|a| a.as_str()));
+
+    let mut components = CreateComponents::default();
+    components.create_action_row(|row| {
+        row.create_select_menu(|menu| {
+            menu.custom_id(build_custom_id(prefix, &args))
+                .placeholder("Choisir la série")
+                .options(|options| {
+                    for show in shows.iter().take(MAX_SELECT_OPTIONS) {
+                        options.create_option(|option| {
+                            option
+                                .label(truncate(&show.label(), SELECT_LABEL_MAX_LENGTH))
+                                .value(show.id)
+                        });
+                    }
+                    options
+                })
+        })
+    });
+    CommandResponse::Components("Plusieurs séries correspondent :".to_string(), components)
 }
 
-#[derive(Deserialize)]
-struct TVMazeEpisode {
-    name: String,
-    season: u32,
-    number: u32,
-    airstamp: Option<DateTime<Utc>>,
+async fn lookup_response(
+    prefix: &str,
+    owner: UserId,
+    query: &str,
+    action: &ShowAction,
+) -> Result<CommandResponse, Error> {
+    // TVMaze is queried with blocking requests
+    let query = query.to_string();
+    let response = match tokio::task::spawn_blocking(move || lookup_show(&query)).await?? {
+        ShowLookup::NotFound => "Pas de résultat".into(),
+        ShowLookup::Found(show) => action_response(&show, action),
+        ShowLookup::Ambiguous(shows) => select_show(prefix, owner, action, &shows),
+    };
+    Ok(response)
+}
+
+/**
+ * display the show chosen in the select menu of an ambiguous search
+ */
+async fn handle_show_selection(
+    ctx: &Context,
+    interaction: &MessageComponentInteraction,
+) -> Result<Option<CommandResponse>, Error> {
+    let (_, args) = parse_custom_id(&interaction.data.custom_id);
+    let (owner, action) = match args.split_first() {
+        Some((owner, action)) => (*owner, ShowAction::from_args(action)),
+        None => return Err(anyhow!("missing owner in show custom id")),
+    };
+    let action = action.ok_or_else(|| anyhow!("unknown show action"))?;
+
+    if owner != interaction.user.id.0.to_string() {
+        reply_ephemeral(
+            ctx,
+            interaction,
+            "Seul l'auteur de la commande peut choisir la série.",
+        )
+        .await?;
+        return Ok(None);
+    }
+
+    let show_id = interaction
+        .data
+        .values
+        .first()
+        .and_then(|v| v.parse::<u64>().ok())
+        .ok_or_else(|| anyhow!("missing selected show"))?;
+    let show = tokio::task::spawn_blocking(move || fetch_show(show_id)).await??;
+    Ok(Some(action_response(&show, &action)))
+}
+
+/**
+ * suggestions for a show option, the value is the show id so that homonyms can be told apart
+ */
+async fn suggest_shows(interaction: &AutocompleteInteraction) -> Result<Vec<Choice>, Error> {
+    let typed = focused_value(&interaction.data.options)
+        .unwrap_or_default()
+        .trim();
+    if typed.chars().count() < MIN_AUTOCOMPLETE_LENGTH {
+        return Ok(Vec::new());
+    }
+
+    let typed = typed.to_string();
+    let choices = tokio::task::spawn_blocking(move || search_shows(&typed))
+        .await??
+        .iter()
+        .map(|s| choice(&s.label(), &format!("#{}", s.id)))
+        .collect();
+    Ok(choices)
 }
 
 pub struct EpisodesCommand {}
@@ -50,13 +301,19 @@ impl SlashCommand for EpisodesCommand {
                     .description("Le nom de la série")
                     .kind(CommandOptionType::String)
                     .required(true)
+                    .set_autocomplete(true)
             });
     }
 
+    fn custom_id_prefixes(&self) -> &'static [&'static str] {
+        &[NEXT_PREFIX]
+    }
+
     async fn handle(
         &self,
+        _ctx: &Context,
         interaction: &ApplicationCommandInteraction,
-    ) -> Result<Option<String>, Error> {
+    ) -> Result<Option<CommandResponse>, Error> {
         if interaction.data.name != "next" {
             return Ok(None);
         }
@@ -64,7 +321,7 @@ impl SlashCommand for EpisodesCommand {
         let option = interaction
             .data
             .options
-            .get(0)
+            .first()
             .ok_or_else(|| anyhow!("missing tv_show option"))?
             .resolved
             .as_ref()
@@ -75,79 +332,183 @@ impl SlashCommand for EpisodesCommand {
             _ => return Err(anyhow!("wrong value type for tv_show option")),
         };
 
-        let url = format!(
-            "https://api.tvmaze.com/singlesearch/shows?q={}&embed=episodes",
-            search_terms
-        );
-
-        let mut lines: Vec<String> = Vec::new();
-        match ureq::get(&url).call() {
-            Ok(r) => {
-                let search_result = r.into_json::<TVMazeSearch>()?;
-                match search_result.externals.imdb {
-                    None => lines.push(search_result.name),
-                    Some(imdb) => lines.push(format!(
-                        "{} <http://www.imdb.com/title/{}>",
-                        search_result.name, imdb
-                    )),
-                }
-
-                let (previous, next) =
-                    find_previous_and_next_episodes(&search_result.embedded.episodes);
-
-                lines.push(match next {
-                    None => "Next Episode: N/A".to_string(),
-                    Some(ep) => format!("Next Episode: {}", build_episode_line(ep)),
-                });
-                lines.push(match previous {
-                    None => "Previous Episode: N/A".to_string(),
-                    Some(ep) => format!("Previous Episode: {}", build_episode_line(ep)),
-                });
-            }
-            Err(e) => {
-                if let ureq::Error::Status(404, _) = e {
-                    lines.push("Pas de résultat".to_string());
-                } else {
-                    return Err(anyhow!("{}", e));
-                }
-            }
+        let response = lookup_response(
+            NEXT_PREFIX,
+            interaction.user.id,
+            search_terms,
+            &ShowAction::Next,
+        )
+        .await?;
+        Ok(Some(response))
+    }
+
+    async fn autocomplete(
+        &self,
+        interaction: &AutocompleteInteraction,
+    ) -> Result<Option<Vec<Choice>>, Error> {
+        if interaction.data.name != "next" {
+            return Ok(None);
         }
+        suggest_shows(interaction).await.map(Some)
+    }
 
-        Ok(Some(lines.join("\n")))
+    async fn handle_component(
+        &self,
+        ctx: &Context,
+        interaction: &MessageComponentInteraction,
+    ) -> Result<Option<CommandResponse>, Error> {
+        handle_show_selection(ctx, interaction).await
     }
 }
 
-fn find_previous_and_next_episodes(
-    episodes: &[TVMazeEpisode],
-) -> (Option<&TVMazeEpisode>, Option<&TVMazeEpisode>) {
-    let mut previous: Option<&TVMazeEpisode> = None;
-    let mut next: Option<&TVMazeEpisode> = None;
-
-    let now = Utc::now();
-    for episode in episodes {
-        if let Some(d) = episode.airstamp {
-            if d < now {
-                previous = Some(episode);
-            } else if next.is_none() {
-                next = Some(episode);
+/// details about a show: general information, the episodes of a season or a single episode
+pub struct ShowCommand {}
+
+#[async_trait]
+impl SlashCommand for ShowCommand {
+    fn register(&self, command: &mut CreateApplicationCommand) {
+        command
+            .name("show")
+            .description("Informations sur une série")
+            .create_option(|option| {
+                option
+                    .name("info")
+                    .description("chaîne, statut et horaires de diffusion")
+                    .kind(CommandOptionType::SubCommand)
+                    .create_sub_option(|sub_option| {
+                        sub_option
+                            .name("tv_show")
+                            .description("le nom de la série")
+                            .kind(CommandOptionType::String)
+                            .required(true)
+                            .set_autocomplete(true)
+                    })
+            })
+            .create_option(|option| {
+                option
+                    .name("season")
+                    .description("les épisodes d'une saison")
+                    .kind(CommandOptionType::SubCommand)
+                    .create_sub_option(|sub_option| {
+                        sub_option
+                            .name("tv_show")
+                            .description("le nom de la série")
+                            .kind(CommandOptionType::String)
+                            .required(true)
+                            .set_autocomplete(true)
+                    })
+                    .create_sub_option(|sub_option| {
+                        sub_option
+                            .name("season")
+                            .description("numéro de la saison")
+                            .kind(CommandOptionType::Integer)
+                            .min_int_value(0)
+                            .required(true)
+                    })
+            })
+            .create_option(|option| {
+                option
+                    .name("episode")
+                    .description("le résumé d'un épisode")
+                    .kind(CommandOptionType::SubCommand)
+                    .create_sub_option(|sub_option| {
+                        sub_option
+                            .name("tv_show")
+                            .description("le nom de la série")
+                            .kind(CommandOptionType::String)
+                            .required(true)
+                            .set_autocomplete(true)
+                    })
+                    .create_sub_option(|sub_option| {
+                        sub_option
+                            .name("season")
+                            .description("numéro de la saison")
+                            .kind(CommandOptionType::Integer)
+                            .min_int_value(0)
+                            .required(true)
+                    })
+                    .create_sub_option(|sub_option| {
+                        sub_option
+                            .name("episode")
+                            .description("numéro de l'épisode")
+                            .kind(CommandOptionType::Integer)
+                            .min_int_value(1)
+                            .required(true)
+                    })
+            });
+    }
+
+    fn custom_id_prefixes(&self) -> &'static [&'static str] {
+        &[SHOW_PREFIX]
+    }
+
+    async fn handle(
+        &self,
+        _ctx: &Context,
+        interaction: &ApplicationCommandInteraction,
+    ) -> Result<Option<CommandResponse>, Error> {
+        if interaction.data.name != "show" {
+            return Ok(None);
+        }
+
+        let command = interaction
+            .data
+            .options
+            .first()
+            .ok_or_else(|| anyhow!("missing command option"))?;
+        let query = match find_option(&command.options, "tv_show") {
+            Some(CommandDataOptionValue::String(q)) => q,
+            _ => return Err(anyhow!("missing tv_show option")),
+        };
+        let number = |name: &str| match find_option(&command.options, name) {
+            Some(CommandDataOptionValue::Integer(i)) => {
+                u32::try_from(*i).map_err(|_| anyhow!("invalid {} option", name))
             }
+            _ => Err(anyhow!("missing {} option", name)),
+        };
+
+        let action = match command.name.as_str() {
+            "info" => ShowAction::Info,
+            "season" => ShowAction::Season(number("season")?),
+            "episode" => ShowAction::Episode(number("season")?, number("episode")?),
+            e => return Err(anyhow!("unknown command {}", e)),
+        };
+
+        let response = lookup_response(SHOW_PREFIX, interaction.user.id, query, &action).await?;
+        Ok(Some(response))
+    }
+
+    async fn autocomplete(
+        &self,
+        interaction: &AutocompleteInteraction,
+    ) -> Result<Option<Vec<Choice>>, Error> {
+        if interaction.data.name != "show" {
+            return Ok(None);
         }
+        suggest_shows(interaction).await.map(Some)
     }
 
-    (previous, next)
+    async fn handle_component(
+        &self,
+        ctx: &Context,
+        interaction: &MessageComponentInteraction,
+    ) -> Result<Option<CommandResponse>, Error> {
+        handle_show_selection(ctx, interaction).await
+    }
 }
 
-fn build_episode_line(episode: &TVMazeEpisode) -> String {
-    let episode_id = format!("S{:02}E{:02}", episode.season, episode.number);
-    match episode.airstamp {
-        None => format!("{} - {} (?)", episode_id, episode.name),
-        Some(date) => {
-            let formatted_airdate = date.format("%Y-%m-%d %H:%M").to_string();
-            let human_airdate = format!("{}", HumanTime::from(date));
-            format!(
-                "{} - {} ({}, {})",
-                episode_id, episode.name, formatted_airdate, human_airdate
-            )
-        }
+#[cfg(test)]
+mod tests {
+    use super::ShowAction;
+
+    #[test]
+    fn show_action_round_trip() {
+        let args = ShowAction::Episode(2, 5).to_args();
+        let args: Vec<&str> = args.iter().map(|a| a.as_str()).collect();
+        assert!(matches!(
+            ShowAction::from_args(&args),
+            Some(ShowAction::Episode(2, 5))
+        ));
+        assert!(ShowAction::from_args(&["season", "x"]).is_none());
     }
 }
@@ -0,0 +1,559 @@
+use crate::commands::find_option;
+use crate::commands::SlashCommand;
+use crate::db::feed_subscription::FeedSubscription;
+use crate::db::rss::RssFeedEntry;
+use crate::output::CommandResponse;
+use crate::scheduler::Job;
+use crate::utils::feed::accepts;
+use crate::utils::feed::entry_image;
+use crate::utils::feed::normalize_rules;
+use crate::utils::feed::parse_rules;
+use crate::utils::feed::render_template;
+use crate::utils::feed::FilterRule;
+use crate::utils::feed::DEFAULT_TEMPLATE;
+use crate::utils::net::public_agent;
+use anyhow::anyhow;
+use anyhow::Error;
+use chrono::Duration;
+use chrono::Utc;
+use feed_rs::model::Entry;
+use feed_rs::model::Feed;
+use feed_rs::parser;
+use serenity::async_trait;
+use serenity::builder::CreateApplicationCommand;
+use serenity::client::Context;
+use serenity::http::Http;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::model::id::ChannelId;
+use serenity::model::prelude::command::CommandOptionType;
+use serenity::model::prelude::interaction::application_command::CommandDataOption;
+use serenity::model::prelude::interaction::application_command::CommandDataOptionValue;
+use serenity::model::Permissions;
+use sqlx::MySqlPool;
+use std::collections::HashMap;
+use std::sync::Arc;
+use url::Url;
+
+pub const FEED_POLL_JOB_KIND: &str = "feed_poll";
+/// subscribed feeds are polled every five minutes
+pub const FEED_POLL_SCHEDULE: &str = "0 */5 * * * *";
+/// seen entries are forgotten after this many days, once they have left the feed
+const SEEN_ENTRY_RETENTION_DAYS: i64 = 30;
+
+/**
+ * download and parse an RSS, Atom or JSON feed, only from a public http or https address
+ */
+pub fn fetch_feed(url: &str) -> Result<Feed, Error> {
+    let parsed = Url::parse(url)?;
+    if parsed.scheme() != "http" && parsed.scheme() != "https" {
+        return Err(anyhow!("unsupported scheme {}", parsed.scheme()));
+    }
+    // the resolver also applies to the redirections
+    let xml = public_agent().get(url).call()?.into_string()?;
+    let feed = parser::parse(xml.as_bytes())?;
+    Ok(feed)
+}
+
+/// typed instead of rules or a format to go back to the default
+const CLEAR_VALUE: &str = "-";
+
+/// message posted for a new entry of a subscribed feed
+pub struct FeedMessage {
+    pub content: String,
+    pub image: Option<String>,
+}
+
+/// builds the messages of a subscription, its filter rules being compiled once for all the entries
+pub struct MessageBuilder<'a> {
+    subscription: &'a FeedSubscription,
+    include: Vec<FilterRule>,
+    exclude: Vec<FilterRule>,
+}
+
+impl<'a> MessageBuilder<'a> {
+    pub fn new(subscription: &'a FeedSubscription) -> Result<MessageBuilder<'a>, Error> {
+        Ok(MessageBuilder {
+            subscription,
+            include: parse_rules(subscription.include_filter.as_deref().unwrap_or_default())?,
+            exclude: parse_rules(subscription.exclude_filter.as_deref().unwrap_or_default())?,
+        })
+    }
+
+    /**
+     * message for an entry of the feed, None if the filters of the subscription reject it
+     */
+    pub fn build(&self, entry: &Entry) -> Option<FeedMessage> {
+        if !accepts(entry, &self.include, &self.exclude) {
+            return None;
+        }
+
+        let template = self
+            .subscription
+            .template
+            .as_deref()
+            .unwrap_or(DEFAULT_TEMPLATE);
+        render_template(template, &self.subscription.title, entry).map(|content| FeedMessage {
+            content,
+            image: entry_image(entry),
+        })
+    }
+}
+
+fn describe_rules(rules: &Option<String>) -> String {
+    match rules {
+        None => "aucun".to_string(),
+        Some(r) => r.split('\n').collect::<Vec<&str>>().join(", "),
+    }
+}
+
+/**
+ * subscribe a channel to a feed, the entries currently in the feed are considered as already seen
+ */
+pub async fn subscribe(
+    pool: &MySqlPool,
+    interaction: &ApplicationCommandInteraction,
+    url: &str,
+    title: &str,
+    feed: &Feed,
+) -> Result<i64, Error> {
+    let subscription_id = FeedSubscription::insert(
+        pool,
+        interaction.guild_id.map(|g| g.0),
+        interaction.channel_id.0,
+        url,
+        title,
+        interaction.user.id.0,
+    )
+    .await?;
+
+    for entry in &feed.entries {
+        mark_seen(pool, subscription_id, entry).await?;
+    }
+    Ok(subscription_id)
+}
+
+/**
+ * remember that an entry was seen by a subscription
+ */
+pub async fn mark_seen(pool: &MySqlPool, subscription_id: i64, entry: &Entry) -> Result<(), Error> {
+    RssFeedEntry::save(
+        pool,
+        subscription_id,
+        &entry.id,
+        entry.published.or(entry.updated),
+    )
+    .await?;
+    Ok(())
+}
+
+/**
+ * entries of the feed not seen yet by the subscription, oldest first
+ */
+pub async fn unseen_entries<'a>(
+    pool: &MySqlPool,
+    subscription_id: i64,
+    feed: &'a Feed,
+) -> Result<Vec<&'a Entry>, Error> {
+    let guids: Vec<&str> = feed.entries.iter().map(|e| e.id.as_str()).collect();
+    let seen = RssFeedEntry::find_seen_guids(pool, subscription_id, &guids).await?;
+    Ok(feed
+        .entries
+        .iter()
+        .rev()
+        .filter(|e| !seen.contains(&e.id))
+        .collect())
+}
+
+pub struct FeedCommand {
+    pub db_pool: Arc<MySqlPool>,
+}
+
+impl FeedCommand {
+    async fn trigger_add(
+        &self,
+        interaction: &ApplicationCommandInteraction,
+        command: &CommandDataOption,
+    ) -> Result<Option<CommandResponse>, Error> {
+        let option = command
+            .options
+            .first()
+            .ok_or_else(|| anyhow!("missing command sub option"))?
+            .resolved
+            .as_ref()
+            .ok_or_else(|| anyhow!("missing command sub option value"))?;
+
+        let url = match option {
+            CommandDataOptionValue::String(s) => s.trim(),
+            _ => return Err(anyhow!("wrong value type for command sub option")),
+        };
+
+        if FeedSubscription::find_by_channel_and_url(&self.db_pool, interaction.channel_id.0, url)
+            .await?
+            .is_some()
+        {
+            return Ok(Some("Ce salon est déjà abonné à ce flux.".into()));
+        }
+
+        let owned_url = url.to_string();
+        let feed = match tokio::task::spawn_blocking(move || fetch_feed(&owned_url)).await? {
+            Ok(feed) => feed,
+            Err(e) => {
+                println!("error while fetching feed {} : {}", url, e);
+                return Ok(Some("Impossible de lire ce flux.".into()));
+            }
+        };
+        let title = feed
+            .title
+            .as_ref()
+            .map(|t| t.content.clone())
+            .unwrap_or_else(|| url.to_string());
+
+        let id = subscribe(&self.db_pool, interaction, url, &title, &feed).await?;
+        Ok(Some(
+            format!(
+                "Abonnement {} ajouté : {}. Les nouveaux articles seront publiés dans ce salon.",
+                id, title
+            )
+            .into(),
+        ))
+    }
+
+    async fn trigger_remove(
+        &self,
+        interaction: &ApplicationCommandInteraction,
+        command: &CommandDataOption,
+    ) -> Result<Option<CommandResponse>, Error> {
+        let option = command
+            .options
+            .first()
+            .ok_or_else(|| anyhow!("missing command sub option"))?
+            .resolved
+            .as_ref()
+            .ok_or_else(|| anyhow!("missing command sub option value"))?;
+
+        let id = match option {
+            CommandDataOptionValue::Integer(i) => *i,
+            _ => return Err(anyhow!("wrong value type for command sub option")),
+        };
+
+        let result = FeedSubscription::delete(&self.db_pool, interaction.channel_id.0, id).await?;
+        if result.rows_affected() == 0 {
+            return Ok(Some(
+                "Pas d'abonnement avec cet identifiant dans ce salon.".into(),
+            ));
+        }
+
+        RssFeedEntry::delete_by_subscription(&self.db_pool, id).await?;
+        Ok(Some(format!("Abonnement {} supprimé.", id).into()))
+    }
+
+    async fn find_subscription(
+        &self,
+        interaction: &ApplicationCommandInteraction,
+        command: &CommandDataOption,
+    ) -> Result<Option<FeedSubscription>, Error> {
+        let id = match find_option(&command.options, "id") {
+            Some(CommandDataOptionValue::Integer(i)) => *i,
+            _ => return Err(anyhow!("missing id option")),
+        };
+        FeedSubscription::find_by_channel_and_id(&self.db_pool, interaction.channel_id.0, id).await
+    }
+
+    async fn trigger_filter(
+        &self,
+        interaction: &ApplicationCommandInteraction,
+        command: &CommandDataOption,
+    ) -> Result<Option<CommandResponse>, Error> {
+        let subscription = match self.find_subscription(interaction, command).await? {
+            Some(s) => s,
+            None => {
+                return Ok(Some(
+                    "Pas d'abonnement avec cet identifiant dans ce salon.".into(),
+                ))
+            }
+        };
+
+        let mut filters = [subscription.include_filter, subscription.exclude_filter];
+        for (filter, name) in filters.iter_mut().zip(["include", "exclude"]) {
+            match find_option(&command.options, name) {
+                Some(CommandDataOptionValue::String(s)) if s.trim() == CLEAR_VALUE => {
+                    *filter = None
+                }
+                Some(CommandDataOptionValue::String(s)) => match normalize_rules(s) {
+                    Ok(rules) if rules.is_empty() => *filter = None,
+                    Ok(rules) => *filter = Some(rules),
+                    Err(e) => return Ok(Some(format!("Règle invalide : {}", e).into())),
+                },
+                _ => {}
+            }
+        }
+        let [include_filter, exclude_filter] = filters;
+
+        FeedSubscription::update_filters(
+            &self.db_pool,
+            subscription.id,
+            include_filter.as_deref(),
+            exclude_filter.as_deref(),
+        )
+        .await?;
+        Ok(Some(
+            format!(
+                "Filtres de l'abonnement {} :\ninclure : {}\nexclure : {}",
+                subscription.id,
+                describe_rules(&include_filter),
+                describe_rules(&exclude_filter)
+            )
+            .into(),
+        ))
+    }
+
+    async fn trigger_template(
+        &self,
+        interaction: &ApplicationCommandInteraction,
+        command: &CommandDataOption,
+    ) -> Result<Option<CommandResponse>, Error> {
+        let subscription = match self.find_subscription(interaction, command).await? {
+            Some(s) => s,
+            None => {
+                return Ok(Some(
+                    "Pas d'abonnement avec cet identifiant dans ce salon.".into(),
+                ))
+            }
+        };
+
+        let template = match find_option(&command.options, "format") {
+            Some(CommandDataOptionValue::String(s)) if s.trim() == CLEAR_VALUE => None,
+            Some(CommandDataOptionValue::String(s)) => Some(s.trim().to_string()),
+            _ => subscription.template,
+        };
+
+        FeedSubscription::update_template(&self.db_pool, subscription.id, template.as_deref())
+            .await?;
+        Ok(Some(
+            format!(
+                "Format de l'abonnement {} : `{}`",
+                subscription.id,
+                template.as_deref().unwrap_or(DEFAULT_TEMPLATE)
+            )
+            .into(),
+        ))
+    }
+
+    async fn trigger_list(
+        &self,
+        interaction: &ApplicationCommandInteraction,
+    ) -> Result<Option<CommandResponse>, Error> {
+        let subscriptions =
+            FeedSubscription::find_by_channel(&self.db_pool, interaction.channel_id.0).await?;
+        if subscriptions.is_empty() {
+            return Ok(Some("Aucun abonnement dans ce salon.".into()));
+        }
+
+        let lines: Vec<String> = subscriptions
+            .iter()
+            .map(|s| format!("{}. {} <{}>", s.id, s.title, s.url))
+            .collect();
+        Ok(Some(lines.join("\n").into()))
+    }
+}
+
+#[async_trait]
+impl SlashCommand for FeedCommand {
+    fn register(&self, command: &mut CreateApplicationCommand) {
+        command
+            .name("feed")
+            .description("Abonnements aux flux RSS")
+            .default_member_permissions(Permissions::MANAGE_CHANNELS)
+            .dm_permission(false)
+            .create_option(|option| {
+                option
+                    .name("add")
+                    .description("abonner ce salon à un flux RSS, Atom ou JSON")
+                    .kind(CommandOptionType::SubCommand)
+                    .create_sub_option(|sub_option| {
+                        sub_option
+                            .name("url")
+                            .description("adresse du flux")
+                            .kind(CommandOptionType::String)
+                            .required(true)
+                    })
+            })
+            .create_option(|option| {
+                option
+                    .name("remove")
+                    .description("supprimer un abonnement de ce salon")
+                    .kind(CommandOptionType::SubCommand)
+                    .create_sub_option(|sub_option| {
+                        sub_option
+                            .name("id")
+                            .description("identifiant de l'abonnement")
+                            .kind(CommandOptionType::Integer)
+                            .required(true)
+                    })
+            })
+            .create_option(|option| {
+                option
+                    .name("filter")
+                    .description("filtrer les articles publiés par un abonnement")
+                    .kind(CommandOptionType::SubCommand)
+                    .create_sub_option(|sub_option| {
+                        sub_option
+                            .name("id")
+                            .description("identifiant de l'abonnement")
+                            .kind(CommandOptionType::Integer)
+                            .required(true)
+                    })
+                    .create_sub_option(|sub_option| {
+                        sub_option
+                            .name("include")
+                            .description("mots clés ou /regex/ requis, séparés par des virgules (- pour effacer)")
+                            .kind(CommandOptionType::String)
+                    })
+                    .create_sub_option(|sub_option| {
+                        sub_option
+                            .name("exclude")
+                            .description("mots clés ou /regex/ exclus, séparés par des virgules (- pour effacer)")
+                            .kind(CommandOptionType::String)
+                    })
+            })
+            .create_option(|option| {
+                option
+                    .name("template")
+                    .description("format des messages d'un abonnement")
+                    .kind(CommandOptionType::SubCommand)
+                    .create_sub_option(|sub_option| {
+                        sub_option
+                            .name("id")
+                            .description("identifiant de l'abonnement")
+                            .kind(CommandOptionType::Integer)
+                            .required(true)
+                    })
+                    .create_sub_option(|sub_option| {
+                        sub_option
+                            .name("format")
+                            .description("{feed} {title} {link} {author} {published} {summary} (- pour le format par défaut)")
+                            .kind(CommandOptionType::String)
+                    })
+            })
+            .create_option(|option| {
+                option
+                    .name("list")
+                    .description("les abonnements de ce salon")
+                    .kind(CommandOptionType::SubCommand)
+            });
+    }
+
+    async fn handle(
+        &self,
+        _ctx: &Context,
+        interaction: &ApplicationCommandInteraction,
+    ) -> Result<Option<CommandResponse>, Error> {
+        if interaction.data.name != "feed" {
+            return Ok(None);
+        }
+
+        let command = interaction
+            .data
+            .options
+            .first()
+            .ok_or_else(|| anyhow!("missing command option"))?;
+
+        match command.name.as_str() {
+            "add" => self.trigger_add(interaction, command).await,
+            "remove" => self.trigger_remove(interaction, command).await,
+            "filter" => self.trigger_filter(interaction, command).await,
+            "template" => self.trigger_template(interaction, command).await,
+            "list" => self.trigger_list(interaction).await,
+            e => Err(anyhow!("unknown command {}", e)),
+        }
+    }
+}
+
+/// posts the new entries of every subscribed feed
+pub struct FeedPollJob {
+    pub db_pool: Arc<MySqlPool>,
+}
+
+impl FeedPollJob {
+    async fn post_unseen_entries(
+        &self,
+        http: &Http,
+        subscription: &FeedSubscription,
+        feed: &Feed,
+    ) -> Result<(), Error> {
+        let builder = MessageBuilder::new(subscription)?;
+        for entry in unseen_entries(&self.db_pool, subscription.id, feed).await? {
+            mark_seen(&self.db_pool, subscription.id, entry).await?;
+            if let Some(message) = builder.build(entry) {
+                ChannelId(subscription.channel_id)
+                    .send_message(http, |m| {
+                        m.content(&message.content);
+                        if let Some(image) = &message.image {
+                            m.embed(|e| e.image(image));
+                        }
+                        m
+                    })
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn prune_seen_entries(
+        &self,
+        subscription: &FeedSubscription,
+        feed: &Feed,
+    ) -> Result<(), Error> {
+        // an empty feed is more likely a broken download than a feed whose entries were all removed,
+        // forgetting every entry would post them all again once it is back
+        if feed.entries.is_empty() {
+            return Ok(());
+        }
+        // a guid still in the feed must stay known, otherwise its entry would be posted again
+        let guids: Vec<&str> = feed.entries.iter().map(|e| e.id.as_str()).collect();
+        let before = Utc::now() - Duration::days(SEEN_ENTRY_RETENTION_DAYS);
+        RssFeedEntry::prune(&self.db_pool, subscription.id, before, &guids).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Job for FeedPollJob {
+    fn kind(&self) -> &'static str {
+        FEED_POLL_JOB_KIND
+    }
+
+    async fn run(&self, http: &Http, _payload: &str) -> Result<(), Error> {
+        // several channels can subscribe to the same feed, only download it once
+        let mut feeds: HashMap<String, Feed> = HashMap::new();
+        for subscription in FeedSubscription::find_all(&self.db_pool).await? {
+            if !feeds.contains_key(&subscription.url) {
+                let url = subscription.url.clone();
+                match tokio::task::spawn_blocking(move || fetch_feed(&url)).await? {
+                    Ok(feed) => {
+                        feeds.insert(subscription.url.clone(), feed);
+                    }
+                    Err(e) => {
+                        println!("error while fetching feed {} : {}", subscription.url, e);
+                        continue;
+                    }
+                }
+            }
+
+            let feed = &feeds[&subscription.url];
+            if let Err(e) = self.post_unseen_entries(http, &subscription, feed).await {
+                println!(
+                    "error while posting entries of subscription {} : {}",
+                    subscription.id, e
+                );
+            }
+            if let Err(e) = self.prune_seen_entries(&subscription, feed).await {
+                println!(
+                    "error while pruning entries of subscription {} : {}",
+                    subscription.id, e
+                );
+            }
+        }
+        Ok(())
+    }
+}
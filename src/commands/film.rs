@@ -0,0 +1,189 @@
+use crate::commands::SlashCommand;
+use crate::output::truncate;
+use crate::output::CommandResponse;
+use crate::utils::movies::Movie;
+use crate::utils::movies::MovieProvider;
+use crate::utils::movies::ReleaseKind;
+use anyhow::anyhow;
+use anyhow::Error;
+use serenity::async_trait;
+use serenity::builder::CreateApplicationCommand;
+use serenity::builder::CreateEmbed;
+use serenity::client::Context;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::model::prelude::command::CommandOptionType;
+use serenity::model::prelude::interaction::application_command::CommandDataOptionValue;
+use std::sync::Arc;
+
+const SYNOPSIS_MAX_LENGTH: usize = 1000;
+
+fn release_kind_label(kind: ReleaseKind) -> &'static str {
+    match kind {
+        ReleaseKind::Theatrical => "Cinéma",
+        ReleaseKind::Digital => "VOD / streaming",
+        ReleaseKind::Physical => "DVD / Blu-ray",
+        ReleaseKind::Tv => "Télévision",
+    }
+}
+
+/**
+ * embed describing a film
+ */
+fn build_embed(movie: &Movie) -> CreateEmbed {
+    let mut embed = CreateEmbed::default();
+    match movie.year {
+        Some(year) => embed.title(format!("{} ({})", movie.title, year)),
+        None => embed.title(&movie.title),
+    };
+    if let Some(synopsis) = &movie.synopsis {
+        embed.description(truncate(synopsis, SYNOPSIS_MAX_LENGTH));
+    }
+    if let Some(runtime) = movie.runtime {
+        embed.field(
+            "Durée",
+            format!("{} h {:02}", runtime / 60, runtime % 60),
+            true,
+        );
+    }
+    if !movie.genres.is_empty() {
+        embed.field("Genres", movie.genres.join(", "), true);
+    }
+    if let Some(rating) = movie.rating {
+        embed.field(
+            "Note",
+            format!("{:.1}/10 ({} votes)", rating, movie.vote_count.unwrap_or(0)),
+            true,
+        );
+    }
+    if !movie.release_dates.is_empty() {
+        let lines: Vec<String> = movie
+            .release_dates
+            .iter()
+            .map(|r| {
+                format!(
+                    "{} : {}",
+                    release_kind_label(r.kind),
+                    r.date.format("%d/%m/%Y")
+                )
+            })
+            .collect();
+        embed.field("Sorties", lines.join("\n"), false);
+    }
+    if let Some(imdb_id) = &movie.imdb_id {
+        let link = format!("https://www.imdb.com/title/{}", imdb_id);
+        embed.url(&link);
+        embed.field("IMDb", link, false);
+    }
+    if let Some(poster) = &movie.poster_url {
+        embed.image(poster);
+    }
+    embed
+}
+
+fn film_response(provider: &dyn MovieProvider, query: &str) -> Result<CommandResponse, Error> {
+    match provider.search(query)? {
+        None => Ok("Pas de résultat".into()),
+        Some(movie) => Ok(CommandResponse::Embed(build_embed(&movie))),
+    }
+}
+
+pub struct FilmCommand {
+    pub provider: Arc<dyn MovieProvider>,
+}
+
+#[async_trait]
+impl SlashCommand for FilmCommand {
+    fn register(&self, command: &mut CreateApplicationCommand) {
+        command
+            .name("film")
+            .description("Chercher les informations d'un film")
+            .create_option(|option| {
+                option
+                    .name("title")
+                    .description("Le titre du film")
+                    .kind(CommandOptionType::String)
+                    .required(true)
+            });
+    }
+
+    async fn handle(
+        &self,
+        _ctx: &Context,
+        interaction: &ApplicationCommandInteraction,
+    ) -> Result<Option<CommandResponse>, Error> {
+        if interaction.data.name != "film" {
+            return Ok(None);
+        }
+
+        let option = interaction
+            .data
+            .options
+            .first()
+            .ok_or_else(|| anyhow!("missing title option"))?
+            .resolved
+            .as_ref()
+            .ok_or_else(|| anyhow!("missing title option value"))?;
+
+        let title = match option {
+            CommandDataOptionValue::String(t) => t.trim(),
+            _ => return Err(anyhow!("wrong value type for title option")),
+        };
+
+        // the provider can download from an API, which blocks
+        let provider = Arc::clone(&self.provider);
+        let title = title.to_string();
+        tokio::task::spawn_blocking(move || film_response(provider.as_ref(), &title))
+            .await?
+            .map(Some)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::output::CommandResponse;
+    use crate::utils::movies::LocalMovieProvider;
+    use serde_json::json;
+
+    fn provider() -> LocalMovieProvider {
+        LocalMovieProvider {
+            movies: serde_json::from_str(
+                r#"[{
+                "title": "Inception", "year": 2010, "runtime": 148, "genres": ["Action", "Science-Fiction"],
+                "synopsis": "Dom Cobb est un voleur expérimenté.", "rating": 8.36, "vote_count": 35000,
+                "poster_url": "https://image.tmdb.org/t/p/w500/poster.jpg", "imdb_id": "tt1375666",
+                "release_dates": [{"kind": "theatrical", "date": "2010-07-21"}]
+            }]"#,
+            )
+            .unwrap(),
+        }
+    }
+
+    #[test]
+    fn film_embed() {
+        let embed = match super::film_response(&provider(), "inception").unwrap() {
+            CommandResponse::Embed(embed) => embed,
+            _ => panic!("expected an embed"),
+        };
+        assert_eq!(embed.0["title"], json!("Inception (2010)"));
+        assert_eq!(
+            embed.0["url"],
+            json!("https://www.imdb.com/title/tt1375666")
+        );
+        assert_eq!(
+            embed.0["image"]["url"],
+            json!("https://image.tmdb.org/t/p/w500/poster.jpg")
+        );
+        let fields = embed.0["fields"].as_array().unwrap();
+        assert_eq!(fields[0]["value"], json!("2 h 28"));
+        assert_eq!(fields[2]["value"], json!("8.4/10 (35000 votes)"));
+        assert_eq!(fields[3]["value"], json!("Cinéma : 21/07/2010"));
+    }
+
+    #[test]
+    fn film_not_found() {
+        assert!(matches!(
+            super::film_response(&provider(), "nothing").unwrap(),
+            CommandResponse::Text(_)
+        ));
+    }
+}
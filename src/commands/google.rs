@@ -1,10 +1,12 @@
 use crate::commands::SlashCommand;
+use crate::output::CommandResponse;
 use crate::utils::google::GoogleSearcher;
 use crate::utils::google::SearchMode;
 use anyhow::anyhow;
 use anyhow::Error;
 use serenity::async_trait;
 use serenity::builder::CreateApplicationCommand;
+use serenity::client::Context;
 use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
 use serenity::model::prelude::command::CommandOptionType;
 use serenity::model::prelude::interaction::application_command::CommandDataOptionValue;
@@ -31,8 +33,9 @@ impl SlashCommand for GoogleCommand {
 
     async fn handle(
         &self,
+        _ctx: &Context,
         interaction: &ApplicationCommandInteraction,
-    ) -> Result<Option<String>, Error> {
+    ) -> Result<Option<CommandResponse>, Error> {
         if interaction.data.name != "google" {
             return Ok(None);
         }
@@ -40,7 +43,7 @@ impl SlashCommand for GoogleCommand {
         let option = interaction
             .data
             .options
-            .get(0)
+            .first()
             .ok_or_else(|| anyhow!("missing terms option"))?
             .resolved
             .as_ref()
@@ -51,13 +54,20 @@ impl SlashCommand for GoogleCommand {
             _ => return Err(anyhow!("wrong value type for terms option")),
         };
 
-        match self
+        let items = self
             .google_searcher
-            .search(search_terms.to_string(), SearchMode::Web)
-        {
-            Ok(Some(r)) => Ok(Some(format!("{} - {}", r.title, r.link))),
-            Ok(None) => Ok(Some("Pas de résultat".to_string())),
-            Err(e) => Err(e),
+            .search_all(search_terms.to_string(), SearchMode::Web)?;
+        if items.is_empty() {
+            return Ok(Some("Pas de résultat".into()));
         }
+
+        let pages = items
+            .iter()
+            .map(|r| match &r.snippet {
+                None => format!("{} - {}", r.title, r.link),
+                Some(snippet) => format!("{} - {}\n{}", r.title, r.link, snippet),
+            })
+            .collect();
+        Ok(Some(CommandResponse::Pages(pages)))
     }
 }
@@ -1,10 +1,12 @@
 use crate::commands::SlashCommand;
+use crate::output::CommandResponse;
 use crate::utils::google::GoogleSearcher;
 use crate::utils::google::SearchMode;
 use anyhow::anyhow;
 use anyhow::Error;
 use serenity::async_trait;
 use serenity::builder::CreateApplicationCommand;
+use serenity::client::Context;
 use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
 use serenity::model::prelude::command::CommandOptionType;
 use serenity::model::prelude::interaction::application_command::CommandDataOptionValue;
@@ -31,8 +33,9 @@ impl SlashCommand for GoogleImageCommand {
 
     async fn handle(
         &self,
+        _ctx: &Context,
         interaction: &ApplicationCommandInteraction,
-    ) -> Result<Option<String>, Error> {
+    ) -> Result<Option<CommandResponse>, Error> {
         if interaction.data.name != "image" {
             return Ok(None);
         }
@@ -40,7 +43,7 @@ impl SlashCommand for GoogleImageCommand {
         let option = interaction
             .data
             .options
-            .get(0)
+            .first()
             .ok_or_else(|| anyhow!("missing terms option"))?
             .resolved
             .as_ref()
@@ -55,8 +58,8 @@ impl SlashCommand for GoogleImageCommand {
             .google_searcher
             .search(search_terms.to_string(), SearchMode::Image)
         {
-            Ok(Some(r)) => Ok(Some(r.link)),
-            Ok(None) => Ok(Some("Pas de résultat".to_string())),
+            Ok(Some(r)) => Ok(Some(r.link.into())),
+            Ok(None) => Ok(Some("Pas de résultat".into())),
             Err(e) => Err(e),
         }
     }
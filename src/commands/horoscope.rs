@@ -1,4 +1,5 @@
 use crate::commands::SlashCommand;
+use crate::output::CommandResponse;
 use anyhow::anyhow;
 use anyhow::Error;
 use linked_hash_map::LinkedHashMap;
@@ -6,6 +7,7 @@ use scraper::Html;
 use scraper::Selector;
 use serenity::async_trait;
 use serenity::builder::CreateApplicationCommand;
+use serenity::client::Context;
 use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
 use serenity::model::prelude::command::CommandOptionType;
 use serenity::model::prelude::interaction::application_command::CommandDataOptionValue;
@@ -33,8 +35,9 @@ impl SlashCommand for HoroscopeCommand {
 
     async fn handle(
         &self,
+        _ctx: &Context,
         interaction: &ApplicationCommandInteraction,
-    ) -> Result<Option<String>, Error> {
+    ) -> Result<Option<CommandResponse>, Error> {
         if interaction.data.name != "horoscope" {
             return Ok(None);
         }
@@ -42,7 +45,7 @@ impl SlashCommand for HoroscopeCommand {
         let option = interaction
             .data
             .options
-            .get(0)
+            .first()
             .ok_or_else(|| anyhow!("missing sign option"))?
             .resolved
             .as_ref()
@@ -73,7 +76,7 @@ impl SlashCommand for HoroscopeCommand {
             .text()
             .nth(1)
             .ok_or_else(|| anyhow!("cannot extract text of node"))?;
-        Ok(Some(format!("{}{}", sign, horoscope)))
+        Ok(Some(format!("{}{}", sign, horoscope).into()))
     }
 }
 
@@ -0,0 +1,172 @@
+use crate::commands::find_option;
+use crate::db::connerie::Connerie;
+use crate::output::CommandResponse;
+use crate::utils::markov::MarkovChain;
+use crate::utils::search::fold;
+use crate::utils::text::is_stop_word;
+use crate::utils::text::tokenize;
+use crate::SlashCommand;
+use anyhow::Error;
+use serenity::async_trait;
+use serenity::builder::CreateApplicationCommand;
+use serenity::client::Context;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::model::prelude::command::CommandOptionType;
+use serenity::model::prelude::interaction::application_command::CommandDataOptionValue;
+use sqlx::MySqlPool;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+/// number of previous words choosing the next one
+const MARKOV_ORDER: usize = 2;
+const MAX_SENTENCE_WORDS: usize = 40;
+/// the chain is saved at most this often when learning, the conneries added since are learned again when it is loaded
+const SAVE_INTERVAL: Duration = Duration::from_secs(600);
+
+/// the chain learned from the conneries, saved on disk and loaded when first used
+pub struct MarkovModel {
+    pub path: PathBuf,
+    chain: Mutex<Option<MarkovChain>>,
+    last_saved: Mutex<Instant>,
+}
+
+impl MarkovModel {
+    pub fn new(path: PathBuf) -> MarkovModel {
+        MarkovModel {
+            path,
+            chain: Mutex::new(None),
+            last_saved: Mutex::new(Instant::now()),
+        }
+    }
+
+    async fn save(&self, chain: &MarkovChain) -> Result<(), Error> {
+        // written next to the model then renamed so that a crash never leaves a truncated model
+        let tmp = self.path.with_extension("tmp");
+        tokio::fs::write(&tmp, serde_json::to_vec(chain)?).await?;
+        tokio::fs::rename(&tmp, &self.path).await?;
+        *self.last_saved.lock().await = Instant::now();
+        Ok(())
+    }
+
+    /**
+     * the saved chain, or a new one, having learned the conneries added since it was saved
+     */
+    async fn load(&self, pool: &MySqlPool) -> Result<MarkovChain, Error> {
+        let mut chain = match tokio::fs::read(&self.path).await {
+            Ok(data) => match serde_json::from_slice::<MarkovChain>(&data) {
+                Ok(chain) => chain,
+                // a new chain learns every connerie again, and replaces the broken one when saved
+                Err(e) => {
+                    println!(
+                        "invalid markov model {}, learning it again : {}",
+                        self.path.display(),
+                        e
+                    );
+                    MarkovChain::new(MARKOV_ORDER)
+                }
+            },
+            Err(_) => MarkovChain::new(MARKOV_ORDER),
+        };
+        let conneries = Connerie::find_after(pool, chain.last_learned_id).await?;
+        if !conneries.is_empty() {
+            for connerie in conneries {
+                chain.learn(&connerie.value);
+                chain.last_learned_id = connerie.id;
+            }
+            self.save(&chain).await?;
+        }
+        Ok(chain)
+    }
+
+    /**
+     * learn a connerie just added
+     */
+    pub async fn learn(&self, pool: &MySqlPool, id: i64, text: &str) -> Result<(), Error> {
+        let mut guard = self.chain.lock().await;
+        let chain = match guard.as_mut() {
+            Some(chain) => chain,
+            // loading the chain learns the new connerie
+            None => {
+                *guard = Some(self.load(pool).await?);
+                return Ok(());
+            }
+        };
+        chain.learn(text);
+        chain.last_learned_id = chain.last_learned_id.max(id);
+
+        if self.last_saved.lock().await.elapsed() >= SAVE_INTERVAL {
+            self.save(chain).await?;
+        }
+        Ok(())
+    }
+
+    /**
+     * a new sentence containing one of the seeds if some are given
+     */
+    pub async fn generate(
+        &self,
+        pool: &MySqlPool,
+        seeds: &[&str],
+    ) -> Result<Option<String>, Error> {
+        let mut guard = self.chain.lock().await;
+        if guard.is_none() {
+            *guard = Some(self.load(pool).await?);
+        }
+        Ok(guard
+            .as_ref()
+            .and_then(|chain| chain.generate(seeds, MAX_SENTENCE_WORDS, &mut rand::thread_rng())))
+    }
+}
+
+/**
+ * the words of a text worth generating a sentence about
+ */
+pub fn seed_words(text: &str, min_length: usize) -> Vec<String> {
+    tokenize(text)
+        .into_iter()
+        .filter(|w| w.chars().count() >= min_length && !is_stop_word(w))
+        .collect()
+}
+
+pub struct MarkovCommand {
+    pub db_pool: Arc<MySqlPool>,
+    pub model: Arc<MarkovModel>,
+}
+
+#[async_trait]
+impl SlashCommand for MarkovCommand {
+    fn register(&self, command: &mut CreateApplicationCommand) {
+        command
+            .name("markov")
+            .description("Une nouvelle phrase inventée à partir des conneries")
+            .create_option(|option| {
+                option
+                    .name("seed")
+                    .description("mot que la phrase doit contenir")
+                    .kind(CommandOptionType::String)
+            });
+    }
+
+    async fn handle(
+        &self,
+        _ctx: &Context,
+        interaction: &ApplicationCommandInteraction,
+    ) -> Result<Option<CommandResponse>, Error> {
+        if interaction.data.name != "markov" {
+            return Ok(None);
+        }
+
+        let seeds = match find_option(&interaction.data.options, "seed") {
+            Some(CommandDataOptionValue::String(s)) => tokenize(&fold(s)),
+            _ => Vec::new(),
+        };
+        let seeds: Vec<&str> = seeds.iter().map(String::as_str).collect();
+        match self.model.generate(&self.db_pool, &seeds).await? {
+            Some(sentence) => Ok(Some(sentence.into())),
+            None if seeds.is_empty() => Ok(Some("Pas encore de conneries à imiter".into())),
+            None => Ok(Some("Je ne connais pas ce mot".into())),
+        }
+    }
+}
@@ -1,11 +1,13 @@
 use crate::commands::SlashCommand;
 use crate::db::connerie::Connerie;
+use crate::output::CommandResponse;
 use anyhow::anyhow;
 use anyhow::Error;
 use rand::Rng;
 use serde::Deserialize;
 use serenity::async_trait;
 use serenity::builder::CreateApplicationCommand;
+use serenity::client::Context;
 use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
 use serenity::model::prelude::command::CommandOptionType;
 use serenity::model::prelude::interaction::application_command::CommandDataOptionValue;
@@ -99,8 +101,9 @@ impl SlashCommand for MemeCommand {
 
     async fn handle(
         &self,
+        _ctx: &Context,
         interaction: &ApplicationCommandInteraction,
-    ) -> Result<Option<String>, Error> {
+    ) -> Result<Option<CommandResponse>, Error> {
         if interaction.data.name != "meme" {
             return Ok(None);
         }
@@ -130,6 +133,6 @@ impl SlashCommand for MemeCommand {
             ])?
             .into_json::<CaptionImageResponse>()?;
         let url = caption_image_response.data.url;
-        Ok(Some(url))
+        Ok(Some(url.into()))
     }
 }
@@ -1,8 +1,17 @@
+use crate::autocomplete::Choice;
+use crate::output::CommandResponse;
+use crate::output::Overflow;
 use anyhow::Error;
 use serenity::async_trait;
 use serenity::builder::CreateApplicationCommand;
 use serenity::client::Context;
 use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::model::application::interaction::application_command::CommandDataOption;
+use serenity::model::application::interaction::application_command::CommandDataOptionValue;
+use serenity::model::application::interaction::autocomplete::AutocompleteInteraction;
+use serenity::model::application::interaction::message_component::MessageComponentInteraction;
+use serenity::model::application::interaction::modal::ModalSubmitInteraction;
+use serenity::model::guild::Member;
 use serenity::model::prelude::Message;
 
 pub mod blague;
@@ -10,25 +19,105 @@ pub mod buzz;
 pub mod connerie;
 pub mod eight_ball;
 pub mod episodes;
+pub mod feed;
+pub mod film;
 pub mod google;
 pub mod google_image;
 pub mod horoscope;
+pub mod markov;
 pub mod meme;
 pub mod quote;
+pub mod remind;
 pub mod skandite;
+pub mod tv_schedule;
+pub mod watch;
 pub mod youtube;
 
+pub const NOT_GUILD_ADMIN: &str =
+    "Seuls les membres qui peuvent gérer le serveur peuvent modifier ce réglage.";
+
+pub fn is_guild_admin(member: Option<&Member>) -> bool {
+    member
+        .and_then(|m| m.permissions)
+        .map(|p| p.manage_guild())
+        .unwrap_or(false)
+}
+
+pub fn can_manage_channels(member: Option<&Member>) -> bool {
+    member
+        .and_then(|m| m.permissions)
+        .map(|p| p.manage_channels())
+        .unwrap_or(false)
+}
+
+/**
+ * value of an optional option, looked up by name
+ */
+pub fn find_option<'a>(
+    options: &'a [CommandDataOption],
+    name: &str,
+) -> Option<&'a CommandDataOptionValue> {
+    options
+        .iter()
+        .find(|o| o.name == name)
+        .and_then(|o| o.resolved.as_ref())
+}
+
 #[async_trait]
 pub trait SlashCommand: Send + Sync {
     fn register(&self, command: &mut CreateApplicationCommand);
 
+    /// how to send a response too long for a single Discord message
+    fn overflow(&self) -> Overflow {
+        Overflow::Split
+    }
+
+    /// prefixes of the custom ids of the message components and modals handled by this command
+    fn custom_id_prefixes(&self) -> &'static [&'static str] {
+        &[]
+    }
+
     async fn handle(
         &self,
+        ctx: &Context,
         interaction: &ApplicationCommandInteraction,
-    ) -> Result<Option<String>, Error>;
+    ) -> Result<Option<CommandResponse>, Error>;
+
+    /// suggestions for the option the user is typing, None if the interaction is for another command
+    async fn autocomplete(
+        &self,
+        _interaction: &AutocompleteInteraction,
+    ) -> Result<Option<Vec<Choice>>, Error> {
+        Ok(None)
+    }
+
+    /// called when a message component (button, select menu) with one of the custom id prefixes is used,
+    /// return None if the command already answered the interaction itself
+    async fn handle_component(
+        &self,
+        _ctx: &Context,
+        _interaction: &MessageComponentInteraction,
+    ) -> Result<Option<CommandResponse>, Error> {
+        Ok(None)
+    }
+
+    /// called when a modal with one of the custom id prefixes is submitted,
+    /// return None if the command already answered the interaction itself
+    async fn handle_modal(
+        &self,
+        _ctx: &Context,
+        _interaction: &ModalSubmitInteraction,
+    ) -> Result<Option<CommandResponse>, Error> {
+        Ok(None)
+    }
 }
 
 #[async_trait]
 pub trait MessageCommand: Send + Sync {
+    /// how to send a response too long for a single Discord message
+    fn overflow(&self) -> Overflow {
+        Overflow::Split
+    }
+
     async fn handle(&self, ctx: &Context, message: &Message) -> Result<Option<String>, Error>;
 }
@@ -1,94 +1,835 @@
+use crate::autocomplete::choice;
+use crate::autocomplete::focused_value;
+use crate::autocomplete::Autocompleter;
+use crate::autocomplete::Choice;
+use crate::autocomplete::MAX_CHOICES;
+use crate::commands::find_option;
+use crate::commands::is_guild_admin;
 use crate::commands::SlashCommand;
+use crate::commands::NOT_GUILD_ADMIN;
+use crate::db::quote::NewQuote;
 use crate::db::quote::Quote;
+use crate::db::quote_attachment::QuoteAttachment;
+use crate::db::quote_book::QuoteBookInvite;
+use crate::db::quote_book::QuoteBookLink;
+use crate::db::quote_line::NewQuoteLine;
+use crate::db::quote_line::QuoteLine;
+use crate::db::quote_of_the_day::QuoteOfTheDay;
+use crate::db::quote_vote::QuoteVote;
+use crate::db::quote_vote::VoteTally;
+use crate::db::quote_vote::DOWN_VOTE;
+use crate::db::quote_vote::UP_VOTE;
+use crate::interactions::build_custom_id;
+use crate::interactions::parse_custom_id;
+use crate::interactions::StateStore;
+use crate::output::reply_ephemeral;
+use crate::output::truncate;
+use crate::output::CommandResponse;
+use crate::output::Overflow;
+use crate::scheduler::Job;
+use crate::utils::archive::summarize_embed;
+use crate::utils::archive::QuoteArchive;
+use crate::utils::archive::MAX_ARCHIVED_FILE_SIZE;
+use crate::utils::card::download_image;
+use crate::utils::card::render as render_card;
+use crate::utils::card::QuoteCard;
+use crate::utils::search::SearchQuery;
 use anyhow::anyhow;
 use anyhow::Error;
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
 use serenity::async_trait;
 use serenity::builder::CreateApplicationCommand;
+use serenity::builder::CreateComponents;
+use serenity::builder::CreateEmbed;
+use serenity::client::Context;
+use serenity::http::CacheHttp;
+use serenity::http::Http;
+use serenity::http::StatusCode;
+use serenity::model::application::component::ButtonStyle;
 use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::model::application::interaction::autocomplete::AutocompleteInteraction;
+use serenity::model::application::interaction::message_component::MessageComponentInteraction;
+use serenity::model::channel::AttachmentType;
+use serenity::model::channel::Message;
+use serenity::model::guild::Member;
+use serenity::model::id::ChannelId;
+use serenity::model::id::GuildId;
+use serenity::model::id::MessageId;
+use serenity::model::id::UserId;
 use serenity::model::prelude::command::CommandOptionType;
 use serenity::model::prelude::command::CommandType;
 use serenity::model::prelude::interaction::application_command::CommandDataOption;
 use serenity::model::prelude::interaction::application_command::CommandDataOptionValue;
+use serenity::Error as SerenityError;
 use sqlx::MySqlPool;
+use std::borrow::Cow;
 use std::sync::Arc;
+use std::time::Duration as StdDuration;
+
+/// Discord refuses longer embed descriptions
+const EMBED_DESCRIPTION_MAX_LENGTH: usize = 4096;
+const QUOTE_PREFIX: &str = "quote";
+const UNDO_ACTION: &str = "undo";
+const VOTE_ACTION: &str = "vote";
+const LEADERBOARD_SIZE: usize = 10;
+const LEADERBOARD_EXCERPT_LENGTH: usize = 150;
+/// a deleted quote can be restored with the button of the deletion message during this delay
+const UNDO_WINDOW_MINUTES: i64 = 5;
+/// results of a search, shown a few per page
+const SEARCH_RESULTS_LIMIT: usize = 100;
+/// members looked up for each name of a legacy quote author
+const MEMBER_SEARCH_LIMIT: u64 = 10;
+const SEARCH_RESULTS_PER_PAGE: usize = 5;
+const EXCERPT_MAX_LENGTH: usize = 300;
+const NOT_AUTHORIZED: &str = "Vous n'avez pas le droit de modifier les citations.";
+const INVALID_NUMBER: &str = "Numéro invalide.";
+pub const QUOTE_OF_THE_DAY_JOB_KIND: &str = "quote_of_the_day";
+/// the guilds whose posting time has come are looked for every minute
+pub const QUOTE_OF_THE_DAY_SCHEDULE: &str = "0 * * * * *";
+const DEFAULT_TIMEZONE: &str = "Europe/Paris";
+/// the first message of a conversation is remembered this long for "End Quote"
+pub const QUOTE_CAPTURE_TTL: StdDuration = StdDuration::from_secs(900);
+const MAX_CONVERSATION_MESSAGES: usize = 20;
+/// files uploaded again when displaying a quote, the others are only listed in its text
+const MAX_DISPLAYED_FILES: usize = 10;
+
+/**
+ * the quote book used by the guild of an interaction, quotes are not available in direct messages
+ */
+async fn book_id(pool: &MySqlPool, guild_id: Option<GuildId>) -> Result<u64, Error> {
+    let guild_id = guild_id.ok_or_else(|| anyhow!("quotes are only available in a guild"))?;
+    QuoteBookLink::find_book_id(pool, guild_id.0).await
+}
 
 pub struct QuoteCommand {
     pub db_pool: Arc<MySqlPool>,
+    pub archive: Arc<QuoteArchive>,
+    /// its cached suggestions are dropped when the guild changes book
+    pub autocompleter: Arc<Autocompleter>,
+    /// roles allowed to edit and delete quotes, in addition to the members who can manage messages
+    pub admin_role_ids: Vec<u64>,
+}
+
+/**
+ * a conversation, one line per message with its speaker
+ */
+fn dialogue(lines: &[QuoteLine]) -> String {
+    lines
+        .iter()
+        .map(|l| format!("**{}** : {}", l.author_name, l.content))
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/**
+ * embed displaying a quote with its author, date and a link to the original message
+ */
+fn quote_embed(quote: &Quote, avatar: Option<String>, lines: &[QuoteLine]) -> CreateEmbed {
+    let mut embed = CreateEmbed::default();
+    let link = quote.message_link();
+    if let Some(author) = &quote.author_name {
+        embed.author(|a| {
+            a.name(author);
+            if let Some(avatar) = avatar {
+                a.icon_url(avatar);
+            }
+            if let Some(link) = &link {
+                a.url(link);
+            }
+            a
+        });
+    }
+    let description = if quote.author_name.is_none() && !lines.is_empty() {
+        dialogue(lines)
+    } else {
+        quote.quote.clone()
+    };
+    embed.description(truncate(&description, EMBED_DESCRIPTION_MAX_LENGTH));
+    if let Some(link) = &link {
+        embed.field(
+            "Message original",
+            format!("[Aller au message]({})", link),
+            true,
+        );
+    }
+    if let Some(added_by) = quote.added_by {
+        embed.field("Ajoutée par", format!("<@{}>", added_by), true);
+    }
+    embed.footer(|f| f.text(format!("Citation n°{}", quote.number)));
+    if let Some(date) = quote.quoted_at.or(quote.created_at) {
+        embed.timestamp(date.to_rfc3339());
+    }
+    embed
+}
+
+/**
+ * pages listing quotes a few at a time, with the words of the search highlighted
+ */
+fn quote_pages(quotes: &[Quote], query: Option<&SearchQuery>) -> Vec<String> {
+    quotes
+        .chunks(SEARCH_RESULTS_PER_PAGE)
+        .map(|chunk| {
+            chunk
+                .iter()
+                .map(|q| {
+                    let text = match query {
+                        Some(query) => query.excerpt(&q.text(), EXCERPT_MAX_LENGTH),
+                        None => truncate(&q.text(), EXCERPT_MAX_LENGTH),
+                    };
+                    format!("**{}.** {}", q.number, text)
+                })
+                .collect::<Vec<String>>()
+                .join("\n")
+        })
+        .collect()
+}
+
+/**
+ * 👍 and 👎 buttons showing the votes of a quote
+ */
+fn vote_components(quote: &Quote, tally: &VoteTally) -> CreateComponents {
+    let quote_id = quote.id.to_string();
+    let mut components = CreateComponents::default();
+    components.create_action_row(|row| {
+        row.create_button(|button| {
+            button
+                .custom_id(build_custom_id(
+                    QUOTE_PREFIX,
+                    &[VOTE_ACTION, &quote_id, &UP_VOTE.to_string()],
+                ))
+                .label(format!("👍 {}", tally.up))
+                .style(ButtonStyle::Secondary)
+        })
+        .create_button(|button| {
+            button
+                .custom_id(build_custom_id(
+                    QUOTE_PREFIX,
+                    &[VOTE_ACTION, &quote_id, &DOWN_VOTE.to_string()],
+                ))
+                .label(format!("👎 {}", tally.down))
+                .style(ButtonStyle::Secondary)
+        })
+    });
+    components
+}
+
+async fn author_avatar(cache_http: impl CacheHttp, quote: &Quote) -> Option<String> {
+    let author_id = quote.author_id?;
+    UserId(author_id)
+        .to_user(cache_http)
+        .await
+        .ok()
+        .map(|u| u.face())
+}
+
+/**
+ * embed of a quote with its vote buttons
+ */
+async fn quote_message(
+    pool: &MySqlPool,
+    cache_http: impl CacheHttp,
+    quote: &Quote,
+) -> Result<(CreateEmbed, CreateComponents), Error> {
+    let avatar = author_avatar(cache_http, quote).await;
+    let lines = QuoteLine::find_by_quote(pool, quote.id).await?;
+    let tally = QuoteVote::tally(pool, quote.id).await?;
+    Ok((
+        quote_embed(quote, avatar, &lines),
+        vote_components(quote, &tally),
+    ))
 }
 
 impl QuoteCommand {
     fn format_quote(quote: &Quote) -> String {
-        format!("{}. {}", quote.number, quote.quote)
+        format!("{}. {}", quote.number, quote.text())
     }
 
-    async fn trigger_get(&self, command: &CommandDataOption) -> Result<Option<String>, Error> {
-        let option = command
-            .options
-            .get(0)
-            .ok_or_else(|| anyhow!("missing command sub option"))?
-            .resolved
-            .as_ref()
-            .ok_or_else(|| anyhow!("missing command sub option value"))?;
+    async fn quote_response(
+        &self,
+        ctx: &Context,
+        quote: Option<Quote>,
+    ) -> Result<Option<CommandResponse>, Error> {
+        match quote {
+            None => Ok(Some("Pas de résultat!".into())),
+            Some(q) => {
+                let (embed, components) = quote_message(&self.db_pool, ctx, &q).await?;
+                Ok(Some(CommandResponse::EmbedComponents(embed, components)))
+            }
+        }
+    }
 
-        let number = match option {
-            CommandDataOptionValue::String(s) => s,
-            _ => return Err(anyhow!("wrong value type for command sub option")),
+    async fn trigger_get(
+        &self,
+        ctx: &Context,
+        book_id: u64,
+        command: &CommandDataOption,
+    ) -> Result<Option<CommandResponse>, Error> {
+        let number = match QuoteCommand::number_option(command)? {
+            Some(n) => n,
+            None => return Ok(Some(INVALID_NUMBER.into())),
         };
 
-        let quote = Quote::find_by_number(&self.db_pool, number.parse::<i64>()?).await?;
-        match quote {
-            None => Ok(Some("Pas de résultat!".to_string())),
-            Some(q) => Ok(Some(QuoteCommand::format_quote(&q))),
+        let quote = Quote::find_by_number(&self.db_pool, book_id, number).await?;
+        let attachments = match &quote {
+            Some(q) => QuoteAttachment::find_by_quote(&self.db_pool, q.id).await?,
+            None => Vec::new(),
+        };
+        let response = self.quote_response(ctx, quote).await?;
+
+        let mut files = Vec::new();
+        for attachment in attachments.into_iter().take(MAX_DISPLAYED_FILES) {
+            match self.archive.read(&attachment.path).await {
+                Ok(data) => files.push(AttachmentType::Bytes {
+                    data: Cow::Owned(data),
+                    filename: attachment.filename,
+                }),
+                Err(e) => println!(
+                    "error while reading archived file {} : {}",
+                    attachment.path, e
+                ),
+            }
         }
+        if files.is_empty() {
+            return Ok(response);
+        }
+        Ok(response.map(|r| CommandResponse::WithFiles(Box::new(r), files)))
     }
 
-    async fn trigger_find(&self, command: &CommandDataOption) -> Result<Option<String>, Error> {
-        let option = command
-            .options
-            .get(0)
-            .ok_or_else(|| anyhow!("missing command sub option"))?
-            .resolved
-            .as_ref()
-            .ok_or_else(|| anyhow!("missing command sub option value"))?;
+    async fn trigger_card(
+        &self,
+        ctx: &Context,
+        book_id: u64,
+        command: &CommandDataOption,
+    ) -> Result<Option<CommandResponse>, Error> {
+        let number = match QuoteCommand::number_option(command)? {
+            Some(n) => n,
+            None => return Ok(Some(INVALID_NUMBER.into())),
+        };
+        let quote = match Quote::find_by_number(&self.db_pool, book_id, number).await? {
+            Some(q) => q,
+            None => return Ok(Some("Pas de résultat!".into())),
+        };
 
-        let search_terms = match option {
-            CommandDataOptionValue::String(s) => s,
-            _ => return Err(anyhow!("wrong value type for command sub option")),
+        let avatar_url = author_avatar(ctx, &quote).await;
+        let title = format!("Citation n°{}", quote.number);
+        let footer = match quote.quoted_at.or(quote.created_at) {
+            Some(date) => format!("{} · {}", title, date.format("%d/%m/%Y")),
+            None => title.clone(),
         };
+        let author = quote
+            .author_name
+            .clone()
+            .unwrap_or_else(|| "Conversation".to_string());
+        let text = quote.quote.clone();
+        // the download and the drawing block, they must not hold up the other commands
+        let png = tokio::task::spawn_blocking(move || {
+            let avatar = avatar_url.and_then(|url| {
+                download_image(&url)
+                    .map_err(|e| println!("error while downloading avatar {} : {}", url, e))
+                    .ok()
+            });
+            render_card(&QuoteCard {
+                author: &author,
+                avatar,
+                text: &text,
+                footer: &footer,
+            })
+        })
+        .await??;
 
-        let tokens: Vec<&str> = search_terms.split(' ').collect();
-        let quotes = Quote::search(&self.db_pool, &tokens[..]).await?;
+        Ok(Some(CommandResponse::WithFiles(
+            Box::new(title.into()),
+            vec![AttachmentType::Bytes {
+                data: Cow::Owned(png),
+                filename: format!("citation_{}.png", quote.number),
+            }],
+        )))
+    }
+
+    /**
+     * the member whose quotes are wanted, if the option is given
+     */
+    fn user_option(command: &CommandDataOption) -> Option<u64> {
+        match find_option(&command.options, "user") {
+            Some(CommandDataOptionValue::User(user, _)) => Some(user.id.0),
+            _ => None,
+        }
+    }
+
+    async fn trigger_find(
+        &self,
+        book_id: u64,
+        command: &CommandDataOption,
+    ) -> Result<Option<CommandResponse>, Error> {
+        let author_id = QuoteCommand::user_option(command);
+        let query = match find_option(&command.options, "terms") {
+            Some(CommandDataOptionValue::String(s)) => Some(SearchQuery::parse(s)),
+            _ => None,
+        };
+
+        let quotes = match (&query, author_id) {
+            (Some(query), _) if query.to_boolean_mode().is_some() => {
+                Quote::search(
+                    &self.db_pool,
+                    book_id,
+                    query,
+                    author_id,
+                    SEARCH_RESULTS_LIMIT,
+                )
+                .await?
+            }
+            (_, Some(author_id)) if query.is_none() => {
+                Quote::find_by_author(&self.db_pool, book_id, author_id, SEARCH_RESULTS_LIMIT)
+                    .await?
+            }
+            _ => return Ok(Some("Précisez au moins un mot à chercher.".into())),
+        };
         if quotes.is_empty() {
-            return Ok(Some("Pas de résultat.".to_string()));
+            return Ok(Some("Pas de résultat.".into()));
         }
 
-        let quote_ids = quotes
-            .iter()
-            .map(|q| q.number.to_string())
-            .collect::<Vec<String>>();
+        Ok(Some(CommandResponse::Pages(quote_pages(
+            &quotes,
+            query.as_ref(),
+        ))))
+    }
 
-        let message = format!(
-            "Quotes correspondants à la recherche : {}",
-            quote_ids.join(", ")
-        );
-        Ok(Some(message))
+    fn is_authorized(&self, member: Option<&Member>) -> bool {
+        member
+            .map(|m| {
+                m.roles.iter().any(|r| self.admin_role_ids.contains(&r.0))
+                    || m.permissions.map(|p| p.manage_messages()).unwrap_or(false)
+            })
+            .unwrap_or(false)
     }
 
-    async fn trigger_random(&self) -> Result<Option<String>, Error> {
-        let quote = Quote::random(&self.db_pool).await?;
-        match quote {
-            None => Ok(Some("Pas de résultat!".to_string())),
-            Some(q) => Ok(Some(QuoteCommand::format_quote(&q))),
+    fn number_option(command: &CommandDataOption) -> Result<Option<i64>, Error> {
+        match find_option(&command.options, "id") {
+            Some(CommandDataOptionValue::String(s)) => Ok(s.trim().parse::<i64>().ok()),
+            _ => Err(anyhow!("missing id option")),
         }
     }
 
-    async fn trigger_count(&self) -> Result<Option<String>, Error> {
-        let count = Quote::count(&self.db_pool).await?;
-        Ok(Some(format!(
-            "Il y a {} citations dans la base de données.",
-            count
+    async fn trigger_edit(
+        &self,
+        book_id: u64,
+        interaction: &ApplicationCommandInteraction,
+        command: &CommandDataOption,
+    ) -> Result<Option<CommandResponse>, Error> {
+        if !self.is_authorized(interaction.member.as_ref()) {
+            return Ok(Some(NOT_AUTHORIZED.into()));
+        }
+        let number = match QuoteCommand::number_option(command)? {
+            Some(n) => n,
+            None => return Ok(Some(INVALID_NUMBER.into())),
+        };
+        let text = match find_option(&command.options, "text") {
+            Some(CommandDataOptionValue::String(s)) => s.trim(),
+            _ => return Err(anyhow!("missing text option")),
+        };
+
+        let result =
+            Quote::update_text(&self.db_pool, book_id, number, text, interaction.user.id.0).await?;
+        if result.rows_affected() == 0 {
+            return Ok(Some("Pas de résultat!".into()));
+        }
+        Ok(Some(format!("Citation {} modifiée.", number).into()))
+    }
+
+    async fn trigger_delete(
+        &self,
+        book_id: u64,
+        interaction: &ApplicationCommandInteraction,
+        command: &CommandDataOption,
+    ) -> Result<Option<CommandResponse>, Error> {
+        if !self.is_authorized(interaction.member.as_ref()) {
+            return Ok(Some(NOT_AUTHORIZED.into()));
+        }
+        let number = match QuoteCommand::number_option(command)? {
+            Some(n) => n,
+            None => return Ok(Some(INVALID_NUMBER.into())),
+        };
+
+        let result =
+            Quote::soft_delete(&self.db_pool, book_id, number, interaction.user.id.0).await?;
+        if result.rows_affected() == 0 {
+            return Ok(Some("Pas de résultat!".into()));
+        }
+
+        let number = number.to_string();
+        let mut components = CreateComponents::default();
+        components.create_action_row(|row| {
+            row.create_button(|button| {
+                button
+                    .custom_id(build_custom_id(QUOTE_PREFIX, &[UNDO_ACTION, &number]))
+                    .label("Annuler")
+                    .style(ButtonStyle::Secondary)
+            })
+        });
+        Ok(Some(CommandResponse::Components(
+            format!(
+                "Citation {} supprimée. Elle peut être restaurée pendant {} minutes.",
+                number, UNDO_WINDOW_MINUTES
+            ),
+            components,
         )))
     }
+
+    async fn trigger_random(
+        &self,
+        ctx: &Context,
+        book_id: u64,
+        command: &CommandDataOption,
+    ) -> Result<Option<CommandResponse>, Error> {
+        let author_id = QuoteCommand::user_option(command);
+        let weighted = matches!(
+            find_option(&command.options, "weighted"),
+            Some(CommandDataOptionValue::Boolean(true))
+        );
+        let quote = if weighted {
+            Quote::random_weighted(&self.db_pool, book_id, author_id).await?
+        } else {
+            Quote::random(&self.db_pool, book_id, author_id).await?
+        };
+        self.quote_response(ctx, quote).await
+    }
+
+    async fn trigger_ranking(
+        &self,
+        book_id: u64,
+        best: bool,
+    ) -> Result<Option<CommandResponse>, Error> {
+        let quotes = Quote::ranked(&self.db_pool, book_id, best, LEADERBOARD_SIZE).await?;
+        if quotes.is_empty() {
+            return Ok(Some("Aucune citation n'a encore reçu de vote.".into()));
+        }
+
+        let lines: Vec<String> = quotes
+            .iter()
+            .enumerate()
+            .map(|(i, q)| {
+                format!(
+                    "{}. **n°{}** ({:+}) {}",
+                    i + 1,
+                    q.quote.number,
+                    q.score,
+                    truncate(&q.quote.text(), LEADERBOARD_EXCERPT_LENGTH)
+                )
+            })
+            .collect();
+        Ok(Some(lines.join("\n").into()))
+    }
+
+    async fn vote(
+        &self,
+        ctx: &Context,
+        interaction: &MessageComponentInteraction,
+        quote_id: i64,
+        value: i8,
+    ) -> Result<Option<CommandResponse>, Error> {
+        let book_id = book_id(&self.db_pool, interaction.guild_id).await?;
+        let quote = match Quote::find_by_id(&self.db_pool, quote_id).await? {
+            Some(q) if q.book_id == book_id => q,
+            _ => {
+                reply_ephemeral(ctx, interaction, "Cette citation n'existe plus.").await?;
+                return Ok(None);
+            }
+        };
+
+        QuoteVote::toggle(&self.db_pool, quote.id, interaction.user.id.0, value).await?;
+        let (mut embed, components) = quote_message(&self.db_pool, ctx, &quote).await?;
+        // keep the title of the quote of the day
+        if let Some(title) = interaction
+            .message
+            .embeds
+            .first()
+            .and_then(|e| e.title.as_ref())
+        {
+            embed.title(title);
+        }
+        Ok(Some(CommandResponse::EmbedComponents(embed, components)))
+    }
+
+    async fn undo_delete(
+        &self,
+        ctx: &Context,
+        interaction: &MessageComponentInteraction,
+        number: i64,
+    ) -> Result<Option<CommandResponse>, Error> {
+        if !self.is_authorized(interaction.member.as_ref()) {
+            reply_ephemeral(ctx, interaction, NOT_AUTHORIZED).await?;
+            return Ok(None);
+        }
+
+        let book_id = book_id(&self.db_pool, interaction.guild_id).await?;
+        let deleted_after = Utc::now() - Duration::minutes(UNDO_WINDOW_MINUTES);
+        let result = Quote::restore(&self.db_pool, book_id, number, deleted_after).await?;
+        if result.rows_affected() == 0 {
+            reply_ephemeral(ctx, interaction, "Trop tard pour restaurer cette citation.").await?;
+            return Ok(None);
+        }
+        Ok(Some(format!("Citation {} restaurée.", number).into()))
+    }
+
+    async fn trigger_count(
+        &self,
+        book_id: u64,
+        command: &CommandDataOption,
+    ) -> Result<Option<CommandResponse>, Error> {
+        let author_id = QuoteCommand::user_option(command);
+        let count = Quote::count(&self.db_pool, book_id, author_id).await?;
+        let reply = match author_id {
+            Some(author_id) => format!("<@{}> a {} citations.", author_id, count),
+            None => format!("Il y a {} citations dans la base de données.", count),
+        };
+        Ok(Some(reply.into()))
+    }
+
+    async fn trigger_book(
+        &self,
+        ctx: &Context,
+        interaction: &ApplicationCommandInteraction,
+        command: &CommandDataOption,
+    ) -> Result<Option<CommandResponse>, Error> {
+        let guild_id = interaction
+            .guild_id
+            .ok_or_else(|| anyhow!("quotes are only available in a guild"))?
+            .0;
+        let sub_command = command
+            .options
+            .first()
+            .ok_or_else(|| anyhow!("missing command sub option"))?;
+
+        if sub_command.name == "info" {
+            return self.trigger_book_info(guild_id).await;
+        }
+
+        if !is_guild_admin(interaction.member.as_ref()) {
+            return Ok(Some(NOT_GUILD_ADMIN.into()));
+        }
+
+        match sub_command.name.as_str() {
+            "share" => {
+                // a shared book is always the own book of a guild, links never chain
+                if QuoteBookLink::find_book_id(&self.db_pool, guild_id).await? != guild_id {
+                    return Ok(Some(
+                        "Ce serveur utilise le livre de citations d'un autre serveur, il ne peut pas le partager.".into(),
+                    ));
+                }
+                let code =
+                    QuoteBookInvite::create(&self.db_pool, guild_id, interaction.user.id.0).await?;
+                Ok(Some(
+                    format!(
+                        "Un administrateur de l'autre serveur peut rejoindre le livre de citations de ce serveur avec `/quote book join code:{}` pendant 24 heures.",
+                        code
+                    )
+                    .into(),
+                ))
+            }
+            "join" => {
+                let code = match find_option(&sub_command.options, "code") {
+                    Some(CommandDataOptionValue::String(s)) => s.trim().to_string(),
+                    _ => return Err(anyhow!("missing code option")),
+                };
+                let book_id = match QuoteBookInvite::redeem(&self.db_pool, &code).await? {
+                    Some(b) if b != guild_id => b,
+                    _ => return Ok(Some("Code invalide ou expiré.".into())),
+                };
+                if QuoteBookLink::find_book_id(&self.db_pool, book_id).await? != book_id {
+                    return Ok(Some(
+                        "Ce livre de citations est lui-même celui d'un autre serveur.".into(),
+                    ));
+                }
+                if !QuoteBookLink::find_guild_ids(&self.db_pool, guild_id)
+                    .await?
+                    .is_empty()
+                {
+                    return Ok(Some(
+                        "D'autres serveurs utilisent le livre de citations de ce serveur, il ne peut pas en rejoindre un autre.".into(),
+                    ));
+                }
+                QuoteBookLink::link(&self.db_pool, guild_id, book_id, interaction.user.id.0)
+                    .await?;
+                self.autocompleter.forget_guild(guild_id);
+                Ok(Some(
+                    format!(
+                        "Ce serveur utilise maintenant le livre de citations du serveur {}.",
+                        book_id
+                    )
+                    .into(),
+                ))
+            }
+            "authors" => self.attribute_legacy_quotes(ctx, guild_id).await,
+            "unlink" => {
+                let result = QuoteBookLink::unlink(&self.db_pool, guild_id).await?;
+                self.autocompleter.forget_guild(guild_id);
+                if result.rows_affected() == 0 {
+                    return Ok(Some(
+                        "Ce serveur utilise déjà son propre livre de citations.".into(),
+                    ));
+                }
+                Ok(Some(
+                    "Ce serveur utilise à nouveau son propre livre de citations.".into(),
+                ))
+            }
+            e => Err(anyhow!("unknown command {}", e)),
+        }
+    }
+
+    async fn trigger_daily(
+        &self,
+        interaction: &ApplicationCommandInteraction,
+        command: &CommandDataOption,
+    ) -> Result<Option<CommandResponse>, Error> {
+        let guild_id = interaction
+            .guild_id
+            .ok_or_else(|| anyhow!("quotes are only available in a guild"))?
+            .0;
+        let sub_command = command
+            .options
+            .first()
+            .ok_or_else(|| anyhow!("missing command sub option"))?;
+        if !is_guild_admin(interaction.member.as_ref()) {
+            return Ok(Some(NOT_GUILD_ADMIN.into()));
+        }
+
+        match sub_command.name.as_str() {
+            "set" => {
+                let channel_id = match find_option(&sub_command.options, "channel") {
+                    Some(CommandDataOptionValue::Channel(c)) => c.id.0,
+                    _ => return Err(anyhow!("missing channel option")),
+                };
+                let post_time = match find_option(&sub_command.options, "time") {
+                    Some(CommandDataOptionValue::String(s)) => {
+                        NaiveTime::parse_from_str(s.trim(), "%H:%M").ok()
+                    }
+                    _ => return Err(anyhow!("missing time option")),
+                };
+                let post_time = match post_time {
+                    Some(t) => t,
+                    None => return Ok(Some("Heure invalide, utilisez le format HH:MM.".into())),
+                };
+                let timezone = match find_option(&sub_command.options, "timezone") {
+                    Some(CommandDataOptionValue::String(s)) => s.trim().to_string(),
+                    _ => DEFAULT_TIMEZONE.to_string(),
+                };
+                let tz = match timezone.parse::<Tz>() {
+                    Ok(tz) => tz,
+                    Err(_) => {
+                        return Ok(Some(
+                            format!("Fuseau horaire inconnu : {}", timezone).into(),
+                        ))
+                    }
+                };
+                let on_this_day = matches!(
+                    find_option(&sub_command.options, "on_this_day"),
+                    Some(CommandDataOptionValue::Boolean(true))
+                );
+
+                let starts_on = first_post_date(Utc::now(), tz, post_time);
+                let config = QuoteOfTheDay {
+                    guild_id,
+                    channel_id,
+                    post_time,
+                    timezone,
+                    on_this_day,
+                    cycle: 0,
+                    starts_on: Some(starts_on),
+                };
+                QuoteOfTheDay::upsert(&self.db_pool, &config, interaction.user.id.0).await?;
+                Ok(Some(
+                    format!(
+                        "Une citation sera postée dans <#{}> tous les jours à {} ({}), à partir du {}.",
+                        config.channel_id,
+                        config.post_time.format("%H:%M"),
+                        config.timezone,
+                        starts_on.format("%d/%m/%Y")
+                    )
+                    .into(),
+                ))
+            }
+            "off" => {
+                let result = QuoteOfTheDay::delete(&self.db_pool, guild_id).await?;
+                if result.rows_affected() == 0 {
+                    return Ok(Some("La citation du jour n'est pas activée.".into()));
+                }
+                Ok(Some("La citation du jour est désactivée.".into()))
+            }
+            e => Err(anyhow!("unknown command {}", e)),
+        }
+    }
+
+    /**
+     * attribute the legacy quotes, written as "<name> quote", to the members of the guild with that name
+     * when a single member has it, so that they are listed and counted by member
+     */
+    async fn attribute_legacy_quotes(
+        &self,
+        ctx: &Context,
+        guild_id: u64,
+    ) -> Result<Option<CommandResponse>, Error> {
+        if QuoteBookLink::find_book_id(&self.db_pool, guild_id).await? != guild_id {
+            return Ok(Some(
+                "Ce serveur utilise le livre de citations d'un autre serveur.".into(),
+            ));
+        }
+
+        let names = Quote::find_unattributed_names(&self.db_pool, guild_id).await?;
+
+        let mut attributed = 0;
+        let mut unmatched = Vec::new();
+        // the search ignores case, only the members named exactly like the author are kept
+        for name in &names {
+            let members = GuildId(guild_id)
+                .search_members(&ctx.http, name, Some(MEMBER_SEARCH_LIMIT))
+                .await?;
+            let mut author_ids: Vec<u64> = members
+                .iter()
+                .filter(|m| &m.user.name == name || m.nick.as_ref() == Some(name))
+                .map(|m| m.user.id.0)
+                .collect();
+            author_ids.sort_unstable();
+            author_ids.dedup();
+            match author_ids.as_slice() {
+                [author_id] => {
+                    attributed +=
+                        Quote::attribute_legacy(&self.db_pool, guild_id, name, *author_id).await?
+                }
+                _ => unmatched.push(name.as_str()),
+            }
+        }
+
+        let mut reply = format!(
+            "{} anciennes citations attribuées à leur auteur.",
+            attributed
+        );
+        if !unmatched.is_empty() {
+            reply.push_str(&format!(
+                "\nAucun membre unique ne porte ces noms : {}",
+                unmatched.join(", ")
+            ));
+        }
+        Ok(Some(reply.into()))
+    }
+
+    async fn trigger_book_info(&self, guild_id: u64) -> Result<Option<CommandResponse>, Error> {
+        let book_id = QuoteBookLink::find_book_id(&self.db_pool, guild_id).await?;
+        let mut lines = vec![if book_id == guild_id {
+            "Ce serveur utilise son propre livre de citations.".to_string()
+        } else {
+            format!(
+                "Ce serveur utilise le livre de citations du serveur {}.",
+                book_id
+            )
+        }];
+        let guild_ids = QuoteBookLink::find_guild_ids(&self.db_pool, book_id).await?;
+        if !guild_ids.is_empty() {
+            let guild_ids: Vec<String> = guild_ids.iter().map(|g| g.to_string()).collect();
+            lines.push(format!("Serveurs liés : {}", guild_ids.join(", ")));
+        }
+        Ok(Some(lines.join("\n").into()))
+    }
 }
 
 #[async_trait]
@@ -97,6 +838,7 @@ impl SlashCommand for QuoteCommand {
         command
             .name("quote")
             .description("Citations")
+            .dm_permission(false)
             .create_option(|option| {
                 option
                     .name("get")
@@ -108,6 +850,21 @@ impl SlashCommand for QuoteCommand {
                             .description("id")
                             .kind(CommandOptionType::String)
                             .required(true)
+                            .set_autocomplete(true)
+                    })
+            })
+            .create_option(|option| {
+                option
+                    .name("card")
+                    .description("image d'une citation, à partager")
+                    .kind(CommandOptionType::SubCommand)
+                    .create_sub_option(|sub_option| {
+                        sub_option
+                            .name("id")
+                            .description("id")
+                            .kind(CommandOptionType::String)
+                            .required(true)
+                            .set_autocomplete(true)
                     })
             })
             .create_option(|option| {
@@ -118,9 +875,14 @@ impl SlashCommand for QuoteCommand {
                     .create_sub_option(|sub_option| {
                         sub_option
                             .name("terms")
-                            .description("mots clés")
+                            .description("mots clés, \"expression exacte\", -mot exclu")
                             .kind(CommandOptionType::String)
-                            .required(true)
+                    })
+                    .create_sub_option(|sub_option| {
+                        sub_option
+                            .name("user")
+                            .description("auteur des citations")
+                            .kind(CommandOptionType::User)
                     })
             })
             .create_option(|option| {
@@ -128,19 +890,177 @@ impl SlashCommand for QuoteCommand {
                     .name("random")
                     .description("une citation au hasard")
                     .kind(CommandOptionType::SubCommand)
+                    .create_sub_option(|sub_option| {
+                        sub_option
+                            .name("user")
+                            .description("auteur de la citation")
+                            .kind(CommandOptionType::User)
+                    })
+                    .create_sub_option(|sub_option| {
+                        sub_option
+                            .name("weighted")
+                            .description("favoriser les citations les mieux notées")
+                            .kind(CommandOptionType::Boolean)
+                    })
+            })
+            .create_option(|option| {
+                option
+                    .name("top")
+                    .description("les citations les mieux notées")
+                    .kind(CommandOptionType::SubCommand)
+            })
+            .create_option(|option| {
+                option
+                    .name("flop")
+                    .description("les citations les moins bien notées")
+                    .kind(CommandOptionType::SubCommand)
             })
             .create_option(|option| {
                 option
                     .name("count")
                     .description("combien de citations il y a dans la base de données")
                     .kind(CommandOptionType::SubCommand)
+                    .create_sub_option(|sub_option| {
+                        sub_option
+                            .name("user")
+                            .description("auteur des citations")
+                            .kind(CommandOptionType::User)
+                    })
+            })
+            .create_option(|option| {
+                option
+                    .name("edit")
+                    .description("corriger le texte d'une citation")
+                    .kind(CommandOptionType::SubCommand)
+                    .create_sub_option(|sub_option| {
+                        sub_option
+                            .name("id")
+                            .description("id")
+                            .kind(CommandOptionType::String)
+                            .required(true)
+                            .set_autocomplete(true)
+                    })
+                    .create_sub_option(|sub_option| {
+                        sub_option
+                            .name("text")
+                            .description("nouveau texte")
+                            .kind(CommandOptionType::String)
+                            .required(true)
+                    })
+            })
+            .create_option(|option| {
+                option
+                    .name("delete")
+                    .description("supprimer une citation")
+                    .kind(CommandOptionType::SubCommand)
+                    .create_sub_option(|sub_option| {
+                        sub_option
+                            .name("id")
+                            .description("id")
+                            .kind(CommandOptionType::String)
+                            .required(true)
+                            .set_autocomplete(true)
+                    })
+            })
+            .create_option(|option| {
+                option
+                    .name("book")
+                    .description("livre de citations du serveur")
+                    .kind(CommandOptionType::SubCommandGroup)
+                    .create_sub_option(|sub_option| {
+                        sub_option
+                            .name("info")
+                            .description("quel livre de citations utilise ce serveur")
+                            .kind(CommandOptionType::SubCommand)
+                    })
+                    .create_sub_option(|sub_option| {
+                        sub_option
+                            .name("share")
+                            .description("créer un code pour qu'un autre serveur rejoigne le livre de citations de ce serveur")
+                            .kind(CommandOptionType::SubCommand)
+                    })
+                    .create_sub_option(|sub_option| {
+                        sub_option
+                            .name("join")
+                            .description("utiliser le livre de citations d'un autre serveur")
+                            .kind(CommandOptionType::SubCommand)
+                            .create_sub_option(|o| {
+                                o.name("code")
+                                    .description("code donné par /quote book share sur l'autre serveur")
+                                    .kind(CommandOptionType::String)
+                                    .required(true)
+                            })
+                    })
+                    .create_sub_option(|sub_option| {
+                        sub_option
+                            .name("authors")
+                            .description("attribuer les anciennes citations aux membres qui portent le nom de leur auteur")
+                            .kind(CommandOptionType::SubCommand)
+                    })
+                    .create_sub_option(|sub_option| {
+                        sub_option
+                            .name("unlink")
+                            .description("revenir au livre de citations propre à ce serveur")
+                            .kind(CommandOptionType::SubCommand)
+                    })
+            })
+            .create_option(|option| {
+                option
+                    .name("daily")
+                    .description("citation du jour")
+                    .kind(CommandOptionType::SubCommandGroup)
+                    .create_sub_option(|sub_option| {
+                        sub_option
+                            .name("set")
+                            .description("poster une citation tous les jours")
+                            .kind(CommandOptionType::SubCommand)
+                            .create_sub_option(|o| {
+                                o.name("channel")
+                                    .description("salon où poster la citation")
+                                    .kind(CommandOptionType::Channel)
+                                    .required(true)
+                            })
+                            .create_sub_option(|o| {
+                                o.name("time")
+                                    .description("heure de la citation (HH:MM)")
+                                    .kind(CommandOptionType::String)
+                                    .required(true)
+                            })
+                            .create_sub_option(|o| {
+                                o.name("timezone")
+                                    .description("fuseau horaire, Europe/Paris par défaut")
+                                    .kind(CommandOptionType::String)
+                            })
+                            .create_sub_option(|o| {
+                                o.name("on_this_day")
+                                    .description(
+                                        "préférer les citations ajoutées le même jour les années précédentes",
+                                    )
+                                    .kind(CommandOptionType::Boolean)
+                            })
+                    })
+                    .create_sub_option(|sub_option| {
+                        sub_option
+                            .name("off")
+                            .description("ne plus poster de citation du jour")
+                            .kind(CommandOptionType::SubCommand)
+                    })
             });
     }
 
+    fn custom_id_prefixes(&self) -> &'static [&'static str] {
+        &[QUOTE_PREFIX]
+    }
+
+    fn overflow(&self) -> Overflow {
+        Overflow::Attachment
+    }
+
     async fn handle(
         &self,
+        ctx: &Context,
         interaction: &ApplicationCommandInteraction,
-    ) -> Result<Option<String>, Error> {
+    ) -> Result<Option<CommandResponse>, Error> {
         if interaction.data.name != "quote" {
             return Ok(None);
         }
@@ -148,33 +1068,308 @@ impl SlashCommand for QuoteCommand {
         let command = interaction
             .data
             .options
-            .get(0)
+            .first()
             .ok_or_else(|| anyhow!("missing command option"))?;
 
         match command.name.as_str() {
-            "get" => self.trigger_get(command).await,
-            "find" => self.trigger_find(command).await,
-            "random" => self.trigger_random().await,
-            "count" => self.trigger_count().await,
+            "book" => return self.trigger_book(ctx, interaction, command).await,
+            "daily" => return self.trigger_daily(interaction, command).await,
+            _ => {}
+        }
+
+        let book_id = book_id(&self.db_pool, interaction.guild_id).await?;
+        match command.name.as_str() {
+            "get" => self.trigger_get(ctx, book_id, command).await,
+            "card" => self.trigger_card(ctx, book_id, command).await,
+            "find" => self.trigger_find(book_id, command).await,
+            "random" => self.trigger_random(ctx, book_id, command).await,
+            "count" => self.trigger_count(book_id, command).await,
+            "top" => self.trigger_ranking(book_id, true).await,
+            "flop" => self.trigger_ranking(book_id, false).await,
+            "edit" => self.trigger_edit(book_id, interaction, command).await,
+            "delete" => self.trigger_delete(book_id, interaction, command).await,
             e => Err(anyhow!("unknown command {}", e)),
         }
     }
+
+    async fn handle_component(
+        &self,
+        ctx: &Context,
+        interaction: &MessageComponentInteraction,
+    ) -> Result<Option<CommandResponse>, Error> {
+        let (_, args) = parse_custom_id(&interaction.data.custom_id);
+        // a custom id which cannot be read still gets an answer, the click would fail otherwise
+        let number = match args.get(1).and_then(|n| n.parse::<i64>().ok()) {
+            Some(n) => n,
+            None => {
+                reply_ephemeral(ctx, interaction, INVALID_NUMBER).await?;
+                return Ok(None);
+            }
+        };
+        match args.as_slice() {
+            [action, _] if *action == UNDO_ACTION => {
+                self.undo_delete(ctx, interaction, number).await
+            }
+            [action, _, value] if *action == VOTE_ACTION => {
+                let value = match value.parse::<i8>() {
+                    Ok(UP_VOTE) => UP_VOTE,
+                    _ => DOWN_VOTE,
+                };
+                self.vote(ctx, interaction, number, value).await
+            }
+            _ => Err(anyhow!("unknown quote action")),
+        }
+    }
+
+    async fn autocomplete(
+        &self,
+        interaction: &AutocompleteInteraction,
+    ) -> Result<Option<Vec<Choice>>, Error> {
+        if interaction.data.name != "quote" {
+            return Ok(None);
+        }
+
+        let book_id = book_id(&self.db_pool, interaction.guild_id).await?;
+        let typed = focused_value(&interaction.data.options)
+            .unwrap_or_default()
+            .trim();
+        let quotes = if typed.chars().all(|c| c.is_ascii_digit()) {
+            Quote::find_by_number_prefix(&self.db_pool, book_id, typed, MAX_CHOICES).await?
+        } else {
+            Quote::search(
+                &self.db_pool,
+                book_id,
+                &SearchQuery::parse(typed),
+                None,
+                MAX_CHOICES,
+            )
+            .await?
+        };
+
+        let choices = quotes
+            .iter()
+            .take(MAX_CHOICES)
+            .map(|q| choice(&QuoteCommand::format_quote(q), &q.number.to_string()))
+            .collect();
+        Ok(Some(choices))
+    }
+}
+
+/**
+ * the local date whose quote is due, once the posting time of the day has passed
+ */
+fn due_date(now: DateTime<Utc>, timezone: Tz, post_time: NaiveTime) -> Option<NaiveDate> {
+    let local = now.with_timezone(&timezone);
+    (local.time() >= post_time).then(|| local.date_naive())
+}
+
+/**
+ * the first local date to post on, tomorrow if the posting time of today has already passed
+ */
+fn first_post_date(now: DateTime<Utc>, timezone: Tz, post_time: NaiveTime) -> NaiveDate {
+    let today = now.with_timezone(&timezone).date_naive();
+    match due_date(now, timezone, post_time) {
+        Some(_) => today.succ_opt().unwrap_or(today),
+        None => today,
+    }
+}
+
+/**
+ * whether Discord refused a message because the channel was deleted or the bot may no longer post in it
+ */
+fn is_unreachable_channel(error: &Error) -> bool {
+    match error.downcast_ref::<SerenityError>() {
+        Some(SerenityError::Http(e)) => matches!(
+            e.status_code(),
+            Some(StatusCode::FORBIDDEN | StatusCode::NOT_FOUND)
+        ),
+        _ => false,
+    }
+}
+
+/// posts the quote of the day of each configured guild
+pub struct QuoteOfTheDayJob {
+    pub db_pool: Arc<MySqlPool>,
+}
+
+impl QuoteOfTheDayJob {
+    async fn post(
+        &self,
+        http: &Http,
+        config: &mut QuoteOfTheDay,
+        date: NaiveDate,
+    ) -> Result<(), Error> {
+        if config.starts_on.is_some_and(|s| date < s)
+            || config.has_posted(&self.db_pool, date).await?
+        {
+            return Ok(());
+        }
+
+        let book_id = QuoteBookLink::find_book_id(&self.db_pool, config.guild_id).await?;
+        let mut quote = None;
+        if config.on_this_day {
+            quote = config
+                .pick_quote(&self.db_pool, book_id, Some(date))
+                .await?;
+        }
+        let on_this_day = quote.is_some();
+        if quote.is_none() {
+            quote = config.pick_quote(&self.db_pool, book_id, None).await?;
+        }
+        if quote.is_none() && Quote::count(&self.db_pool, book_id, None).await? > 0 {
+            config.start_new_cycle(&self.db_pool).await?;
+            quote = config.pick_quote(&self.db_pool, book_id, None).await?;
+        }
+        let quote = match quote {
+            Some(q) => q,
+            None => return Ok(()),
+        };
+        if !config.claim_day(&self.db_pool, date, quote.id).await? {
+            return Ok(());
+        }
+
+        let title = match quote.quoted_at.or(quote.created_at) {
+            Some(quoted_at) if on_this_day => {
+                let years = date.year() - quoted_at.year();
+                format!(
+                    "Ce jour-là, il y a {} an{}",
+                    years,
+                    if years > 1 { "s" } else { "" }
+                )
+            }
+            _ => "Citation du jour".to_string(),
+        };
+        let result = self.send(http, config, &quote, title).await;
+        match &result {
+            Ok(()) => {}
+            // trying again every minute would not help, the guild has to configure another channel
+            Err(e) if is_unreachable_channel(e) => {
+                QuoteOfTheDay::delete(&self.db_pool, config.guild_id).await?;
+                println!(
+                    "quote of the day of guild {} disabled, channel {} unreachable",
+                    config.guild_id, config.channel_id
+                );
+            }
+            // the day was claimed before sending, the next run tries again
+            Err(_) => {
+                if let Err(e) = config.release_day(&self.db_pool, date).await {
+                    println!("error while releasing the quote of the day : {}", e);
+                }
+            }
+        }
+        result
+    }
+
+    async fn send(
+        &self,
+        http: &Http,
+        config: &QuoteOfTheDay,
+        quote: &Quote,
+        title: String,
+    ) -> Result<(), Error> {
+        let (mut embed, components) = quote_message(&self.db_pool, http, quote).await?;
+        embed.title(title);
+        ChannelId(config.channel_id)
+            .send_message(http, |message| {
+                message.set_embed(embed).set_components(components)
+            })
+            .await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Job for QuoteOfTheDayJob {
+    fn kind(&self) -> &'static str {
+        QUOTE_OF_THE_DAY_JOB_KIND
+    }
+
+    async fn run(&self, http: &Http, _payload: &str) -> Result<(), Error> {
+        for mut config in QuoteOfTheDay::find_all(&self.db_pool).await? {
+            let timezone = match config.timezone.parse::<Tz>() {
+                Ok(tz) => tz,
+                Err(e) => {
+                    println!("invalid timezone for guild {} : {}", config.guild_id, e);
+                    continue;
+                }
+            };
+            if let Some(date) = due_date(Utc::now(), timezone, config.post_time) {
+                if let Err(e) = self.post(http, &mut config, date).await {
+                    println!(
+                        "error while posting the quote of the day of guild {} : {}",
+                        config.guild_id, e
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// context menu listing the quotes of a member
+pub struct QuoteUserCommand {
+    pub db_pool: Arc<MySqlPool>,
+}
+
+#[async_trait]
+impl SlashCommand for QuoteUserCommand {
+    fn register(&self, command: &mut CreateApplicationCommand) {
+        command
+            .name("Quotes de cet utilisateur")
+            .kind(CommandType::User)
+            .dm_permission(false);
+    }
+
+    fn overflow(&self) -> Overflow {
+        Overflow::Attachment
+    }
+
+    async fn handle(
+        &self,
+        _ctx: &Context,
+        interaction: &ApplicationCommandInteraction,
+    ) -> Result<Option<CommandResponse>, Error> {
+        if interaction.data.name != "Quotes de cet utilisateur" {
+            return Ok(None);
+        }
+
+        let user = interaction
+            .data
+            .resolved
+            .users
+            .values()
+            .next()
+            .ok_or_else(|| anyhow!("users map is empty"))?;
+
+        let book_id = book_id(&self.db_pool, interaction.guild_id).await?;
+        let quotes =
+            Quote::find_by_author(&self.db_pool, book_id, user.id.0, SEARCH_RESULTS_LIMIT).await?;
+        if quotes.is_empty() {
+            return Ok(Some(format!("Pas de citation de {}.", user.name).into()));
+        }
+        Ok(Some(CommandResponse::Pages(quote_pages(&quotes, None))))
+    }
 }
 
 pub struct QuoteAddCommand {
     pub db_pool: Arc<MySqlPool>,
+    pub archive: Arc<QuoteArchive>,
 }
 
 #[async_trait]
 impl SlashCommand for QuoteAddCommand {
     fn register(&self, command: &mut CreateApplicationCommand) {
-        command.name("Add Quote").kind(CommandType::Message);
+        command
+            .name("Add Quote")
+            .kind(CommandType::Message)
+            .dm_permission(false);
     }
 
     async fn handle(
         &self,
+        _ctx: &Context,
         interaction: &ApplicationCommandInteraction,
-    ) -> Result<Option<String>, Error> {
+    ) -> Result<Option<CommandResponse>, Error> {
         if interaction.data.name != "Add Quote" {
             return Ok(None);
         }
@@ -187,10 +1382,322 @@ impl SlashCommand for QuoteAddCommand {
             .next()
             .ok_or_else(|| anyhow!("messages map is empty"))?;
 
-        let quote = format!("<{}> {}", message.author.name, message.content);
-        let i = Quote::save(&self.db_pool, &quote).await?;
-        let reply = format!("Quote {} ajoutée : {}", i, quote);
+        let mut quote = NewQuote::conversation(
+            vec![message_line(message)],
+            interaction.guild_id.map(|g| g.0),
+            message.channel_id.0,
+            interaction.user.id.0,
+        )
+        .ok_or_else(|| anyhow!("empty quote"))?;
+        // a single message needs no lines
+        quote.lines.clear();
+        let book_id = book_id(&self.db_pool, interaction.guild_id).await?;
+        quote.attachments = archive_attachments(&self.archive, book_id, &[message]).await;
+        let i = save_quote(&self.db_pool, &self.archive, book_id, &quote).await?;
+        let reply = format!(
+            "Quote {} ajoutée : <{}> {}",
+            i, message.author.name, quote.quote
+        );
+
+        Ok(Some(reply.into()))
+    }
+}
+
+/**
+ * the text of a message with its embeds summarized and its files listed
+ */
+fn message_text(message: &Message) -> String {
+    let mut parts: Vec<String> = Vec::new();
+    if !message.content.trim().is_empty() {
+        parts.push(message.content.clone());
+    }
+    parts.extend(message.embeds.iter().filter_map(summarize_embed));
+    parts.extend(
+        message
+            .attachments
+            .iter()
+            .map(|a| format!("📎 {}", a.filename)),
+    );
+    parts.join("\n")
+}
+
+/**
+ * archive the files of quoted messages, those too large or failing to download are only listed in the text
+ */
+async fn archive_attachments(
+    archive: &QuoteArchive,
+    book_id: u64,
+    messages: &[&Message],
+) -> Vec<QuoteAttachment> {
+    let mut archived = Vec::new();
+    for message in messages {
+        for attachment in &message.attachments {
+            if attachment.size > MAX_ARCHIVED_FILE_SIZE {
+                continue;
+            }
+            match archive.store(book_id, message.id.0, attachment).await {
+                Ok(path) => archived.push(QuoteAttachment {
+                    message_id: message.id.0,
+                    filename: attachment.filename.clone(),
+                    content_type: attachment.content_type.clone(),
+                    path,
+                    size: attachment.size,
+                }),
+                Err(e) => println!(
+                    "error while archiving attachment {} : {}",
+                    attachment.url, e
+                ),
+            }
+        }
+    }
+    archived
+}
+
+/**
+ * save a quote, removing its archived files if it could not be saved so that none is left without quote
+ */
+async fn save_quote(
+    pool: &MySqlPool,
+    archive: &QuoteArchive,
+    book_id: u64,
+    quote: &NewQuote,
+) -> Result<i64, Error> {
+    let result = Quote::save(pool, book_id, quote).await;
+    if result.is_err() {
+        for attachment in &quote.attachments {
+            if let Err(e) = archive.remove(&attachment.path).await {
+                println!(
+                    "error while removing archived file {} : {}",
+                    attachment.path, e
+                );
+            }
+        }
+    }
+    result
+}
+
+fn message_line(message: &Message) -> NewQuoteLine {
+    NewQuoteLine {
+        author_id: message.author.id.0,
+        author_name: message.author.name.clone(),
+        message_id: message.id.0,
+        content: message_text(message),
+        posted_at: Utc
+            .timestamp_opt(message.timestamp.unix_timestamp(), 0)
+            .single(),
+    }
+}
+
+/// first message of a conversation being quoted
+#[derive(Clone)]
+pub struct QuoteCaptureStart {
+    channel_id: ChannelId,
+    message_id: MessageId,
+}
+
+/// context menu marking the first message of a conversation to quote
+pub struct QuoteStartCommand {
+    /// keyed by the id of the member capturing the conversation
+    pub captures: Arc<StateStore<QuoteCaptureStart>>,
+}
+
+#[async_trait]
+impl SlashCommand for QuoteStartCommand {
+    fn register(&self, command: &mut CreateApplicationCommand) {
+        command
+            .name("Start Quote")
+            .kind(CommandType::Message)
+            .dm_permission(false);
+    }
+
+    async fn handle(
+        &self,
+        _ctx: &Context,
+        interaction: &ApplicationCommandInteraction,
+    ) -> Result<Option<CommandResponse>, Error> {
+        if interaction.data.name != "Start Quote" {
+            return Ok(None);
+        }
+
+        let message = interaction
+            .data
+            .resolved
+            .messages
+            .values()
+            .next()
+            .ok_or_else(|| anyhow!("messages map is empty"))?;
+        self.captures.set(
+            &interaction.user.id.to_string(),
+            QuoteCaptureStart {
+                channel_id: message.channel_id,
+                message_id: message.id,
+            },
+        );
+
+        Ok(Some(
+            "Début de la citation enregistré, utilisez « End Quote » sur son dernier message."
+                .into(),
+        ))
+    }
+}
+
+/// context menu quoting the conversation from the message marked with "Start Quote" to this one
+pub struct QuoteEndCommand {
+    pub db_pool: Arc<MySqlPool>,
+    pub archive: Arc<QuoteArchive>,
+    pub captures: Arc<StateStore<QuoteCaptureStart>>,
+}
+
+#[async_trait]
+impl SlashCommand for QuoteEndCommand {
+    fn register(&self, command: &mut CreateApplicationCommand) {
+        command
+            .name("End Quote")
+            .kind(CommandType::Message)
+            .dm_permission(false);
+    }
+
+    async fn handle(
+        &self,
+        ctx: &Context,
+        interaction: &ApplicationCommandInteraction,
+    ) -> Result<Option<CommandResponse>, Error> {
+        if interaction.data.name != "End Quote" {
+            return Ok(None);
+        }
+
+        let end = interaction
+            .data
+            .resolved
+            .messages
+            .values()
+            .next()
+            .ok_or_else(|| anyhow!("messages map is empty"))?;
+        let start = match self.captures.get(&interaction.user.id.to_string()) {
+            Some(s) => s,
+            None => {
+                return Ok(Some(
+                    "Utilisez d'abord « Start Quote » sur le premier message.".into(),
+                ))
+            }
+        };
+        if start.channel_id != end.channel_id {
+            return Ok(Some(
+                "Le premier et le dernier message doivent être dans le même salon.".into(),
+            ));
+        }
+        let (first_id, last_id) = if start.message_id <= end.id {
+            (start.message_id, end.id)
+        } else {
+            (end.id, start.message_id)
+        };
+
+        let mut messages = vec![end.channel_id.message(&ctx.http, first_id).await?];
+        if first_id != last_id {
+            messages.extend(
+                end.channel_id
+                    // the first message already counts as one
+                    .messages(&ctx.http, |r| {
+                        r.after(first_id)
+                            .limit(MAX_CONVERSATION_MESSAGES as u64 - 1)
+                    })
+                    .await?,
+            );
+        }
+        messages.sort_by_key(|m| m.id);
+        messages.dedup_by_key(|m| m.id);
+        if !messages.iter().any(|m| m.id == last_id) {
+            return Ok(Some(
+                format!(
+                    "Trop de messages, une citation en contient au plus {}.",
+                    MAX_CONVERSATION_MESSAGES
+                )
+                .into(),
+            ));
+        }
+
+        let messages: Vec<&Message> = messages
+            .iter()
+            .filter(|m| m.id <= last_id && !message_text(m).is_empty())
+            .collect();
+        let lines = messages.iter().map(|m| message_line(m)).collect();
+        let mut quote = match NewQuote::conversation(
+            lines,
+            interaction.guild_id.map(|g| g.0),
+            end.channel_id.0,
+            interaction.user.id.0,
+        ) {
+            Some(q) => q,
+            None => return Ok(Some("Ces messages ne contiennent pas de texte.".into())),
+        };
+        let book_id = book_id(&self.db_pool, interaction.guild_id).await?;
+        quote.attachments = archive_attachments(&self.archive, book_id, &messages).await;
+        let number = save_quote(&self.db_pool, &self.archive, book_id, &quote).await?;
+        self.captures.remove(&interaction.user.id.to_string());
 
-        Ok(Some(reply))
+        Ok(Some(
+            format!("Quote {} ajoutée ({} messages).", number, quote.lines.len()).into(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::db::quote_line::QuoteLine;
+    use chrono::{NaiveDate, NaiveTime, TimeZone, Utc};
+
+    fn line(author_name: &str, content: &str) -> QuoteLine {
+        QuoteLine {
+            position: 0,
+            author_id: 1,
+            author_name: author_name.to_string(),
+            message_id: 1,
+            content: content.to_string(),
+            posted_at: None,
+        }
+    }
+
+    #[test]
+    fn dialogue() {
+        let lines = vec![line("alice", "tu viens ?"), line("bob", "non")];
+        assert_eq!(
+            super::dialogue(&lines),
+            "**alice** : tu viens ?\n**bob** : non"
+        );
+    }
+
+    #[test]
+    fn due_date_in_local_time() {
+        let post_time = NaiveTime::from_hms_opt(9, 0, 0).unwrap();
+        let paris = chrono_tz::Europe::Paris;
+
+        // 7:30 UTC is 9:30 in Paris during summer time
+        let now = Utc.with_ymd_and_hms(2023, 7, 14, 7, 30, 0).unwrap();
+        assert_eq!(
+            super::due_date(now, paris, post_time),
+            NaiveDate::from_ymd_opt(2023, 7, 14)
+        );
+        assert_eq!(super::due_date(now, chrono_tz::UTC, post_time), None);
+
+        // 23:30 UTC is already the next day in Paris
+        let now = Utc.with_ymd_and_hms(2023, 7, 14, 23, 30, 0).unwrap();
+        assert_eq!(super::due_date(now, paris, post_time), None);
+    }
+
+    #[test]
+    fn first_post_date_after_post_time() {
+        let post_time = NaiveTime::from_hms_opt(9, 0, 0).unwrap();
+        let paris = chrono_tz::Europe::Paris;
+
+        let now = Utc.with_ymd_and_hms(2023, 7, 14, 6, 30, 0).unwrap();
+        assert_eq!(
+            super::first_post_date(now, paris, post_time),
+            NaiveDate::from_ymd_opt(2023, 7, 14).unwrap()
+        );
+        let now = Utc.with_ymd_and_hms(2023, 7, 14, 7, 30, 0).unwrap();
+        assert_eq!(
+            super::first_post_date(now, paris, post_time),
+            NaiveDate::from_ymd_opt(2023, 7, 15).unwrap()
+        );
     }
 }
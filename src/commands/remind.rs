@@ -0,0 +1,124 @@
+use crate::commands::SlashCommand;
+use crate::db::scheduled_job::ScheduledJob;
+use crate::output::CommandResponse;
+use crate::scheduler::Job;
+use anyhow::anyhow;
+use anyhow::Error;
+use chrono::Duration;
+use chrono::Utc;
+use chrono_humanize::HumanTime;
+use serde::Deserialize;
+use serde::Serialize;
+use serenity::async_trait;
+use serenity::builder::CreateApplicationCommand;
+use serenity::client::Context;
+use serenity::http::Http;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::model::id::ChannelId;
+use serenity::model::id::UserId;
+use serenity::model::prelude::command::CommandOptionType;
+use serenity::model::prelude::interaction::application_command::CommandDataOptionValue;
+use sqlx::MySqlPool;
+use std::sync::Arc;
+
+const REMINDER_JOB_KIND: &str = "reminder";
+const MAX_REMINDER_MINUTES: u64 = 60 * 24 * 30;
+
+#[derive(Serialize, Deserialize)]
+struct Reminder {
+    channel_id: u64,
+    user_id: u64,
+    message: String,
+}
+
+pub struct RemindCommand {
+    pub db_pool: Arc<MySqlPool>,
+}
+
+#[async_trait]
+impl SlashCommand for RemindCommand {
+    fn register(&self, command: &mut CreateApplicationCommand) {
+        command
+            .name("remind")
+            .description("Programmer un rappel")
+            .create_option(|option| {
+                option
+                    .name("minutes")
+                    .description("Dans combien de minutes ?")
+                    .kind(CommandOptionType::Integer)
+                    .min_int_value(1)
+                    .max_int_value(MAX_REMINDER_MINUTES)
+                    .required(true)
+            })
+            .create_option(|option| {
+                option
+                    .name("message")
+                    .description("De quoi faut-il se souvenir ?")
+                    .kind(CommandOptionType::String)
+                    .required(true)
+            });
+    }
+
+    async fn handle(
+        &self,
+        _ctx: &Context,
+        interaction: &ApplicationCommandInteraction,
+    ) -> Result<Option<CommandResponse>, Error> {
+        if interaction.data.name != "remind" {
+            return Ok(None);
+        }
+
+        let mut minutes: Option<i64> = None;
+        let mut message: Option<String> = None;
+        for option in &interaction.data.options {
+            match (option.name.as_str(), &option.resolved) {
+                ("minutes", Some(CommandDataOptionValue::Integer(m))) => minutes = Some(*m),
+                ("message", Some(CommandDataOptionValue::String(m))) => {
+                    message = Some(m.to_string())
+                }
+                _ => return Err(anyhow!("wrong value type for option {}", option.name)),
+            }
+        }
+        let minutes = minutes.ok_or_else(|| anyhow!("missing minutes option"))?;
+        let message = message.ok_or_else(|| anyhow!("missing message option"))?;
+
+        let reminder = Reminder {
+            channel_id: interaction.channel_id.0,
+            user_id: interaction.user.id.0,
+            message,
+        };
+        let run_at = Utc::now() + Duration::minutes(minutes);
+        ScheduledJob::insert_once(
+            &self.db_pool,
+            REMINDER_JOB_KIND,
+            &serde_json::to_string(&reminder)?,
+            run_at,
+        )
+        .await?;
+
+        Ok(Some(
+            format!("Rappel programmé {}", HumanTime::from(run_at)).into(),
+        ))
+    }
+}
+
+pub struct ReminderJob {}
+
+#[async_trait]
+impl Job for ReminderJob {
+    fn kind(&self) -> &'static str {
+        REMINDER_JOB_KIND
+    }
+
+    async fn run(&self, http: &Http, payload: &str) -> Result<(), Error> {
+        let reminder: Reminder = serde_json::from_str(payload)?;
+        // only the reminded user is pinged, whatever mentions the message contains
+        ChannelId(reminder.channel_id)
+            .send_message(http, |m| {
+                m.content(format!("⏰ <@{}> {}", reminder.user_id, reminder.message))
+                    .allowed_mentions(|am| am.users(vec![UserId(reminder.user_id)]))
+            })
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,235 @@
+use crate::commands::find_option;
+use crate::commands::SlashCommand;
+use crate::db::watched_show::WatchedShow;
+use crate::output::split_content;
+use crate::output::CommandResponse;
+use crate::output::MESSAGE_MAX_LENGTH;
+use crate::utils::tvmaze::build_episode_line;
+use crate::utils::tvmaze::fetch_schedule;
+use crate::utils::tvmaze::TVMazeEpisode;
+use anyhow::Error;
+use chrono::DateTime;
+use chrono::NaiveDate;
+use chrono::Utc;
+use chrono_tz::Tz;
+use serenity::async_trait;
+use serenity::builder::CreateApplicationCommand;
+use serenity::client::Context;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::model::prelude::command::CommandOptionType;
+use serenity::model::prelude::interaction::application_command::CommandDataOptionValue;
+use sqlx::MySqlPool;
+use std::collections::BTreeMap;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+const DEFAULT_COUNTRY: &str = "FR";
+const UNKNOWN_NETWORK: &str = "Autres";
+
+/// time zone of the schedules of a country, the one of its main channels for countries spanning several
+const COUNTRY_TIMEZONES: &[(&str, Tz)] = &[
+    ("AU", chrono_tz::Australia::Sydney),
+    ("BE", chrono_tz::Europe::Brussels),
+    ("BR", chrono_tz::America::Sao_Paulo),
+    ("CA", chrono_tz::America::Toronto),
+    ("CH", chrono_tz::Europe::Zurich),
+    ("DE", chrono_tz::Europe::Berlin),
+    ("DK", chrono_tz::Europe::Copenhagen),
+    ("ES", chrono_tz::Europe::Madrid),
+    ("FI", chrono_tz::Europe::Helsinki),
+    ("FR", chrono_tz::Europe::Paris),
+    ("GB", chrono_tz::Europe::London),
+    ("IE", chrono_tz::Europe::Dublin),
+    ("IT", chrono_tz::Europe::Rome),
+    ("JP", chrono_tz::Asia::Tokyo),
+    ("KR", chrono_tz::Asia::Seoul),
+    ("NL", chrono_tz::Europe::Amsterdam),
+    ("NO", chrono_tz::Europe::Oslo),
+    ("NZ", chrono_tz::Pacific::Auckland),
+    ("PL", chrono_tz::Europe::Warsaw),
+    ("SE", chrono_tz::Europe::Stockholm),
+    ("US", chrono_tz::America::New_York),
+];
+
+/**
+ * the current date in a country, UTC's for the countries we do not know
+ */
+fn today_in(country: &str, now: DateTime<Utc>) -> NaiveDate {
+    let timezone = COUNTRY_TIMEZONES
+        .iter()
+        .find(|(code, _)| *code == country)
+        .map(|(_, timezone)| *timezone)
+        .unwrap_or(chrono_tz::UTC);
+    now.with_timezone(&timezone).date_naive()
+}
+
+/**
+ * episodes grouped by network then sorted by air time, the shows followed by the user are highlighted
+ */
+fn format_schedule(episodes: &[TVMazeEpisode], watched_shows: &HashSet<u64>) -> String {
+    let mut networks: BTreeMap<&str, Vec<&TVMazeEpisode>> = BTreeMap::new();
+    for episode in episodes {
+        let network = episode
+            .show
+            .as_ref()
+            .and_then(|s| s.channel())
+            .map(|n| n.name.as_str())
+            .unwrap_or(UNKNOWN_NETWORK);
+        networks.entry(network).or_default().push(episode);
+    }
+
+    let mut sections: Vec<String> = Vec::new();
+    for (network, mut episodes) in networks {
+        episodes.sort_by(|a, b| a.airtime.cmp(&b.airtime));
+        let mut lines = vec![format!("**{}**", network)];
+        for episode in episodes {
+            let show_name = episode
+                .show
+                .as_ref()
+                .map(|s| s.name.as_str())
+                .unwrap_or_default();
+            let line = format!(
+                "{} {} - {}",
+                episode.airtime.as_deref().unwrap_or("--:--"),
+                show_name,
+                build_episode_line(episode)
+            );
+            let watched = episode
+                .show
+                .as_ref()
+                .map(|s| watched_shows.contains(&s.id))
+                .unwrap_or(false);
+            lines.push(if watched {
+                format!("⭐ **{}**", line)
+            } else {
+                line
+            });
+        }
+        sections.push(lines.join("\n"));
+    }
+    sections.join("\n\n")
+}
+
+/// what is on TV on a given day
+pub struct TVScheduleCommand {
+    pub db_pool: Arc<MySqlPool>,
+}
+
+#[async_trait]
+impl SlashCommand for TVScheduleCommand {
+    fn register(&self, command: &mut CreateApplicationCommand) {
+        command
+            .name("tvschedule")
+            .description("Les programmes télé du jour")
+            .create_option(|option| {
+                option
+                    .name("country")
+                    .description("code du pays, FR par défaut")
+                    .kind(CommandOptionType::String)
+                    .min_length(2)
+                    .max_length(2)
+            })
+            .create_option(|option| {
+                option
+                    .name("date")
+                    .description("date au format AAAA-MM-JJ, aujourd'hui dans le pays par défaut")
+                    .kind(CommandOptionType::String)
+            });
+    }
+
+    async fn handle(
+        &self,
+        _ctx: &Context,
+        interaction: &ApplicationCommandInteraction,
+    ) -> Result<Option<CommandResponse>, Error> {
+        if interaction.data.name != "tvschedule" {
+            return Ok(None);
+        }
+
+        let country = match find_option(&interaction.data.options, "country") {
+            Some(CommandDataOptionValue::String(c)) => c.trim().to_uppercase(),
+            _ => DEFAULT_COUNTRY.to_string(),
+        };
+        let date = match find_option(&interaction.data.options, "date") {
+            Some(CommandDataOptionValue::String(d)) => {
+                match NaiveDate::parse_from_str(d.trim(), "%Y-%m-%d") {
+                    Ok(date) => date,
+                    Err(_) => {
+                        return Ok(Some(
+                            "Date invalide, le format attendu est AAAA-MM-JJ.".into(),
+                        ))
+                    }
+                }
+            }
+            _ => today_in(&country, Utc::now()),
+        };
+
+        let schedule_country = country.clone();
+        let episodes =
+            tokio::task::spawn_blocking(move || fetch_schedule(&schedule_country, date)).await??;
+        if episodes.is_empty() {
+            return Ok(Some(
+                format!("Pas de programme pour {} le {}.", country, date).into(),
+            ));
+        }
+
+        let watched_shows: HashSet<u64> = WatchedShow::find_by_user_or_channel(
+            &self.db_pool,
+            interaction.user.id.0,
+            interaction.channel_id.0,
+        )
+        .await?
+        .iter()
+        .map(|w| w.show_id)
+        .collect();
+
+        let content = format!(
+            "Programmes {} du {}\n\n{}",
+            country,
+            date.format("%d/%m/%Y"),
+            format_schedule(&episodes, &watched_shows)
+        );
+        Ok(Some(CommandResponse::Pages(split_content(
+            &content,
+            MESSAGE_MAX_LENGTH,
+        ))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::tvmaze::TVMazeEpisode;
+    use chrono::{NaiveDate, TimeZone, Utc};
+    use std::collections::HashSet;
+
+    #[test]
+    fn format_schedule() {
+        let episodes: Vec<TVMazeEpisode> = serde_json::from_str(
+            r#"[
+            {"id": 1, "name": "B", "season": 1, "number": 2, "airstamp": null, "airtime": "21:00", "summary": null,
+             "show": {"id": 10, "name": "Série B", "network": {"name": "TF1"}}},
+            {"id": 2, "name": "A", "season": 3, "number": 1, "airstamp": null, "airtime": "20:00", "summary": null,
+             "show": {"id": 11, "name": "Série A", "network": {"name": "TF1"}}},
+            {"id": 3, "name": "C", "season": 1, "number": 1, "airstamp": null, "airtime": "20:30", "summary": null,
+             "show": {"id": 12, "name": "Série C", "network": {"name": "France 2"}}}
+        ]"#,
+        )
+        .unwrap();
+        let watched: HashSet<u64> = [10].into_iter().collect();
+
+        assert_eq!(
+            super::format_schedule(&episodes, &watched),
+            "**France 2**\n20:30 Série C - S01E01 - C (?)\n\n\
+             **TF1**\n20:00 Série A - S03E01 - A (?)\n⭐ **21:00 Série B - S01E02 - B (?)**"
+        );
+    }
+
+    #[test]
+    fn today_in_country() {
+        let now = Utc.with_ymd_and_hms(2023, 1, 1, 3, 0, 0).unwrap();
+        let new_year = NaiveDate::from_ymd_opt(2023, 1, 1).unwrap();
+        assert_eq!(super::today_in("FR", now), new_year);
+        assert_eq!(super::today_in("US", now), new_year.pred_opt().unwrap());
+        assert_eq!(super::today_in("XX", now), new_year);
+    }
+}
@@ -0,0 +1,380 @@
+use crate::autocomplete::choice;
+use crate::autocomplete::focused_value;
+use crate::autocomplete::Choice;
+use crate::commands::can_manage_channels;
+use crate::commands::find_option;
+use crate::commands::SlashCommand;
+use crate::db::watched_show::NextEpisode;
+use crate::db::watched_show::WatchedShow;
+use crate::output::CommandResponse;
+use crate::scheduler::Job;
+use crate::utils::tvmaze::episode_title;
+use crate::utils::tvmaze::fetch_show;
+use crate::utils::tvmaze::find_episode_after;
+use crate::utils::tvmaze::search_show;
+use crate::utils::tvmaze::search_shows;
+use crate::utils::tvmaze::show_id;
+use crate::utils::tvmaze::TVMazeShow;
+use anyhow::anyhow;
+use anyhow::Error;
+use chrono::{DateTime, Utc};
+use chrono_humanize::HumanTime;
+use serenity::async_trait;
+use serenity::builder::CreateApplicationCommand;
+use serenity::client::Context;
+use serenity::http::Http;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::model::application::interaction::autocomplete::AutocompleteInteraction;
+use serenity::model::id::ChannelId;
+use serenity::model::id::UserId;
+use serenity::model::prelude::command::CommandOptionType;
+use serenity::model::prelude::interaction::application_command::CommandDataOption;
+use serenity::model::prelude::interaction::application_command::CommandDataOptionValue;
+use sqlx::MySqlPool;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+pub const WATCH_NOTIFY_JOB_KIND: &str = "watch_notify";
+/// aired episodes are looked for every minute
+pub const WATCH_NOTIFY_SCHEDULE: &str = "0 * * * * *";
+pub const WATCH_REFRESH_JOB_KIND: &str = "watch_refresh";
+/// show metadata is refreshed every six hours
+pub const WATCH_REFRESH_SCHEDULE: &str = "0 0 */6 * * *";
+const MIN_AUTOCOMPLETE_LENGTH: usize = 2;
+
+/**
+ * the episode following the aired one if given, else the next one to air
+ */
+fn next_episode(show: &TVMazeShow, aired: Option<(u64, DateTime<Utc>)>) -> Option<NextEpisode> {
+    find_episode_after(show.episodes(), aired).map(|e| NextEpisode {
+        id: e.id,
+        title: episode_title(e),
+        airstamp: e.airstamp,
+    })
+}
+
+fn describe_next_episode(title: Option<&str>, airstamp: Option<DateTime<Utc>>) -> String {
+    match (title, airstamp) {
+        (Some(title), Some(date)) => format!("{} ({})", title, HumanTime::from(date)),
+        _ => "pas de prochain épisode annoncé".to_string(),
+    }
+}
+
+/**
+ * fetch a show and store its next episode, following the aired one if given, for all its followers
+ */
+async fn refresh_show(
+    pool: &MySqlPool,
+    show_id: u64,
+    aired: Option<(u64, DateTime<Utc>)>,
+) -> Result<(), Error> {
+    let show = tokio::task::spawn_blocking(move || fetch_show(show_id)).await??;
+    WatchedShow::update_show(pool, show_id, &show.name, next_episode(&show, aired)).await?;
+    Ok(())
+}
+
+pub struct WatchCommand {
+    pub db_pool: Arc<MySqlPool>,
+}
+
+impl WatchCommand {
+    async fn trigger_add(
+        &self,
+        interaction: &ApplicationCommandInteraction,
+        command: &CommandDataOption,
+    ) -> Result<Option<CommandResponse>, Error> {
+        let query = match find_option(&command.options, "show") {
+            Some(CommandDataOptionValue::String(s)) => s.trim(),
+            _ => return Err(anyhow!("missing show option")),
+        };
+        let in_channel = interaction.guild_id.is_some()
+            && matches!(
+                find_option(&command.options, "channel"),
+                Some(CommandDataOptionValue::Boolean(true))
+            );
+
+        let (user_id, channel_id) = if in_channel {
+            if !can_manage_channels(interaction.member.as_ref()) {
+                return Ok(Some(
+                    "Il faut pouvoir gérer les salons pour suivre une série dans ce salon.".into(),
+                ));
+            }
+            (None, Some(interaction.channel_id.0))
+        } else {
+            (Some(interaction.user.id.0), None)
+        };
+
+        // the autocompletion gives the id of the chosen show, to tell apart shows with the same name
+        let owned_query = query.to_string();
+        let show = tokio::task::spawn_blocking(move || match show_id(&owned_query) {
+            Some(id) => fetch_show(id).map(Some),
+            None => search_show(&owned_query),
+        })
+        .await??;
+        let show = match show {
+            Some(show) => show,
+            None => return Ok(Some("Pas de résultat".into())),
+        };
+        if WatchedShow::exists(&self.db_pool, show.id, user_id, channel_id).await? {
+            return Ok(Some(format!("{} est déjà suivie.", show.name).into()));
+        }
+
+        let next = next_episode(&show, None);
+        let next_line = describe_next_episode(
+            next.as_ref().map(|e| e.title.as_str()),
+            next.as_ref().and_then(|e| e.airstamp),
+        );
+        let id = WatchedShow::insert(
+            &self.db_pool,
+            show.id,
+            &show.name,
+            user_id,
+            channel_id,
+            next,
+        )
+        .await?;
+        Ok(Some(
+            format!(
+                "Série {} suivie : {}. Prochain épisode : {}",
+                id, show.name, next_line
+            )
+            .into(),
+        ))
+    }
+
+    async fn trigger_list(
+        &self,
+        interaction: &ApplicationCommandInteraction,
+    ) -> Result<Option<CommandResponse>, Error> {
+        let shows = WatchedShow::find_by_user_or_channel(
+            &self.db_pool,
+            interaction.user.id.0,
+            interaction.channel_id.0,
+        )
+        .await?;
+        if shows.is_empty() {
+            return Ok(Some("Aucune série suivie.".into()));
+        }
+
+        let lines: Vec<String> = shows
+            .iter()
+            .map(|s| {
+                let target = match s.channel_id {
+                    Some(c) => format!("<#{}>", c),
+                    None => "en privé".to_string(),
+                };
+                format!(
+                    "{}. {} - {} - {}",
+                    s.id,
+                    s.show_name,
+                    describe_next_episode(s.next_episode_title.as_deref(), s.next_airstamp),
+                    target
+                )
+            })
+            .collect();
+        Ok(Some(lines.join("\n").into()))
+    }
+
+    async fn trigger_remove(
+        &self,
+        interaction: &ApplicationCommandInteraction,
+        command: &CommandDataOption,
+    ) -> Result<Option<CommandResponse>, Error> {
+        let id = match find_option(&command.options, "id") {
+            Some(CommandDataOptionValue::Integer(i)) => *i,
+            _ => return Err(anyhow!("missing id option")),
+        };
+
+        // like adding one, removing a show followed by the channel needs to manage channels
+        let can_manage = can_manage_channels(interaction.member.as_ref());
+        let result = WatchedShow::delete(
+            &self.db_pool,
+            id,
+            interaction.user.id.0,
+            can_manage.then_some(interaction.channel_id.0),
+        )
+        .await?;
+        if result.rows_affected() == 0 && !can_manage {
+            return Ok(Some(
+                "Pas de série suivie par vous avec cet identifiant, il faut pouvoir gérer les salons pour retirer une série suivie par ce salon.".into(),
+            ));
+        }
+        if result.rows_affected() == 0 {
+            return Ok(Some("Pas de série suivie avec cet identifiant.".into()));
+        }
+        Ok(Some(format!("Série {} n'est plus suivie.", id).into()))
+    }
+}
+
+#[async_trait]
+impl SlashCommand for WatchCommand {
+    fn register(&self, command: &mut CreateApplicationCommand) {
+        command
+            .name("watch")
+            .description("Être prévenu de la diffusion des épisodes d'une série")
+            .create_option(|option| {
+                option
+                    .name("add")
+                    .description("suivre une série")
+                    .kind(CommandOptionType::SubCommand)
+                    .create_sub_option(|sub_option| {
+                        sub_option
+                            .name("show")
+                            .description("le nom de la série")
+                            .kind(CommandOptionType::String)
+                            .required(true)
+                            .set_autocomplete(true)
+                    })
+                    .create_sub_option(|sub_option| {
+                        sub_option
+                            .name("channel")
+                            .description("prévenir ce salon plutôt que par message privé")
+                            .kind(CommandOptionType::Boolean)
+                    })
+            })
+            .create_option(|option| {
+                option
+                    .name("list")
+                    .description("les séries suivies par vous et par ce salon")
+                    .kind(CommandOptionType::SubCommand)
+            })
+            .create_option(|option| {
+                option
+                    .name("remove")
+                    .description("ne plus suivre une série")
+                    .kind(CommandOptionType::SubCommand)
+                    .create_sub_option(|sub_option| {
+                        sub_option
+                            .name("id")
+                            .description("identifiant de la série suivie")
+                            .kind(CommandOptionType::Integer)
+                            .required(true)
+                    })
+            });
+    }
+
+    async fn handle(
+        &self,
+        _ctx: &Context,
+        interaction: &ApplicationCommandInteraction,
+    ) -> Result<Option<CommandResponse>, Error> {
+        if interaction.data.name != "watch" {
+            return Ok(None);
+        }
+
+        let command = interaction
+            .data
+            .options
+            .first()
+            .ok_or_else(|| anyhow!("missing command option"))?;
+
+        match command.name.as_str() {
+            "add" => self.trigger_add(interaction, command).await,
+            "list" => self.trigger_list(interaction).await,
+            "remove" => self.trigger_remove(interaction, command).await,
+            e => Err(anyhow!("unknown command {}", e)),
+        }
+    }
+
+    async fn autocomplete(
+        &self,
+        interaction: &AutocompleteInteraction,
+    ) -> Result<Option<Vec<Choice>>, Error> {
+        if interaction.data.name != "watch" {
+            return Ok(None);
+        }
+
+        let typed = focused_value(&interaction.data.options)
+            .unwrap_or_default()
+            .trim();
+        if typed.chars().count() < MIN_AUTOCOMPLETE_LENGTH {
+            return Ok(Some(Vec::new()));
+        }
+
+        let typed = typed.to_string();
+        let choices = tokio::task::spawn_blocking(move || search_shows(&typed))
+            .await??
+            .iter()
+            .map(|s| choice(&s.label(), &format!("#{}", s.id)))
+            .collect();
+        Ok(Some(choices))
+    }
+}
+
+/// notifies the followers of a show when its next episode airs
+pub struct WatchNotifyJob {
+    pub db_pool: Arc<MySqlPool>,
+}
+
+impl WatchNotifyJob {
+    async fn notify(&self, http: &Http, watched: &WatchedShow, title: &str) -> Result<(), Error> {
+        let content = format!("📺 Nouvel épisode de **{}** : {}", watched.show_name, title);
+        match (watched.channel_id, watched.user_id) {
+            (Some(channel_id), _) => {
+                ChannelId(channel_id).say(http, content).await?;
+            }
+            (None, Some(user_id)) => {
+                let dm = UserId(user_id).create_dm_channel(http).await?;
+                dm.say(http, content).await?;
+            }
+            (None, None) => {}
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Job for WatchNotifyJob {
+    fn kind(&self) -> &'static str {
+        WATCH_NOTIFY_JOB_KIND
+    }
+
+    async fn run(&self, http: &Http, _payload: &str) -> Result<(), Error> {
+        let mut aired_shows: HashMap<u64, (u64, DateTime<Utc>)> = HashMap::new();
+        for watched in WatchedShow::find_due(&self.db_pool, Utc::now()).await? {
+            let (episode_id, title, airstamp) = match (
+                watched.next_episode_id,
+                &watched.next_episode_title,
+                watched.next_airstamp,
+            ) {
+                (Some(id), Some(title), Some(airstamp)) => (id, title, airstamp),
+                _ => continue,
+            };
+            if let Err(e) = self.notify(http, &watched, title).await {
+                println!("error while notifying watched show {} : {}", watched.id, e);
+            }
+            // marked even on failure, a closed DM would otherwise be retried every minute
+            WatchedShow::mark_notified(&self.db_pool, watched.id, episode_id).await?;
+            aired_shows.insert(watched.show_id, (episode_id, airstamp));
+        }
+
+        // move the followers of the aired shows on to the following episode
+        for (show_id, aired) in aired_shows {
+            if let Err(e) = refresh_show(&self.db_pool, show_id, Some(aired)).await {
+                println!("error while refreshing show {} : {}", show_id, e);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// refreshes the name and next episode of every followed show
+pub struct WatchRefreshJob {
+    pub db_pool: Arc<MySqlPool>,
+}
+
+#[async_trait]
+impl Job for WatchRefreshJob {
+    fn kind(&self) -> &'static str {
+        WATCH_REFRESH_JOB_KIND
+    }
+
+    async fn run(&self, _http: &Http, _payload: &str) -> Result<(), Error> {
+        for show_id in WatchedShow::find_show_ids(&self.db_pool, Utc::now()).await? {
+            if let Err(e) = refresh_show(&self.db_pool, show_id, None).await {
+                println!("error while refreshing show {} : {}", show_id, e);
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,737 @@
+use anyhow::anyhow;
+use anyhow::Error;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde_json::{Map, Number, Value};
+use sqlx::mysql::MySqlArguments;
+use sqlx::mysql::MySqlRow;
+use sqlx::query::Query;
+use sqlx::{MySql, MySqlPool, Row, Transaction};
+use std::collections::{HashMap, HashSet};
+
+/// a row of a table, keyed by column name
+pub type Record = Map<String, Value>;
+
+/// datetimes written by geekbot dumps and by the mysql client
+const SQL_DATETIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColumnKind {
+    Int,
+    /// discord ids
+    UInt,
+    Text,
+    DateTime,
+}
+
+pub struct Column {
+    pub name: &'static str,
+    pub kind: ColumnKind,
+    /// a record without this column cannot be imported
+    pub required: bool,
+}
+
+const fn column(name: &'static str, kind: ColumnKind, required: bool) -> Column {
+    Column {
+        name,
+        kind,
+        required,
+    }
+}
+
+const QUOTE_COLUMNS: &[Column] = &[
+    column("id", ColumnKind::Int, false),
+    column("bookId", ColumnKind::UInt, false),
+    column("number", ColumnKind::Int, false),
+    column("quote", ColumnKind::Text, true),
+    column("authorId", ColumnKind::UInt, false),
+    column("authorName", ColumnKind::Text, false),
+    column("guildId", ColumnKind::UInt, false),
+    column("channelId", ColumnKind::UInt, false),
+    column("messageId", ColumnKind::UInt, false),
+    column("addedBy", ColumnKind::UInt, false),
+    column("quotedAt", ColumnKind::DateTime, false),
+    column("createdAt", ColumnKind::DateTime, false),
+    column("deletedAt", ColumnKind::DateTime, false),
+    column("deletedBy", ColumnKind::UInt, false),
+    column("editedAt", ColumnKind::DateTime, false),
+    column("editedBy", ColumnKind::UInt, false),
+];
+
+const CONNERIE_COLUMNS: &[Column] = &[
+    column("id", ColumnKind::Int, false),
+    column("value", ColumnKind::Text, true),
+    column("author", ColumnKind::Text, false),
+];
+
+const SKANDITE_COLUMNS: &[Column] = &[
+    column("id", ColumnKind::Int, false),
+    column("url", ColumnKind::Text, true),
+    column("postedDate", ColumnKind::DateTime, false),
+    column("author", ColumnKind::Text, true),
+    column("count", ColumnKind::Int, false),
+];
+
+const FEED_SUBSCRIPTION_COLUMNS: &[Column] = &[
+    column("id", ColumnKind::Int, false),
+    column("guildId", ColumnKind::UInt, false),
+    column("channelId", ColumnKind::UInt, true),
+    column("url", ColumnKind::Text, true),
+    column("title", ColumnKind::Text, true),
+    column("includeFilter", ColumnKind::Text, false),
+    column("excludeFilter", ColumnKind::Text, false),
+    column("template", ColumnKind::Text, false),
+    column("createdBy", ColumnKind::UInt, true),
+    column("createdAt", ColumnKind::DateTime, false),
+];
+
+const QUOTE_LINE_COLUMNS: &[Column] = &[
+    column("id", ColumnKind::Int, false),
+    column("quoteId", ColumnKind::Int, true),
+    column("position", ColumnKind::Int, true),
+    column("authorId", ColumnKind::UInt, true),
+    column("authorName", ColumnKind::Text, true),
+    column("messageId", ColumnKind::UInt, true),
+    column("content", ColumnKind::Text, true),
+    column("postedAt", ColumnKind::DateTime, false),
+];
+
+const QUOTE_ATTACHMENT_COLUMNS: &[Column] = &[
+    column("id", ColumnKind::Int, false),
+    column("quoteId", ColumnKind::Int, true),
+    column("messageId", ColumnKind::UInt, true),
+    column("filename", ColumnKind::Text, true),
+    column("contentType", ColumnKind::Text, false),
+    column("path", ColumnKind::Text, true),
+    column("size", ColumnKind::UInt, true),
+];
+
+const QUOTE_VOTE_COLUMNS: &[Column] = &[
+    column("quoteId", ColumnKind::Int, true),
+    column("userId", ColumnKind::UInt, true),
+    column("value", ColumnKind::Int, true),
+    column("votedAt", ColumnKind::DateTime, true),
+];
+
+const RSS_FEED_COLUMNS: &[Column] = &[
+    column("id", ColumnKind::Int, false),
+    column("subscriptionId", ColumnKind::Int, true),
+    column("guid", ColumnKind::Text, true),
+    column("firstSeen", ColumnKind::DateTime, false),
+    column("publishedDate", ColumnKind::DateTime, false),
+];
+
+/// a table that can be exported and imported
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Table {
+    Quote,
+    QuoteLine,
+    QuoteAttachment,
+    QuoteVote,
+    Connerie,
+    Skandite,
+    FeedSubscription,
+    RssFeed,
+}
+
+/// every table comes after the one its rows refer to, so that their ids are known when importing all of them
+pub const TABLES: [Table; 8] = [
+    Table::Quote,
+    Table::QuoteLine,
+    Table::QuoteAttachment,
+    Table::QuoteVote,
+    Table::Connerie,
+    Table::Skandite,
+    Table::FeedSubscription,
+    Table::RssFeed,
+];
+
+impl Table {
+    pub fn parse(name: &str) -> Option<Table> {
+        TABLES
+            .into_iter()
+            .find(|t| t.name().eq_ignore_ascii_case(name))
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Table::Quote => "Quote",
+            Table::QuoteLine => "QuoteLine",
+            Table::QuoteAttachment => "QuoteAttachment",
+            Table::QuoteVote => "QuoteVote",
+            Table::Connerie => "Connerie",
+            Table::Skandite => "Skandite",
+            Table::FeedSubscription => "FeedSubscription",
+            Table::RssFeed => "RSSFeed",
+        }
+    }
+
+    pub fn columns(&self) -> &'static [Column] {
+        match self {
+            Table::Quote => QUOTE_COLUMNS,
+            Table::QuoteLine => QUOTE_LINE_COLUMNS,
+            Table::QuoteAttachment => QUOTE_ATTACHMENT_COLUMNS,
+            Table::QuoteVote => QUOTE_VOTE_COLUMNS,
+            Table::Connerie => CONNERIE_COLUMNS,
+            Table::Skandite => SKANDITE_COLUMNS,
+            Table::FeedSubscription => FEED_SUBSCRIPTION_COLUMNS,
+            Table::RssFeed => RSS_FEED_COLUMNS,
+        }
+    }
+
+    fn has_id(&self) -> bool {
+        self.columns().iter().any(|c| c.name == "id")
+    }
+
+    /**
+     * the column referring to the id of another table, and that table
+     */
+    fn parent(&self) -> Option<(&'static str, Table)> {
+        match self {
+            Table::QuoteLine | Table::QuoteAttachment | Table::QuoteVote => {
+                Some(("quoteId", Table::Quote))
+            }
+            Table::RssFeed => Some(("subscriptionId", Table::FeedSubscription)),
+            Table::Quote | Table::Connerie | Table::Skandite | Table::FeedSubscription => None,
+        }
+    }
+
+    /**
+     * the columns identifying a row, two rows with the same values are duplicates
+     */
+    fn key_columns(&self) -> &'static [&'static str] {
+        match self {
+            Table::Quote => &["bookId", "quote"],
+            Table::QuoteLine => &["quoteId", "position"],
+            Table::QuoteAttachment => &["quoteId", "path"],
+            Table::QuoteVote => &["quoteId", "userId"],
+            Table::Connerie => &["value"],
+            Table::Skandite => &["url"],
+            Table::FeedSubscription => &["channelId", "url"],
+            Table::RssFeed => &["subscriptionId", "guid"],
+        }
+    }
+}
+
+/// a value read from a record, typed after its column
+#[derive(Debug, PartialEq)]
+enum Field {
+    Int(Option<i64>),
+    UInt(Option<u64>),
+    Text(Option<String>),
+    DateTime(Option<DateTime<Utc>>),
+}
+
+impl Field {
+    fn is_null(&self) -> bool {
+        matches!(
+            self,
+            Field::Int(None) | Field::UInt(None) | Field::Text(None) | Field::DateTime(None)
+        )
+    }
+
+    fn read(row: &MySqlRow, column: &Column) -> Result<Field, Error> {
+        let field = match column.kind {
+            ColumnKind::Int => Field::Int(row.try_get(column.name)?),
+            ColumnKind::UInt => Field::UInt(row.try_get(column.name)?),
+            ColumnKind::Text => Field::Text(row.try_get(column.name)?),
+            ColumnKind::DateTime => Field::DateTime(row.try_get(column.name)?),
+        };
+        Ok(field)
+    }
+
+    /**
+     * a value of a JSON or CSV record, empty strings being null since CSV has no null
+     */
+    fn parse(column: &Column, value: Option<&Value>) -> Result<Field, Error> {
+        let value = match value {
+            None | Some(Value::Null) => None,
+            Some(Value::String(s)) if s.trim().is_empty() => None,
+            Some(v) => Some(v),
+        };
+        let invalid = || anyhow!("invalid value for {} : {:?}", column.name, value);
+        let field = match column.kind {
+            ColumnKind::Int => Field::Int(match value {
+                None => None,
+                Some(Value::Number(n)) => Some(n.as_i64().ok_or_else(invalid)?),
+                Some(Value::String(s)) => Some(s.trim().parse().map_err(|_| invalid())?),
+                Some(_) => return Err(invalid()),
+            }),
+            ColumnKind::UInt => Field::UInt(match value {
+                None => None,
+                Some(Value::Number(n)) => Some(n.as_u64().ok_or_else(invalid)?),
+                Some(Value::String(s)) => Some(s.trim().parse().map_err(|_| invalid())?),
+                Some(_) => return Err(invalid()),
+            }),
+            ColumnKind::Text => Field::Text(match value {
+                None => None,
+                Some(Value::String(s)) => Some(s.clone()),
+                Some(Value::Number(n)) => Some(n.to_string()),
+                Some(_) => return Err(invalid()),
+            }),
+            ColumnKind::DateTime => Field::DateTime(match value {
+                None => None,
+                Some(Value::String(s)) => Some(parse_datetime(s.trim()).ok_or_else(invalid)?),
+                Some(_) => return Err(invalid()),
+            }),
+        };
+        Ok(field)
+    }
+
+    fn to_value(&self) -> Value {
+        match self {
+            Field::Int(Some(i)) => Value::Number(Number::from(*i)),
+            Field::UInt(Some(u)) => Value::Number(Number::from(*u)),
+            Field::Text(Some(s)) => Value::String(s.clone()),
+            Field::DateTime(Some(d)) => Value::String(d.to_rfc3339()),
+            _ => Value::Null,
+        }
+    }
+
+    /**
+     * the value compared to find duplicates
+     */
+    fn key(&self) -> String {
+        match self.to_value() {
+            Value::String(s) => s,
+            Value::Null => String::new(),
+            v => v.to_string(),
+        }
+    }
+
+    fn bind<'q>(self, query: Query<'q, MySql, MySqlArguments>) -> Query<'q, MySql, MySqlArguments> {
+        match self {
+            Field::Int(v) => query.bind(v),
+            Field::UInt(v) => query.bind(v),
+            Field::Text(v) => query.bind(v),
+            Field::DateTime(v) => query.bind(v),
+        }
+    }
+}
+
+/**
+ * a datetime in RFC 3339, as exported, or in SQL format, taken as UTC
+ */
+fn parse_datetime(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|d| d.with_timezone(&Utc))
+        .ok()
+        .or_else(|| {
+            NaiveDateTime::parse_from_str(value, SQL_DATETIME_FORMAT)
+                .ok()
+                .map(|d| DateTime::from_utc(d, Utc))
+        })
+}
+
+/// how to import records
+pub struct ImportOptions {
+    /// book of the imported quotes, instead of the one of each record
+    pub book_id: Option<u64>,
+    /// give new numbers to all the imported quotes instead of keeping the free ones
+    pub renumber: bool,
+    /// roll back the import once done, only reporting what would be imported
+    pub dry_run: bool,
+}
+
+/// what an import did, or would do in a dry run
+#[derive(Debug, Default)]
+pub struct ImportReport {
+    pub imported: usize,
+    pub duplicates: usize,
+    /// imported quotes which could not keep their number
+    pub renumbered: usize,
+    /// records which could not be imported, with their line and the reason
+    pub rejected: Vec<(usize, String)>,
+}
+
+/// ids given to the rows imported by a run, from the id of the record to the id of the row
+#[derive(Default)]
+pub struct ImportedIds {
+    tables: HashMap<Table, HashMap<i64, i64>>,
+}
+
+impl ImportedIds {
+    /**
+     * the id of the row of an imported record, none when the table was not imported by this run
+     */
+    fn get(&self, table: Table, id: i64) -> Option<Option<i64>> {
+        self.tables.get(&table).map(|ids| ids.get(&id).copied())
+    }
+
+    fn imported(&self, table: Table) -> bool {
+        self.tables.contains_key(&table)
+    }
+
+    fn insert(&mut self, table: Table, id: i64, row_id: i64) {
+        self.tables.entry(table).or_default().insert(id, row_id);
+    }
+}
+
+/// numbers of the quote books touched by an import
+#[derive(Default)]
+struct QuoteNumbers {
+    taken: HashMap<u64, HashSet<i64>>,
+    last: HashMap<u64, i64>,
+}
+
+impl QuoteNumbers {
+    async fn load_book(
+        &mut self,
+        tx: &mut Transaction<'_, MySql>,
+        book_id: u64,
+    ) -> Result<(), Error> {
+        if self.taken.contains_key(&book_id) {
+            return Ok(());
+        }
+        // locks the sequence of the book until the import ends
+        let last = sqlx::query_scalar::<_, i64>(
+            "SELECT lastNumber FROM QuoteSequence where scope = ? FOR UPDATE",
+        )
+        .bind(book_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .unwrap_or(0);
+        let taken: HashSet<i64> =
+            sqlx::query_scalar::<_, i64>("SELECT number FROM Quote where bookId = ?")
+                .bind(book_id)
+                .fetch_all(&mut *tx)
+                .await?
+                .into_iter()
+                .collect();
+        let last = taken.iter().copied().fold(last, i64::max);
+        self.taken.insert(book_id, taken);
+        self.last.insert(book_id, last);
+        Ok(())
+    }
+
+    /**
+     * the wanted number if it is free in the book, the one after the last number otherwise
+     */
+    fn assign(&mut self, book_id: u64, wanted: Option<i64>) -> i64 {
+        let taken = self.taken.entry(book_id).or_default();
+        let last = self.last.entry(book_id).or_insert(0);
+        let number = match wanted {
+            Some(n) if n > 0 && !taken.contains(&n) => n,
+            _ => *last + 1,
+        };
+        taken.insert(number);
+        *last = (*last).max(number);
+        number
+    }
+
+    async fn save(self, tx: &mut Transaction<'_, MySql>) -> Result<(), Error> {
+        for (book_id, last) in self.last {
+            sqlx::query(
+                r#"
+                INSERT INTO QuoteSequence (`scope`, `lastNumber`)
+                VALUES(?, ?)
+                ON DUPLICATE KEY UPDATE lastNumber = GREATEST(lastNumber, VALUES(lastNumber))"#,
+            )
+            .bind(book_id)
+            .bind(last)
+            .execute(&mut *tx)
+            .await?;
+        }
+        Ok(())
+    }
+}
+
+/**
+ * the key of a row made of the values of the key columns
+ */
+fn row_key(table: Table, fields: &HashMap<&str, Field>) -> String {
+    table
+        .key_columns()
+        .iter()
+        .map(|c| fields.get(c).map(Field::key).unwrap_or_default())
+        .collect::<Vec<String>>()
+        .join("\u{1f}")
+}
+
+pub async fn export(pool: &MySqlPool, table: Table) -> Result<Vec<Record>, Error> {
+    let columns = table.columns();
+    let order = match table.has_id() {
+        true => "id".to_string(),
+        false => table.key_columns().join(", "),
+    };
+    let sql = format!(
+        "SELECT {} FROM {} ORDER BY {}",
+        columns
+            .iter()
+            .map(|c| format!("`{}`", c.name))
+            .collect::<Vec<String>>()
+            .join(", "),
+        table.name(),
+        order
+    );
+    let rows = sqlx::query(&sql).fetch_all(pool).await?;
+    let mut records = Vec::with_capacity(rows.len());
+    for row in rows {
+        let mut record = Record::new();
+        for column in columns {
+            record.insert(
+                column.name.to_string(),
+                Field::read(&row, column)?.to_value(),
+            );
+        }
+        records.push(record);
+    }
+    Ok(records)
+}
+
+/**
+ * insert records in a single transaction, skipping the rows already present and the invalid ones
+ *
+ * the records referring to a table imported before by the same run are attached to the rows of
+ * that import, the other ones to the rows of the database with the same id
+ */
+pub async fn import(
+    pool: &MySqlPool,
+    table: Table,
+    records: &[Record],
+    options: &ImportOptions,
+    ids: &mut ImportedIds,
+) -> Result<ImportReport, Error> {
+    let mut tx = pool.begin().await?;
+    let mut report = ImportReport::default();
+    let mut numbers = QuoteNumbers::default();
+    ids.tables.entry(table).or_default();
+
+    let mut key_columns: Vec<&str> = table.key_columns().to_vec();
+    if table.has_id() {
+        key_columns.push("id");
+    }
+    let key_sql = format!(
+        "SELECT {} FROM {}",
+        key_columns
+            .iter()
+            .map(|c| format!("`{}`", c))
+            .collect::<Vec<String>>()
+            .join(", "),
+        table.name()
+    );
+    // key of each row -> its id
+    let mut keys = HashMap::new();
+    for row in sqlx::query(&key_sql).fetch_all(&mut tx).await? {
+        let mut fields = HashMap::new();
+        for column in table.columns() {
+            if table.key_columns().contains(&column.name) {
+                fields.insert(column.name, Field::read(&row, column)?);
+            }
+        }
+        let id: Option<i64> = match table.has_id() {
+            true => Some(row.try_get("id")?),
+            false => None,
+        };
+        keys.insert(row_key(table, &fields), id);
+    }
+
+    let parent_ids: HashSet<i64> = match table.parent() {
+        Some((_, parent)) if !ids.imported(parent) => {
+            sqlx::query_scalar::<_, i64>(&format!("SELECT id FROM {}", parent.name()))
+                .fetch_all(&mut tx)
+                .await?
+                .into_iter()
+                .collect()
+        }
+        _ => HashSet::new(),
+    };
+
+    for (i, record) in records.iter().enumerate() {
+        let line = i + 1;
+        let mut fields = HashMap::new();
+        let mut invalid = None;
+        let mut id = None;
+        for column in table.columns() {
+            match Field::parse(column, record.get(column.name)) {
+                // ids are given by the database, the one of the record is only kept to attach its dependents
+                Ok(Field::Int(i)) if column.name == "id" => id = i,
+                Ok(field) if column.required && field.is_null() => {
+                    invalid = Some(format!("missing {}", column.name));
+                }
+                Ok(field) => {
+                    fields.insert(column.name, field);
+                }
+                Err(e) => invalid = Some(e.to_string()),
+            }
+        }
+
+        if let (None, Some((column, parent))) = (&invalid, table.parent()) {
+            if let Some(Field::Int(Some(parent_id))) = fields.get(column) {
+                let row_id = match ids.get(parent, *parent_id) {
+                    Some(row_id) => row_id,
+                    None => parent_ids.contains(parent_id).then_some(*parent_id),
+                };
+                match row_id {
+                    Some(row_id) => {
+                        fields.insert(column, Field::Int(Some(row_id)));
+                    }
+                    None => invalid = Some(format!("unknown {} {}", parent.name(), parent_id)),
+                }
+            }
+        }
+        if let Some(reason) = invalid {
+            report.rejected.push((line, reason));
+            continue;
+        }
+
+        match table {
+            Table::Quote => {
+                let book_id = match (options.book_id, fields.get("bookId")) {
+                    (Some(book_id), _) => book_id,
+                    (None, Some(Field::UInt(Some(book_id)))) => *book_id,
+                    _ => 0,
+                };
+                fields.insert("bookId", Field::UInt(Some(book_id)));
+            }
+            Table::Skandite => {
+                if fields.get("postedDate").is_none_or(Field::is_null) {
+                    fields.insert("postedDate", Field::DateTime(Some(Utc::now())));
+                }
+                if fields.get("count").is_none_or(Field::is_null) {
+                    fields.insert("count", Field::Int(Some(1)));
+                }
+            }
+            Table::FeedSubscription if fields.get("createdAt").is_none_or(Field::is_null) => {
+                fields.insert("createdAt", Field::DateTime(Some(Utc::now())));
+            }
+            _ => {}
+        }
+
+        let key = row_key(table, &fields);
+        if let Some(row_id) = keys.get(&key) {
+            report.duplicates += 1;
+            // the dependents of a duplicate go to the row already present
+            if let (Some(id), Some(row_id)) = (id, row_id) {
+                ids.insert(table, id, *row_id);
+            }
+            continue;
+        }
+
+        if let (Table::Quote, Some(Field::UInt(Some(book_id)))) = (table, fields.get("bookId")) {
+            let book_id = *book_id;
+            numbers.load_book(&mut tx, book_id).await?;
+            let wanted = match fields.get("number") {
+                Some(Field::Int(n)) if !options.renumber => *n,
+                _ => None,
+            };
+            let number = numbers.assign(book_id, wanted);
+            if wanted.is_some_and(|w| w != number) {
+                report.renumbered += 1;
+            }
+            fields.insert("number", Field::Int(Some(number)));
+        }
+
+        // null columns are left out so that the database defaults apply
+        let columns: Vec<&Column> = table
+            .columns()
+            .iter()
+            .filter(|c| fields.get(c.name).is_some_and(|f| !f.is_null()))
+            .collect();
+        let sql = format!(
+            "INSERT INTO {} ({}) VALUES({})",
+            table.name(),
+            columns
+                .iter()
+                .map(|c| format!("`{}`", c.name))
+                .collect::<Vec<String>>()
+                .join(", "),
+            vec!["?"; columns.len()].join(", ")
+        );
+        let mut query = sqlx::query(&sql);
+        for column in columns {
+            if let Some(field) = fields.remove(column.name) {
+                query = field.bind(query);
+            }
+        }
+        let row_id = query.execute(&mut tx).await?.last_insert_id() as i64;
+        let row_id = table.has_id().then_some(row_id);
+        if let (Some(id), Some(row_id)) = (id, row_id) {
+            ids.insert(table, id, row_id);
+        }
+        keys.insert(key, row_id);
+        report.imported += 1;
+    }
+
+    numbers.save(&mut tx).await?;
+    if options.dry_run {
+        tx.rollback().await?;
+    } else {
+        tx.commit().await?;
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Column, ColumnKind, Field, QuoteNumbers, TABLES};
+    use chrono::{TimeZone, Utc};
+    use serde_json::json;
+
+    #[test]
+    fn parse_fields() {
+        let id = Column {
+            name: "authorId",
+            kind: ColumnKind::UInt,
+            required: false,
+        };
+        assert_eq!(
+            Field::parse(&id, Some(&json!(184372164616372224u64))).unwrap(),
+            Field::UInt(Some(184372164616372224))
+        );
+        assert_eq!(
+            Field::parse(&id, Some(&json!(" 42 "))).unwrap(),
+            Field::UInt(Some(42))
+        );
+        assert_eq!(
+            Field::parse(&id, Some(&json!(""))).unwrap(),
+            Field::UInt(None)
+        );
+        assert!(Field::parse(&id, Some(&json!("abc"))).is_err());
+
+        let date = Column {
+            name: "quotedAt",
+            kind: ColumnKind::DateTime,
+            required: false,
+        };
+        let expected = Field::DateTime(Some(Utc.with_ymd_and_hms(2012, 3, 4, 5, 6, 7).unwrap()));
+        assert_eq!(
+            Field::parse(&date, Some(&json!("2012-03-04 05:06:07"))).unwrap(),
+            expected
+        );
+        assert_eq!(
+            Field::parse(&date, Some(&json!("2012-03-04T07:06:07+02:00"))).unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn parents_before_children() {
+        for (i, table) in TABLES.iter().enumerate() {
+            if let Some((column, parent)) = table.parent() {
+                assert!(
+                    TABLES[..i].contains(&parent),
+                    "{} before {}",
+                    parent.name(),
+                    table.name()
+                );
+                assert!(table.columns().iter().any(|c| c.name == column));
+                assert!(table.key_columns().contains(&column));
+            }
+        }
+    }
+
+    #[test]
+    fn assign_numbers() {
+        let mut numbers = QuoteNumbers::default();
+        numbers.taken.insert(1, [1, 2, 5].into_iter().collect());
+        numbers.last.insert(1, 5);
+
+        assert_eq!(numbers.assign(1, Some(3)), 3);
+        assert_eq!(numbers.assign(1, Some(3)), 6);
+        assert_eq!(numbers.assign(1, Some(2)), 7);
+        assert_eq!(numbers.assign(1, None), 8);
+        assert_eq!(numbers.assign(1, Some(20)), 20);
+        assert_eq!(numbers.assign(1, None), 21);
+        assert_eq!(numbers.assign(2, Some(1)), 1);
+    }
+}
@@ -3,6 +3,7 @@ use rand::Rng;
 use sql_builder::SqlBuilder;
 use sqlx::{mysql::MySqlQueryResult, MySqlPool, Row};
 
+#[allow(dead_code)]
 #[derive(sqlx::FromRow)]
 pub struct Connerie {
     pub id: i64,
@@ -19,6 +20,28 @@ impl Connerie {
         Ok(count)
     }
 
+    pub async fn values(pool: &MySqlPool) -> Result<Vec<String>, Error> {
+        let values = sqlx::query("SELECT value FROM Connerie")
+            .fetch_all(pool)
+            .await?
+            .iter()
+            .map(|row| row.get(0))
+            .collect();
+        Ok(values)
+    }
+
+    /**
+     * the conneries added after the given one, oldest first
+     */
+    pub async fn find_after(pool: &MySqlPool, id: i64) -> Result<Vec<Connerie>, Error> {
+        let conneries =
+            sqlx::query_as::<_, Connerie>("SELECT * FROM Connerie where id > ? ORDER BY id")
+                .bind(id)
+                .fetch_all(pool)
+                .await?;
+        Ok(conneries)
+    }
+
     fn build_search_sql(tokens: &[&str], with_spaces: bool) -> Result<String, Error> {
         let mut sql = SqlBuilder::select_from("Connerie");
         sql.field("*");
@@ -0,0 +1,80 @@
+use anyhow::Error;
+use chrono::Utc;
+use serde::Deserialize;
+use sqlx::{mysql::MySqlQueryResult, MySqlPool};
+
+/// how the passive replies are picked
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReplyMode {
+    /// any stored connerie
+    #[default]
+    Random,
+    /// a stored connerie related to the message
+    Relevant,
+    /// a sentence generated by the markov chain
+    Markov,
+}
+
+impl ReplyMode {
+    pub const ALL: [ReplyMode; 3] = [ReplyMode::Random, ReplyMode::Relevant, ReplyMode::Markov];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReplyMode::Random => "random",
+            ReplyMode::Relevant => "relevant",
+            ReplyMode::Markov => "markov",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<ReplyMode> {
+        ReplyMode::ALL.into_iter().find(|m| m.as_str() == value)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ReplyMode::Random => "au hasard",
+            ReplyMode::Relevant => "en rapport avec le message",
+            ReplyMode::Markov => "inventée par la chaîne de Markov",
+        }
+    }
+}
+
+pub struct ConnerieSettings;
+
+impl ConnerieSettings {
+    pub async fn find_reply_mode(
+        pool: &MySqlPool,
+        guild_id: u64,
+    ) -> Result<Option<ReplyMode>, Error> {
+        let mode = sqlx::query_scalar::<_, String>(
+            "SELECT replyMode FROM ConnerieSettings where guildId = ?",
+        )
+        .bind(guild_id)
+        .fetch_optional(pool)
+        .await?;
+        Ok(mode.and_then(|m| ReplyMode::parse(&m)))
+    }
+
+    pub async fn set_reply_mode(
+        pool: &MySqlPool,
+        guild_id: u64,
+        mode: ReplyMode,
+        updated_by: u64,
+    ) -> Result<MySqlQueryResult, Error> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO ConnerieSettings (`guildId`, `replyMode`, `updatedBy`, `updatedAt`)
+            VALUES(?, ?, ?, ?)
+            ON DUPLICATE KEY UPDATE replyMode = VALUES(replyMode), updatedBy = VALUES(updatedBy),
+            updatedAt = VALUES(updatedAt)"#,
+        )
+        .bind(guild_id)
+        .bind(mode.as_str())
+        .bind(updated_by)
+        .bind(Utc::now())
+        .execute(pool)
+        .await?;
+        Ok(result)
+    }
+}
@@ -0,0 +1,137 @@
+use anyhow::Error;
+use chrono::Utc;
+use sqlx::{mysql::MySqlQueryResult, MySqlPool};
+
+#[derive(sqlx::FromRow)]
+pub struct FeedSubscription {
+    pub id: i64,
+    #[sqlx(rename = "channelId")]
+    pub channel_id: u64,
+    pub url: String,
+    pub title: String,
+    #[sqlx(rename = "includeFilter")]
+    pub include_filter: Option<String>,
+    #[sqlx(rename = "excludeFilter")]
+    pub exclude_filter: Option<String>,
+    pub template: Option<String>,
+}
+
+impl FeedSubscription {
+    pub async fn find_all(pool: &MySqlPool) -> Result<Vec<FeedSubscription>, Error> {
+        let subscriptions =
+            sqlx::query_as::<_, FeedSubscription>("SELECT * FROM FeedSubscription ORDER BY id")
+                .fetch_all(pool)
+                .await?;
+        Ok(subscriptions)
+    }
+
+    pub async fn find_by_channel(
+        pool: &MySqlPool,
+        channel_id: u64,
+    ) -> Result<Vec<FeedSubscription>, Error> {
+        let subscriptions = sqlx::query_as::<_, FeedSubscription>(
+            "SELECT * FROM FeedSubscription where channelId = ? ORDER BY id",
+        )
+        .bind(channel_id)
+        .fetch_all(pool)
+        .await?;
+        Ok(subscriptions)
+    }
+
+    pub async fn find_by_channel_and_id(
+        pool: &MySqlPool,
+        channel_id: u64,
+        id: i64,
+    ) -> Result<Option<FeedSubscription>, Error> {
+        let subscription = sqlx::query_as::<_, FeedSubscription>(
+            "SELECT * FROM FeedSubscription where channelId = ? and id = ?",
+        )
+        .bind(channel_id)
+        .bind(id)
+        .fetch_optional(pool)
+        .await?;
+        Ok(subscription)
+    }
+
+    pub async fn find_by_channel_and_url(
+        pool: &MySqlPool,
+        channel_id: u64,
+        url: &str,
+    ) -> Result<Option<FeedSubscription>, Error> {
+        let subscription = sqlx::query_as::<_, FeedSubscription>(
+            "SELECT * FROM FeedSubscription where channelId = ? and url = ?",
+        )
+        .bind(channel_id)
+        .bind(url)
+        .fetch_optional(pool)
+        .await?;
+        Ok(subscription)
+    }
+
+    pub async fn insert(
+        pool: &MySqlPool,
+        guild_id: Option<u64>,
+        channel_id: u64,
+        url: &str,
+        title: &str,
+        created_by: u64,
+    ) -> Result<i64, Error> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO FeedSubscription (`guildId`, `channelId`, `url`, `title`, `createdBy`, `createdAt`)
+            VALUES(?, ?, ?, ?, ?, ?)"#,
+        )
+        .bind(guild_id)
+        .bind(channel_id)
+        .bind(url)
+        .bind(title)
+        .bind(created_by)
+        .bind(Utc::now())
+        .execute(pool)
+        .await?;
+        Ok(result.last_insert_id() as i64)
+    }
+
+    pub async fn update_filters(
+        pool: &MySqlPool,
+        id: i64,
+        include_filter: Option<&str>,
+        exclude_filter: Option<&str>,
+    ) -> Result<MySqlQueryResult, Error> {
+        let result = sqlx::query(
+            "UPDATE FeedSubscription set includeFilter = ?, excludeFilter = ? where id = ?",
+        )
+        .bind(include_filter)
+        .bind(exclude_filter)
+        .bind(id)
+        .execute(pool)
+        .await?;
+        Ok(result)
+    }
+
+    pub async fn update_template(
+        pool: &MySqlPool,
+        id: i64,
+        template: Option<&str>,
+    ) -> Result<MySqlQueryResult, Error> {
+        let result = sqlx::query("UPDATE FeedSubscription set template = ? where id = ?")
+            .bind(template)
+            .bind(id)
+            .execute(pool)
+            .await?;
+        Ok(result)
+    }
+
+    pub async fn delete(
+        pool: &MySqlPool,
+        channel_id: u64,
+        id: i64,
+    ) -> Result<MySqlQueryResult, Error> {
+        let result = sqlx::query("DELETE FROM FeedSubscription where channelId = ? and id = ?")
+            .bind(channel_id)
+            .bind(id)
+            .execute(pool)
+            .await?;
+        Ok(result)
+    }
+}
@@ -1,4 +1,14 @@
+pub mod backup;
 pub mod connerie;
+pub mod connerie_settings;
+pub mod feed_subscription;
 pub mod quote;
+pub mod quote_attachment;
+pub mod quote_book;
+pub mod quote_line;
+pub mod quote_of_the_day;
+pub mod quote_vote;
 pub mod rss;
+pub mod scheduled_job;
 pub mod skandite;
+pub mod watched_show;
@@ -1,78 +1,584 @@
+use crate::db::quote_attachment::QuoteAttachment;
+use crate::db::quote_book::LEGACY_QUOTE_BOOK;
+use crate::db::quote_line::NewQuoteLine;
+use crate::db::quote_line::QuoteLine;
+use crate::utils::search::SearchQuery;
 use anyhow::Error;
+use chrono::{DateTime, Utc};
 use rand::Rng;
-use sql_builder::SqlBuilder;
+use sqlx::mysql::MySqlQueryResult;
 use sqlx::MySqlPool;
 use sqlx::Row;
 
+#[allow(dead_code)]
 #[derive(sqlx::FromRow)]
 pub struct Quote {
     pub id: i64,
     pub quote: String,
     pub number: i64,
+    #[sqlx(rename = "bookId")]
+    pub book_id: u64,
+    #[sqlx(rename = "authorId")]
+    pub author_id: Option<u64>,
+    #[sqlx(rename = "authorName")]
+    pub author_name: Option<String>,
+    #[sqlx(rename = "guildId")]
+    pub guild_id: Option<u64>,
+    #[sqlx(rename = "channelId")]
+    pub channel_id: Option<u64>,
+    #[sqlx(rename = "messageId")]
+    pub message_id: Option<u64>,
+    #[sqlx(rename = "addedBy")]
+    pub added_by: Option<u64>,
+    /// when the quoted message was posted
+    #[sqlx(rename = "quotedAt")]
+    pub quoted_at: Option<DateTime<Utc>>,
+    #[sqlx(rename = "createdAt")]
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+/// quotes of an author or with the author among the speakers of their conversation, when an author
+/// is given (bound three times)
+const AUTHOR_FILTER: &str =
+    "(? IS NULL or authorId = ? or id IN (SELECT quoteId FROM QuoteLine where authorId = ?))";
+
+/// a quote with the sum of its votes
+#[derive(sqlx::FromRow)]
+pub struct ScoredQuote {
+    #[sqlx(flatten)]
+    pub quote: Quote,
+    pub score: i64,
+}
+
+/// a quote taken from Discord messages, before it is numbered and saved
+pub struct NewQuote {
+    pub quote: String,
+    /// none for a conversation between several members
+    pub author_id: Option<u64>,
+    pub author_name: Option<String>,
+    pub guild_id: Option<u64>,
+    pub channel_id: u64,
+    pub message_id: u64,
+    pub added_by: u64,
+    pub quoted_at: Option<DateTime<Utc>>,
+    /// the messages of a conversation, empty for a single message
+    pub lines: Vec<NewQuoteLine>,
+    /// the archived files of the messages
+    pub attachments: Vec<QuoteAttachment>,
+}
+
+impl NewQuote {
+    /**
+     * a quote of consecutive messages, attributed to their author when there is a single one
+     */
+    pub fn conversation(
+        lines: Vec<NewQuoteLine>,
+        guild_id: Option<u64>,
+        channel_id: u64,
+        added_by: u64,
+    ) -> Option<NewQuote> {
+        let first = lines.first()?;
+        let single_author = lines.iter().all(|l| l.author_id == first.author_id);
+        let text = lines
+            .iter()
+            .map(|l| format!("<{}> {}", l.author_name, l.content))
+            .collect::<Vec<String>>()
+            .join("\n");
+        Some(NewQuote {
+            quote: if single_author {
+                lines
+                    .iter()
+                    .map(|l| l.content.as_str())
+                    .collect::<Vec<&str>>()
+                    .join("\n")
+            } else {
+                text
+            },
+            author_id: single_author.then_some(first.author_id),
+            author_name: single_author.then(|| first.author_name.clone()),
+            guild_id,
+            channel_id,
+            message_id: first.message_id,
+            added_by,
+            quoted_at: first.posted_at,
+            lines,
+            attachments: Vec::new(),
+        })
+    }
 }
 
 impl Quote {
-    pub async fn find_by_number(pool: &MySqlPool, number: i64) -> Result<Option<Quote>, Error> {
-        let quote = sqlx::query_as::<_, Quote>("SELECT * FROM Quote where number = ?")
-            .bind(number)
-            .fetch_optional(pool)
-            .await?;
+    /**
+     * the quote preceded by its author, as legacy quotes were written
+     */
+    pub fn text(&self) -> String {
+        match &self.author_name {
+            Some(author) => format!("<{}> {}", author, self.quote),
+            None => self.quote.clone(),
+        }
+    }
+
+    /**
+     * link to the quoted message
+     */
+    pub fn message_link(&self) -> Option<String> {
+        let guild = match self.guild_id {
+            Some(g) => g.to_string(),
+            None => "@me".to_string(),
+        };
+        match (self.channel_id, self.message_id) {
+            (Some(channel), Some(message)) => Some(format!(
+                "https://discord.com/channels/{}/{}/{}",
+                guild, channel, message
+            )),
+            _ => None,
+        }
+    }
+
+    pub async fn find_by_id(pool: &MySqlPool, id: i64) -> Result<Option<Quote>, Error> {
+        let quote =
+            sqlx::query_as::<_, Quote>("SELECT * FROM Quote where id = ? and deletedAt IS NULL")
+                .bind(id)
+                .fetch_optional(pool)
+                .await?;
         Ok(quote)
     }
 
-    pub async fn count(pool: &MySqlPool) -> Result<i64, Error> {
-        let count: i64 = sqlx::query("SELECT count(*) from Quote")
-            .fetch_one(pool)
+    pub async fn find_by_number(
+        pool: &MySqlPool,
+        book_id: u64,
+        number: i64,
+    ) -> Result<Option<Quote>, Error> {
+        let quote = sqlx::query_as::<_, Quote>(
+            "SELECT * FROM Quote where bookId = ? and number = ? and deletedAt IS NULL",
+        )
+        .bind(book_id)
+        .bind(number)
+        .fetch_optional(pool)
+        .await?;
+        Ok(quote)
+    }
+
+    /**
+     * quotes whose number starts with the given digits, the latest ones if there are no digits
+     */
+    pub async fn find_by_number_prefix(
+        pool: &MySqlPool,
+        book_id: u64,
+        prefix: &str,
+        limit: usize,
+    ) -> Result<Vec<Quote>, Error> {
+        let quotes = if prefix.is_empty() {
+            sqlx::query_as::<_, Quote>(
+                "SELECT * FROM Quote WHERE bookId = ? and deletedAt IS NULL ORDER BY number DESC LIMIT ?",
+            )
+            .bind(book_id)
+            .bind(limit as u64)
+            .fetch_all(pool)
             .await?
-            .get(0);
-        Ok(count)
+        } else {
+            sqlx::query_as::<_, Quote>(
+                "SELECT * FROM Quote WHERE bookId = ? and CAST(number AS CHAR) LIKE ? and deletedAt IS NULL ORDER BY number LIMIT ?",
+            )
+            .bind(book_id)
+            .bind(format!("{}%", prefix))
+            .bind(limit as u64)
+            .fetch_all(pool)
+            .await?
+        };
+        Ok(quotes)
     }
 
-    fn build_search_sql(tokens: &[&str]) -> Result<String, Error> {
-        let mut sql = SqlBuilder::select_from("Quote");
-        sql.field("*");
-        for token in tokens {
-            let like_pattern = format!("%{}%", token.to_lowercase());
-            sql.and_where_like("LOWER(quote)", like_pattern);
-        }
-        sql.sql()
+    /**
+     * the latest quotes of an author
+     */
+    pub async fn find_by_author(
+        pool: &MySqlPool,
+        book_id: u64,
+        author_id: u64,
+        limit: usize,
+    ) -> Result<Vec<Quote>, Error> {
+        let quotes = sqlx::query_as::<_, Quote>(&format!(
+            "SELECT * FROM Quote WHERE bookId = ? and {} and deletedAt IS NULL ORDER BY number DESC LIMIT ?",
+            AUTHOR_FILTER
+        ))
+        .bind(book_id)
+        .bind(author_id)
+        .bind(author_id)
+        .bind(author_id)
+        .bind(limit as u64)
+        .fetch_all(pool)
+        .await?;
+        Ok(quotes)
     }
 
-    pub async fn search(pool: &MySqlPool, tokens: &[&str]) -> Result<Vec<Quote>, Error> {
-        let quotes = sqlx::query_as::<_, Quote>(&Quote::build_search_sql(tokens)?)
-            .fetch_all(pool)
-            .await?;
+    pub async fn count(
+        pool: &MySqlPool,
+        book_id: u64,
+        author_id: Option<u64>,
+    ) -> Result<i64, Error> {
+        let count: i64 = sqlx::query(&format!(
+            "SELECT count(*) from Quote where bookId = ? and deletedAt IS NULL and {}",
+            AUTHOR_FILTER
+        ))
+        .bind(book_id)
+        .bind(author_id)
+        .bind(author_id)
+        .bind(author_id)
+        .fetch_one(pool)
+        .await?
+        .get(0);
+        Ok(count)
+    }
+
+    /**
+     * quotes matching a search, the most relevant first. Words shorter than the minimum length of
+     * the full-text index (innodb_ft_min_token_size) are ignored by MySQL
+     */
+    pub async fn search(
+        pool: &MySqlPool,
+        book_id: u64,
+        query: &SearchQuery,
+        author_id: Option<u64>,
+        limit: usize,
+    ) -> Result<Vec<Quote>, Error> {
+        let boolean_query = match query.to_boolean_mode() {
+            Some(q) => q,
+            None => return Ok(Vec::new()),
+        };
+        let quotes = sqlx::query_as::<_, Quote>(&format!(
+            r#"
+            SELECT *, MATCH(authorName, quote) AGAINST(? IN BOOLEAN MODE) AS relevance FROM Quote
+            WHERE bookId = ? and deletedAt IS NULL and {}
+            and MATCH(authorName, quote) AGAINST(? IN BOOLEAN MODE)
+            ORDER BY relevance DESC, number DESC LIMIT ?"#,
+            AUTHOR_FILTER
+        ))
+        .bind(&boolean_query)
+        .bind(book_id)
+        .bind(author_id)
+        .bind(author_id)
+        .bind(author_id)
+        .bind(&boolean_query)
+        .bind(limit as u64)
+        .fetch_all(pool)
+        .await?;
 
         Ok(quotes)
     }
 
-    pub async fn random(pool: &MySqlPool) -> Result<Option<Quote>, Error> {
-        let count = Quote::count(pool).await?;
+    pub async fn random(
+        pool: &MySqlPool,
+        book_id: u64,
+        author_id: Option<u64>,
+    ) -> Result<Option<Quote>, Error> {
+        let count = Quote::count(pool, book_id, author_id).await?;
         if count <= 0 {
             Ok(None)
         } else {
             let offset = rand::thread_rng().gen_range(0..count);
-            let quote = sqlx::query_as::<_, Quote>("SELECT * FROM Quote LIMIT 1 OFFSET ?")
-                .bind(offset)
-                .fetch_one(pool)
-                .await?;
+            let quote = sqlx::query_as::<_, Quote>(&format!(
+                "SELECT * FROM Quote where bookId = ? and deletedAt IS NULL and {} ORDER BY id LIMIT 1 OFFSET ?",
+                AUTHOR_FILTER
+            ))
+            .bind(book_id)
+            .bind(author_id)
+            .bind(author_id)
+            .bind(author_id)
+            .bind(offset)
+            .fetch_one(pool)
+            .await?;
             Ok(Some(quote))
         }
     }
 
-    pub async fn save(pool: &MySqlPool, quote: &str) -> Result<i64, Error> {
-        let number = Quote::count(pool).await? + 1;
+    /**
+     * a random quote, the quotes with a better score being more likely to be picked
+     */
+    pub async fn random_weighted(
+        pool: &MySqlPool,
+        book_id: u64,
+        author_id: Option<u64>,
+    ) -> Result<Option<Quote>, Error> {
+        // weighted sampling: each quote draws -ln(u) / weight and the smallest draw wins, a quote
+        // with a score of n weighs 1 + n, or 1 / (1 - n) when n is negative
+        let quote = sqlx::query_as::<_, Quote>(&format!(
+            r#"
+            SELECT * FROM (
+                SELECT q.*, (SELECT COALESCE(SUM(v.value), 0) FROM QuoteVote v where v.quoteId = q.id) AS score
+                FROM Quote q where q.bookId = ? and q.deletedAt IS NULL and {}
+            ) scored
+            ORDER BY -LOG(1 - RAND()) / IF(score >= 0, 1 + score, 1 / (1 - score)) LIMIT 1"#,
+            AUTHOR_FILTER
+        ))
+        .bind(book_id)
+        .bind(author_id)
+        .bind(author_id)
+        .bind(author_id)
+        .fetch_optional(pool)
+        .await?;
+        Ok(quote)
+    }
+
+    /**
+     * the quotes with the best scores, or the worst ones
+     */
+    pub async fn ranked(
+        pool: &MySqlPool,
+        book_id: u64,
+        best: bool,
+        limit: usize,
+    ) -> Result<Vec<ScoredQuote>, Error> {
+        let (having, order) = if best {
+            ("score > 0", "score DESC")
+        } else {
+            ("score < 0", "score ASC")
+        };
+        let sql = format!(
+            r#"
+            SELECT q.*, CAST(SUM(v.value) AS SIGNED) AS score FROM Quote q
+            JOIN QuoteVote v ON v.quoteId = q.id
+            where q.bookId = ? and q.deletedAt IS NULL
+            GROUP BY q.id HAVING {} ORDER BY {}, q.number LIMIT ?"#,
+            having, order
+        );
+        let quotes = sqlx::query_as::<_, ScoredQuote>(&sql)
+            .bind(book_id)
+            .bind(limit as u64)
+            .fetch_all(pool)
+            .await?;
+        Ok(quotes)
+    }
+
+    /**
+     * save a quote with the next number of its book, numbers are never reused even after a deletion
+     */
+    pub async fn save(pool: &MySqlPool, book_id: u64, quote: &NewQuote) -> Result<i64, Error> {
+        let mut tx = pool.begin().await?;
 
-        sqlx::query(
+        // the row of the sequence stays locked until the transaction ends, a new book starts after
+        // the legacy quotes so that they can still be adopted without conflict
+        let number = sqlx::query(
             r#"
-            INSERT INTO Quote (`quote`, `number`)
-            VALUES(?, ?)"#,
+            INSERT INTO QuoteSequence (`scope`, `lastNumber`)
+            SELECT ?, LAST_INSERT_ID(COALESCE(MAX(number), 0) + 1) FROM Quote where bookId = ?
+            ON DUPLICATE KEY UPDATE lastNumber = LAST_INSERT_ID(QuoteSequence.lastNumber + 1)"#,
         )
-        .bind(quote)
+        .bind(book_id)
+        .bind(LEGACY_QUOTE_BOOK)
+        .execute(&mut tx)
+        .await?
+        .last_insert_id() as i64;
+
+        let quote_id = sqlx::query(
+            r#"
+            INSERT INTO Quote (`bookId`, `quote`, `number`, `authorId`, `authorName`, `guildId`, `channelId`, `messageId`, `addedBy`, `quotedAt`, `createdAt`)
+            VALUES(?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"#,
+        )
+        .bind(book_id)
+        .bind(&quote.quote)
+        .bind(number)
+        .bind(quote.author_id)
+        .bind(&quote.author_name)
+        .bind(quote.guild_id)
+        .bind(quote.channel_id)
+        .bind(quote.message_id)
+        .bind(quote.added_by)
+        .bind(quote.quoted_at)
+        .bind(Utc::now())
+        .execute(&mut tx)
+        .await?
+        .last_insert_id() as i64;
+        QuoteLine::insert_all(&mut tx, quote_id, &quote.lines).await?;
+        QuoteAttachment::insert_all(&mut tx, quote_id, &quote.attachments).await?;
+
+        tx.commit().await?;
+        Ok(number)
+    }
+
+    pub async fn update_text(
+        pool: &MySqlPool,
+        book_id: u64,
+        number: i64,
+        text: &str,
+        edited_by: u64,
+    ) -> Result<MySqlQueryResult, Error> {
+        let result = sqlx::query(
+            "UPDATE Quote set quote = ?, editedAt = ?, editedBy = ? where bookId = ? and number = ? and deletedAt IS NULL",
+        )
+        .bind(text)
+        .bind(Utc::now())
+        .bind(edited_by)
+        .bind(book_id)
         .bind(number)
         .execute(pool)
         .await?;
+        Ok(result)
+    }
 
-        Ok(number)
+    pub async fn soft_delete(
+        pool: &MySqlPool,
+        book_id: u64,
+        number: i64,
+        deleted_by: u64,
+    ) -> Result<MySqlQueryResult, Error> {
+        let result = sqlx::query(
+            "UPDATE Quote set deletedAt = ?, deletedBy = ? where bookId = ? and number = ? and deletedAt IS NULL",
+        )
+        .bind(Utc::now())
+        .bind(deleted_by)
+        .bind(book_id)
+        .bind(number)
+        .execute(pool)
+        .await?;
+        Ok(result)
+    }
+
+    /**
+     * undo the deletion of a quote, only if it was deleted after the given date
+     */
+    pub async fn restore(
+        pool: &MySqlPool,
+        book_id: u64,
+        number: i64,
+        deleted_after: DateTime<Utc>,
+    ) -> Result<MySqlQueryResult, Error> {
+        let result = sqlx::query(
+            "UPDATE Quote set deletedAt = NULL, deletedBy = NULL where bookId = ? and number = ? and deletedAt >= ?",
+        )
+        .bind(book_id)
+        .bind(number)
+        .bind(deleted_after)
+        .execute(pool)
+        .await?;
+        Ok(result)
+    }
+
+    /**
+     * names of the authors of the quotes of a book not attributed to a member, conversations excluded,
+     * names only differing by case or accents being distinct
+     */
+    pub async fn find_unattributed_names(
+        pool: &MySqlPool,
+        book_id: u64,
+    ) -> Result<Vec<String>, Error> {
+        let names = sqlx::query_scalar::<_, String>(
+            r#"
+            SELECT DISTINCT authorName COLLATE utf8mb4_bin FROM Quote
+            where bookId = ? and authorId IS NULL and authorName IS NOT NULL
+            and id NOT IN (SELECT quoteId FROM QuoteLine)"#,
+        )
+        .bind(book_id)
+        .fetch_all(pool)
+        .await?;
+        Ok(names)
+    }
+
+    /**
+     * attribute the quotes of a book whose author is named exactly like a member to that member
+     */
+    pub async fn attribute_legacy(
+        pool: &MySqlPool,
+        book_id: u64,
+        name: &str,
+        author_id: u64,
+    ) -> Result<u64, Error> {
+        let result = sqlx::query(
+            r#"
+            UPDATE Quote set authorId = ?
+            where bookId = ? and authorName COLLATE utf8mb4_bin = ? and authorId IS NULL
+            and id NOT IN (SELECT quoteId FROM QuoteLine)"#,
+        )
+        .bind(author_id)
+        .bind(book_id)
+        .bind(name)
+        .execute(pool)
+        .await?;
+        Ok(result.rows_affected())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Quote;
+
+    /**
+     * quotes migrated by 0007 keep their author in authorName, only the exact name is attributed
+     *
+     * needs a MySQL database, given by XZIBOT_TEST_DATABASE_URL, and is skipped without it
+     */
+    #[tokio::test]
+    async fn attribute_migrated_quotes() {
+        let url = match std::env::var("XZIBOT_TEST_DATABASE_URL") {
+            Ok(url) => url,
+            Err(_) => return,
+        };
+        let pool = sqlx::MySqlPool::connect(&url).await.unwrap();
+        sqlx::migrate!().run(&pool).await.unwrap();
+        let book_id = 4_000_000_000 + rand::random::<u32>() as u64;
+        // the legacy "<Toto> salut" and "<toto> coucou" once migrated
+        for (number, name, text) in [(1, "Toto", "salut"), (2, "toto", "coucou")] {
+            sqlx::query("INSERT INTO Quote (bookId, number, quote, authorName) VALUES(?, ?, ?, ?)")
+                .bind(book_id)
+                .bind(number)
+                .bind(text)
+                .bind(name)
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+
+        let mut names = Quote::find_unattributed_names(&pool, book_id)
+            .await
+            .unwrap();
+        names.sort();
+        assert_eq!(names, vec!["Toto", "toto"]);
+        assert_eq!(
+            Quote::attribute_legacy(&pool, book_id, "Toto", 42)
+                .await
+                .unwrap(),
+            1
+        );
+        assert_eq!(
+            Quote::find_unattributed_names(&pool, book_id)
+                .await
+                .unwrap(),
+            vec!["toto"]
+        );
+
+        sqlx::query("DELETE FROM Quote where bookId = ?")
+            .bind(book_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+    }
+
+    #[test]
+    fn message_link() {
+        let mut quote = Quote {
+            id: 1,
+            quote: "salut".to_string(),
+            number: 1,
+            book_id: 10,
+            author_id: Some(1),
+            author_name: Some("toto".to_string()),
+            guild_id: Some(10),
+            channel_id: Some(20),
+            message_id: Some(30),
+            added_by: Some(2),
+            quoted_at: None,
+            created_at: None,
+        };
+        assert_eq!(quote.text(), "<toto> salut");
+        assert_eq!(
+            quote.message_link().as_deref(),
+            Some("https://discord.com/channels/10/20/30")
+        );
+
+        quote.guild_id = None;
+        assert_eq!(
+            quote.message_link().as_deref(),
+            Some("https://discord.com/channels/@me/20/30")
+        );
     }
 }
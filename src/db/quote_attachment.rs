@@ -0,0 +1,57 @@
+use anyhow::Error;
+use sqlx::{MySql, MySqlPool, Transaction};
+
+/// a file of a quoted message, archived locally
+#[allow(dead_code)]
+#[derive(sqlx::FromRow)]
+pub struct QuoteAttachment {
+    #[sqlx(rename = "messageId")]
+    pub message_id: u64,
+    pub filename: String,
+    #[sqlx(rename = "contentType")]
+    pub content_type: Option<String>,
+    /// relative to the archive directory
+    pub path: String,
+    pub size: u64,
+}
+
+impl QuoteAttachment {
+    pub async fn find_by_quote(
+        pool: &MySqlPool,
+        quote_id: i64,
+    ) -> Result<Vec<QuoteAttachment>, Error> {
+        let attachments = sqlx::query_as::<_, QuoteAttachment>(
+            "SELECT * FROM QuoteAttachment where quoteId = ? ORDER BY id",
+        )
+        .bind(quote_id)
+        .fetch_all(pool)
+        .await?;
+        Ok(attachments)
+    }
+
+    /**
+     * save the attachments of a quote in the transaction saving the quote
+     */
+    pub async fn insert_all(
+        tx: &mut Transaction<'_, MySql>,
+        quote_id: i64,
+        attachments: &[QuoteAttachment],
+    ) -> Result<(), Error> {
+        for attachment in attachments {
+            sqlx::query(
+                r#"
+                INSERT INTO QuoteAttachment (`quoteId`, `messageId`, `filename`, `contentType`, `path`, `size`)
+                VALUES(?, ?, ?, ?, ?, ?)"#,
+            )
+            .bind(quote_id)
+            .bind(attachment.message_id)
+            .bind(&attachment.filename)
+            .bind(&attachment.content_type)
+            .bind(&attachment.path)
+            .bind(attachment.size)
+            .execute(&mut *tx)
+            .await?;
+        }
+        Ok(())
+    }
+}
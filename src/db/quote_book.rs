@@ -0,0 +1,179 @@
+use anyhow::Error;
+use chrono::{Duration, Utc};
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use sqlx::{mysql::MySqlQueryResult, MySqlPool};
+
+/// book of the quotes added outside of any guild, before quotes were scoped
+pub const LEGACY_QUOTE_BOOK: u64 = 0;
+const INVITE_CODE_LENGTH: usize = 10;
+const INVITE_VALIDITY_HOURS: i64 = 24;
+
+/// a guild using the quote book of another guild
+pub struct QuoteBookLink;
+
+impl QuoteBookLink {
+    /**
+     * the book used by a guild, its own unless another guild shared its book with it
+     */
+    pub async fn find_book_id(pool: &MySqlPool, guild_id: u64) -> Result<u64, Error> {
+        let book_id =
+            sqlx::query_scalar::<_, u64>("SELECT bookId FROM QuoteBookLink where guildId = ?")
+                .bind(guild_id)
+                .fetch_optional(pool)
+                .await?;
+        Ok(book_id.unwrap_or(guild_id))
+    }
+
+    /**
+     * the guilds using a book, besides its owner
+     */
+    pub async fn find_guild_ids(pool: &MySqlPool, book_id: u64) -> Result<Vec<u64>, Error> {
+        let guild_ids = sqlx::query_scalar::<_, u64>(
+            "SELECT guildId FROM QuoteBookLink where bookId = ? ORDER BY createdAt",
+        )
+        .bind(book_id)
+        .fetch_all(pool)
+        .await?;
+        Ok(guild_ids)
+    }
+
+    pub async fn link(
+        pool: &MySqlPool,
+        guild_id: u64,
+        book_id: u64,
+        created_by: u64,
+    ) -> Result<MySqlQueryResult, Error> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO QuoteBookLink (`guildId`, `bookId`, `createdBy`, `createdAt`)
+            VALUES(?, ?, ?, ?)
+            ON DUPLICATE KEY UPDATE bookId = VALUES(bookId), createdBy = VALUES(createdBy), createdAt = VALUES(createdAt)"#,
+        )
+        .bind(guild_id)
+        .bind(book_id)
+        .bind(created_by)
+        .bind(Utc::now())
+        .execute(pool)
+        .await?;
+        Ok(result)
+    }
+
+    pub async fn unlink(pool: &MySqlPool, guild_id: u64) -> Result<MySqlQueryResult, Error> {
+        let result = sqlx::query("DELETE FROM QuoteBookLink where guildId = ?")
+            .bind(guild_id)
+            .execute(pool)
+            .await?;
+        Ok(result)
+    }
+
+    /**
+     * move the legacy quotes to the book of a guild, keeping their numbers unless the guild already
+     * has a quote with the same number, returning the number of quotes moved and renumbered
+     */
+    pub async fn adopt_legacy_quotes(pool: &MySqlPool, guild_id: u64) -> Result<(u64, u64), Error> {
+        let mut tx = pool.begin().await?;
+        // locks the sequence of the book until the adoption ends
+        let last = sqlx::query_scalar::<_, i64>(
+            "SELECT lastNumber FROM QuoteSequence where scope = ? FOR UPDATE",
+        )
+        .bind(guild_id)
+        .fetch_optional(&mut tx)
+        .await?
+        .unwrap_or(0);
+        let max_number = sqlx::query_scalar::<_, i64>(
+            "SELECT COALESCE(MAX(number), 0) FROM Quote where bookId IN (?, ?)",
+        )
+        .bind(guild_id)
+        .bind(LEGACY_QUOTE_BOOK)
+        .fetch_one(&mut tx)
+        .await?;
+        let colliding = sqlx::query_scalar::<_, i64>(
+            r#"
+            SELECT l.id FROM Quote l JOIN Quote g on g.bookId = ? and g.number = l.number
+            where l.bookId = ? ORDER BY l.number"#,
+        )
+        .bind(guild_id)
+        .bind(LEGACY_QUOTE_BOOK)
+        .fetch_all(&mut tx)
+        .await?;
+        let mut next = last.max(max_number);
+        for id in &colliding {
+            next += 1;
+            sqlx::query("UPDATE Quote set number = ? where id = ?")
+                .bind(next)
+                .bind(id)
+                .execute(&mut tx)
+                .await?;
+        }
+
+        let result = sqlx::query("UPDATE Quote set bookId = ? where bookId = ?")
+            .bind(guild_id)
+            .bind(LEGACY_QUOTE_BOOK)
+            .execute(&mut tx)
+            .await?;
+        sqlx::query(
+            r#"
+            INSERT INTO QuoteSequence (`scope`, `lastNumber`)
+            SELECT ?, COALESCE(MAX(number), 0) FROM Quote where bookId = ?
+            ON DUPLICATE KEY UPDATE lastNumber = GREATEST(lastNumber, VALUES(lastNumber))"#,
+        )
+        .bind(guild_id)
+        .bind(guild_id)
+        .execute(&mut tx)
+        .await?;
+        tx.commit().await?;
+        Ok((result.rows_affected(), colliding.len() as u64))
+    }
+}
+
+/// code given by a guild sharing its book, another guild joins the book by redeeming it
+pub struct QuoteBookInvite;
+
+impl QuoteBookInvite {
+    /**
+     * create a single use code to join a book, valid for a day
+     */
+    pub async fn create(pool: &MySqlPool, book_id: u64, created_by: u64) -> Result<String, Error> {
+        let code: String = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(INVITE_CODE_LENGTH)
+            .map(char::from)
+            .collect();
+        let now = Utc::now();
+        sqlx::query(
+            r#"
+            INSERT INTO QuoteBookInvite (`code`, `bookId`, `createdBy`, `createdAt`, `expiresAt`)
+            VALUES(?, ?, ?, ?, ?)"#,
+        )
+        .bind(&code)
+        .bind(book_id)
+        .bind(created_by)
+        .bind(now)
+        .bind(now + Duration::hours(INVITE_VALIDITY_HOURS))
+        .execute(pool)
+        .await?;
+        Ok(code)
+    }
+
+    /**
+     * the book of a code that has not expired, the code cannot be used again
+     */
+    pub async fn redeem(pool: &MySqlPool, code: &str) -> Result<Option<u64>, Error> {
+        let mut tx = pool.begin().await?;
+        let book_id = sqlx::query_scalar::<_, u64>(
+            "SELECT bookId FROM QuoteBookInvite where code = ? AND expiresAt > ? FOR UPDATE",
+        )
+        .bind(code)
+        .bind(Utc::now())
+        .fetch_optional(&mut tx)
+        .await?;
+        sqlx::query("DELETE FROM QuoteBookInvite where code = ? OR expiresAt <= ?")
+            .bind(code)
+            .bind(Utc::now())
+            .execute(&mut tx)
+            .await?;
+        tx.commit().await?;
+        Ok(book_id)
+    }
+}
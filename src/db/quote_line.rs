@@ -0,0 +1,67 @@
+use anyhow::Error;
+use chrono::{DateTime, Utc};
+use sqlx::{MySql, MySqlPool, Transaction};
+
+/// a message of a quote capturing a conversation
+#[allow(dead_code)]
+#[derive(sqlx::FromRow)]
+pub struct QuoteLine {
+    pub position: i32,
+    #[sqlx(rename = "authorId")]
+    pub author_id: u64,
+    #[sqlx(rename = "authorName")]
+    pub author_name: String,
+    #[sqlx(rename = "messageId")]
+    pub message_id: u64,
+    pub content: String,
+    #[sqlx(rename = "postedAt")]
+    pub posted_at: Option<DateTime<Utc>>,
+}
+
+/// a message of a conversation being quoted, before the quote is saved
+pub struct NewQuoteLine {
+    pub author_id: u64,
+    pub author_name: String,
+    pub message_id: u64,
+    pub content: String,
+    pub posted_at: Option<DateTime<Utc>>,
+}
+
+impl QuoteLine {
+    pub async fn find_by_quote(pool: &MySqlPool, quote_id: i64) -> Result<Vec<QuoteLine>, Error> {
+        let lines = sqlx::query_as::<_, QuoteLine>(
+            "SELECT * FROM QuoteLine where quoteId = ? ORDER BY position",
+        )
+        .bind(quote_id)
+        .fetch_all(pool)
+        .await?;
+        Ok(lines)
+    }
+
+    /**
+     * save the lines of a quote in the transaction saving the quote
+     */
+    pub async fn insert_all(
+        tx: &mut Transaction<'_, MySql>,
+        quote_id: i64,
+        lines: &[NewQuoteLine],
+    ) -> Result<(), Error> {
+        for (position, line) in lines.iter().enumerate() {
+            sqlx::query(
+                r#"
+                INSERT INTO QuoteLine (`quoteId`, `position`, `authorId`, `authorName`, `messageId`, `content`, `postedAt`)
+                VALUES(?, ?, ?, ?, ?, ?, ?)"#,
+            )
+            .bind(quote_id)
+            .bind(position as i32)
+            .bind(line.author_id)
+            .bind(&line.author_name)
+            .bind(line.message_id)
+            .bind(&line.content)
+            .bind(line.posted_at)
+            .execute(&mut *tx)
+            .await?;
+        }
+        Ok(())
+    }
+}
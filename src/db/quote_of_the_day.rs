@@ -0,0 +1,165 @@
+use crate::db::quote::Quote;
+use anyhow::Error;
+use chrono::{Datelike, NaiveDate, NaiveTime, Utc};
+use sqlx::{mysql::MySqlQueryResult, MySqlPool};
+
+#[derive(sqlx::FromRow)]
+pub struct QuoteOfTheDay {
+    #[sqlx(rename = "guildId")]
+    pub guild_id: u64,
+    #[sqlx(rename = "channelId")]
+    pub channel_id: u64,
+    /// local time of the post, in the timezone of the guild
+    #[sqlx(rename = "postTime")]
+    pub post_time: NaiveTime,
+    pub timezone: String,
+    /// prefer the quotes added on the same date in previous years
+    #[sqlx(rename = "onThisDay")]
+    pub on_this_day: bool,
+    pub cycle: i64,
+    /// first local date to post a quote on
+    #[sqlx(rename = "startsOn")]
+    pub starts_on: Option<NaiveDate>,
+}
+
+impl QuoteOfTheDay {
+    pub async fn find_all(pool: &MySqlPool) -> Result<Vec<QuoteOfTheDay>, Error> {
+        let configs = sqlx::query_as::<_, QuoteOfTheDay>("SELECT * FROM QuoteOfTheDay")
+            .fetch_all(pool)
+            .await?;
+        Ok(configs)
+    }
+
+    pub async fn upsert(
+        pool: &MySqlPool,
+        config: &QuoteOfTheDay,
+        created_by: u64,
+    ) -> Result<MySqlQueryResult, Error> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO QuoteOfTheDay (`guildId`, `channelId`, `postTime`, `timezone`, `onThisDay`, `startsOn`, `createdBy`, `createdAt`)
+            VALUES(?, ?, ?, ?, ?, ?, ?, ?)
+            ON DUPLICATE KEY UPDATE channelId = VALUES(channelId), postTime = VALUES(postTime),
+            timezone = VALUES(timezone), onThisDay = VALUES(onThisDay), startsOn = VALUES(startsOn)"#,
+        )
+        .bind(config.guild_id)
+        .bind(config.channel_id)
+        .bind(config.post_time)
+        .bind(&config.timezone)
+        .bind(config.on_this_day)
+        .bind(config.starts_on)
+        .bind(created_by)
+        .bind(Utc::now())
+        .execute(pool)
+        .await?;
+        Ok(result)
+    }
+
+    pub async fn delete(pool: &MySqlPool, guild_id: u64) -> Result<MySqlQueryResult, Error> {
+        let result = sqlx::query("DELETE FROM QuoteOfTheDay where guildId = ?")
+            .bind(guild_id)
+            .execute(pool)
+            .await?;
+        Ok(result)
+    }
+
+    pub async fn has_posted(&self, pool: &MySqlPool, posted_on: NaiveDate) -> Result<bool, Error> {
+        let posted = sqlx::query_scalar::<_, i64>(
+            "SELECT count(*) FROM QuoteOfTheDayPost where guildId = ? and postedOn = ?",
+        )
+        .bind(self.guild_id)
+        .bind(posted_on)
+        .fetch_one(pool)
+        .await?;
+        Ok(posted > 0)
+    }
+
+    /**
+     * a random quote not posted yet in the current cycle, among the quotes added on the same date
+     * in previous years if a date is given
+     */
+    pub async fn pick_quote(
+        &self,
+        pool: &MySqlPool,
+        book_id: u64,
+        same_date_as: Option<NaiveDate>,
+    ) -> Result<Option<Quote>, Error> {
+        let mut sql = r#"
+            SELECT * FROM Quote q where q.bookId = ? and q.deletedAt IS NULL
+            and NOT EXISTS (
+                SELECT 1 FROM QuoteOfTheDayPost p where p.guildId = ? and p.cycle = ? and p.quoteId = q.id
+            )"#
+        .to_string();
+        if same_date_as.is_some() {
+            sql.push_str(
+                r#"
+            and MONTH(COALESCE(q.quotedAt, q.createdAt)) = ? and DAY(COALESCE(q.quotedAt, q.createdAt)) = ?
+            and YEAR(COALESCE(q.quotedAt, q.createdAt)) < ?"#,
+            );
+        }
+        sql.push_str(" ORDER BY RAND() LIMIT 1");
+
+        let mut query = sqlx::query_as::<_, Quote>(&sql)
+            .bind(book_id)
+            .bind(self.guild_id)
+            .bind(self.cycle);
+        if let Some(date) = same_date_as {
+            query = query.bind(date.month()).bind(date.day()).bind(date.year());
+        }
+        let quote = query.fetch_optional(pool).await?;
+        Ok(quote)
+    }
+
+    /**
+     * start over once every quote has been posted
+     */
+    pub async fn start_new_cycle(&mut self, pool: &MySqlPool) -> Result<(), Error> {
+        sqlx::query("UPDATE QuoteOfTheDay set cycle = cycle + 1 where guildId = ?")
+            .bind(self.guild_id)
+            .execute(pool)
+            .await?;
+        self.cycle += 1;
+        Ok(())
+    }
+
+    /**
+     * record the post of a day before sending it, false if the quote of this day was already posted
+     */
+    pub async fn claim_day(
+        &self,
+        pool: &MySqlPool,
+        posted_on: NaiveDate,
+        quote_id: i64,
+    ) -> Result<bool, Error> {
+        let result = sqlx::query(
+            r#"
+            INSERT IGNORE INTO QuoteOfTheDayPost (`guildId`, `postedOn`, `quoteId`, `cycle`, `postedAt`)
+            VALUES(?, ?, ?, ?, ?)"#,
+        )
+        .bind(self.guild_id)
+        .bind(posted_on)
+        .bind(quote_id)
+        .bind(self.cycle)
+        .bind(Utc::now())
+        .execute(pool)
+        .await?;
+        Ok(result.rows_affected() == 1)
+    }
+
+    /**
+     * forget the post of a day that could not be sent, so that it is tried again
+     */
+    pub async fn release_day(
+        &self,
+        pool: &MySqlPool,
+        posted_on: NaiveDate,
+    ) -> Result<MySqlQueryResult, Error> {
+        let result =
+            sqlx::query("DELETE FROM QuoteOfTheDayPost where guildId = ? and postedOn = ?")
+                .bind(self.guild_id)
+                .bind(posted_on)
+                .execute(pool)
+                .await?;
+        Ok(result)
+    }
+}
@@ -0,0 +1,72 @@
+use anyhow::Error;
+use chrono::Utc;
+use sqlx::MySqlPool;
+
+pub const UP_VOTE: i8 = 1;
+pub const DOWN_VOTE: i8 = -1;
+
+/// the votes of the members on a quote
+pub struct QuoteVote;
+
+/// number of 👍 and 👎 of a quote
+#[derive(sqlx::FromRow)]
+pub struct VoteTally {
+    pub up: i64,
+    pub down: i64,
+}
+
+impl QuoteVote {
+    /**
+     * record the vote of a member, voting the same way again withdraws the vote
+     */
+    pub async fn toggle(
+        pool: &MySqlPool,
+        quote_id: i64,
+        user_id: u64,
+        value: i8,
+    ) -> Result<(), Error> {
+        let mut tx = pool.begin().await?;
+        let previous = sqlx::query_scalar::<_, i8>(
+            "SELECT value FROM QuoteVote where quoteId = ? and userId = ? FOR UPDATE",
+        )
+        .bind(quote_id)
+        .bind(user_id)
+        .fetch_optional(&mut tx)
+        .await?;
+
+        if previous == Some(value) {
+            sqlx::query("DELETE FROM QuoteVote where quoteId = ? and userId = ?")
+                .bind(quote_id)
+                .bind(user_id)
+                .execute(&mut tx)
+                .await?;
+        } else {
+            sqlx::query(
+                r#"
+                INSERT INTO QuoteVote (`quoteId`, `userId`, `value`, `votedAt`)
+                VALUES(?, ?, ?, ?)
+                ON DUPLICATE KEY UPDATE value = VALUES(value), votedAt = VALUES(votedAt)"#,
+            )
+            .bind(quote_id)
+            .bind(user_id)
+            .bind(value)
+            .bind(Utc::now())
+            .execute(&mut tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    pub async fn tally(pool: &MySqlPool, quote_id: i64) -> Result<VoteTally, Error> {
+        let tally = sqlx::query_as::<_, VoteTally>(
+            r#"
+            SELECT CAST(COALESCE(SUM(value > 0), 0) AS SIGNED) AS up, CAST(COALESCE(SUM(value < 0), 0) AS SIGNED) AS down
+            FROM QuoteVote where quoteId = ?"#,
+        )
+        .bind(quote_id)
+        .fetch_one(pool)
+        .await?;
+        Ok(tally)
+    }
+}
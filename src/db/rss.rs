@@ -1,26 +1,103 @@
 use anyhow::Error;
+use chrono::{DateTime, Utc};
 use sqlx::{mysql::MySqlQueryResult, MySqlPool};
+use std::collections::HashSet;
 
 pub struct RssFeedEntry;
 
+/**
+ * placeholders of a sql IN clause
+ */
+fn placeholders(count: usize) -> String {
+    vec!["?"; count].join(", ")
+}
+
 impl RssFeedEntry {
-    pub async fn save(pool: &MySqlPool, guid: &str) -> Result<MySqlQueryResult, Error> {
+    pub async fn save(
+        pool: &MySqlPool,
+        subscription_id: i64,
+        guid: &str,
+        published_date: Option<DateTime<Utc>>,
+    ) -> Result<MySqlQueryResult, Error> {
         let result = sqlx::query(
             r#"
-            INSERT INTO RSSFeed (`guid`)
-            VALUES(?)"#,
+            INSERT INTO RSSFeed (`subscriptionId`, `guid`, `firstSeen`, `publishedDate`)
+            VALUES(?, ?, ?, ?)"#,
         )
+        .bind(subscription_id)
         .bind(guid)
+        .bind(Utc::now())
+        .bind(published_date)
         .execute(pool)
         .await?;
         Ok(result)
     }
 
-    pub async fn exists_by_guid(pool: &MySqlPool, guid: &str) -> Result<bool, Error> {
-        let exists = sqlx::query("SELECT 1 FROM RSSFeed where guid = ?")
-            .bind(guid)
-            .fetch_optional(pool)
+    /**
+     * the guids already seen by a subscription among the given ones, in a single query
+     */
+    pub async fn find_seen_guids(
+        pool: &MySqlPool,
+        subscription_id: i64,
+        guids: &[&str],
+    ) -> Result<HashSet<String>, Error> {
+        if guids.is_empty() {
+            return Ok(HashSet::new());
+        }
+
+        let sql = format!(
+            "SELECT guid FROM RSSFeed where subscriptionId = ? and guid IN ({})",
+            placeholders(guids.len())
+        );
+        let mut query = sqlx::query_scalar::<_, String>(&sql).bind(subscription_id);
+        for guid in guids {
+            query = query.bind(*guid);
+        }
+        let seen = query.fetch_all(pool).await?;
+        Ok(seen.into_iter().collect())
+    }
+
+    /**
+     * forget the entries first seen before a date and no longer present in the feed
+     */
+    pub async fn prune(
+        pool: &MySqlPool,
+        subscription_id: i64,
+        before: DateTime<Utc>,
+        current_guids: &[&str],
+    ) -> Result<MySqlQueryResult, Error> {
+        let mut sql = "DELETE FROM RSSFeed where subscriptionId = ? and firstSeen < ?".to_string();
+        if !current_guids.is_empty() {
+            sql.push_str(&format!(
+                " and guid NOT IN ({})",
+                placeholders(current_guids.len())
+            ));
+        }
+        let mut query = sqlx::query(&sql).bind(subscription_id).bind(before);
+        for guid in current_guids {
+            query = query.bind(*guid);
+        }
+        let result = query.execute(pool).await?;
+        Ok(result)
+    }
+
+    pub async fn delete_by_subscription(
+        pool: &MySqlPool,
+        subscription_id: i64,
+    ) -> Result<MySqlQueryResult, Error> {
+        let result = sqlx::query("DELETE FROM RSSFeed where subscriptionId = ?")
+            .bind(subscription_id)
+            .execute(pool)
             .await?;
-        Ok(exists.is_some())
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn placeholders() {
+        assert_eq!(super::placeholders(1), "?");
+        assert_eq!(super::placeholders(3), "?, ?, ?");
     }
 }
@@ -0,0 +1,129 @@
+use anyhow::Error;
+use chrono::{DateTime, Utc};
+use sqlx::{mysql::MySqlQueryResult, MySqlPool};
+
+#[derive(sqlx::FromRow)]
+pub struct ScheduledJob {
+    pub id: i64,
+    pub name: Option<String>,
+    pub kind: String,
+    pub payload: String,
+    pub schedule: Option<String>,
+    #[sqlx(rename = "nextRun")]
+    pub next_run: DateTime<Utc>,
+    pub attempts: i32,
+}
+
+impl ScheduledJob {
+    pub async fn find_due(
+        pool: &MySqlPool,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<ScheduledJob>, Error> {
+        let jobs = sqlx::query_as::<_, ScheduledJob>(
+            "SELECT * FROM ScheduledJob where nextRun <= ? ORDER BY nextRun",
+        )
+        .bind(now)
+        .fetch_all(pool)
+        .await?;
+        Ok(jobs)
+    }
+
+    pub async fn find_by_name(pool: &MySqlPool, name: &str) -> Result<Option<ScheduledJob>, Error> {
+        let job = sqlx::query_as::<_, ScheduledJob>("SELECT * FROM ScheduledJob where name = ?")
+            .bind(name)
+            .fetch_optional(pool)
+            .await?;
+        Ok(job)
+    }
+
+    /**
+     * schedule a job that will run once at the given date
+     */
+    pub async fn insert_once(
+        pool: &MySqlPool,
+        kind: &str,
+        payload: &str,
+        run_at: DateTime<Utc>,
+    ) -> Result<MySqlQueryResult, Error> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO ScheduledJob (`kind`, `payload`, `nextRun`)
+            VALUES(?, ?, ?)"#,
+        )
+        .bind(kind)
+        .bind(payload)
+        .bind(run_at)
+        .execute(pool)
+        .await?;
+        Ok(result)
+    }
+
+    /**
+     * create or update a recurring job, identified by its name
+     */
+    pub async fn upsert_recurring(
+        pool: &MySqlPool,
+        name: &str,
+        kind: &str,
+        payload: &str,
+        schedule: &str,
+        next_run: DateTime<Utc>,
+    ) -> Result<MySqlQueryResult, Error> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO ScheduledJob (`name`, `kind`, `payload`, `schedule`, `nextRun`)
+            VALUES(?, ?, ?, ?, ?)
+            ON DUPLICATE KEY UPDATE `kind` = VALUES(`kind`), `payload` = VALUES(`payload`),
+                `schedule` = VALUES(`schedule`), `nextRun` = VALUES(`nextRun`)"#,
+        )
+        .bind(name)
+        .bind(kind)
+        .bind(payload)
+        .bind(schedule)
+        .bind(next_run)
+        .execute(pool)
+        .await?;
+        Ok(result)
+    }
+
+    pub async fn reschedule(
+        pool: &MySqlPool,
+        id: i64,
+        last_run: DateTime<Utc>,
+        next_run: DateTime<Utc>,
+    ) -> Result<MySqlQueryResult, Error> {
+        let result = sqlx::query("UPDATE ScheduledJob set lastRun = ?, nextRun = ? where id = ?")
+            .bind(last_run)
+            .bind(next_run)
+            .bind(id)
+            .execute(pool)
+            .await?;
+        Ok(result)
+    }
+
+    /**
+     * run a failed job again later
+     */
+    pub async fn retry(
+        pool: &MySqlPool,
+        id: i64,
+        attempts: i32,
+        next_run: DateTime<Utc>,
+    ) -> Result<MySqlQueryResult, Error> {
+        let result = sqlx::query("UPDATE ScheduledJob set attempts = ?, nextRun = ? where id = ?")
+            .bind(attempts)
+            .bind(next_run)
+            .bind(id)
+            .execute(pool)
+            .await?;
+        Ok(result)
+    }
+
+    pub async fn delete(pool: &MySqlPool, id: i64) -> Result<MySqlQueryResult, Error> {
+        let result = sqlx::query("DELETE FROM ScheduledJob where id = ?")
+            .bind(id)
+            .execute(pool)
+            .await?;
+        Ok(result)
+    }
+}
@@ -5,6 +5,7 @@ use sqlx::{
     MySqlPool, Row,
 };
 
+#[allow(dead_code)]
 pub struct Skandite {
     pub id: i64,
     pub author: String,
@@ -0,0 +1,189 @@
+use anyhow::Error;
+use chrono::{DateTime, Utc};
+use sqlx::{mysql::MySqlQueryResult, MySqlPool};
+
+#[derive(sqlx::FromRow)]
+pub struct WatchedShow {
+    pub id: i64,
+    #[sqlx(rename = "showId")]
+    pub show_id: u64,
+    #[sqlx(rename = "showName")]
+    pub show_name: String,
+    #[sqlx(rename = "userId")]
+    pub user_id: Option<u64>,
+    #[sqlx(rename = "channelId")]
+    pub channel_id: Option<u64>,
+    #[sqlx(rename = "nextEpisodeId")]
+    pub next_episode_id: Option<u64>,
+    #[sqlx(rename = "nextEpisodeTitle")]
+    pub next_episode_title: Option<String>,
+    #[sqlx(rename = "nextAirstamp")]
+    pub next_airstamp: Option<DateTime<Utc>>,
+}
+
+/// the next episode of a show, as stored for its followers
+pub struct NextEpisode {
+    pub id: u64,
+    pub title: String,
+    pub airstamp: Option<DateTime<Utc>>,
+}
+
+impl WatchedShow {
+    /**
+     * shows followed by a user or by a channel
+     */
+    pub async fn find_by_user_or_channel(
+        pool: &MySqlPool,
+        user_id: u64,
+        channel_id: u64,
+    ) -> Result<Vec<WatchedShow>, Error> {
+        let shows = sqlx::query_as::<_, WatchedShow>(
+            "SELECT * FROM WatchedShow where userId = ? or channelId = ? ORDER BY id",
+        )
+        .bind(user_id)
+        .bind(channel_id)
+        .fetch_all(pool)
+        .await?;
+        Ok(shows)
+    }
+
+    pub async fn exists(
+        pool: &MySqlPool,
+        show_id: u64,
+        user_id: Option<u64>,
+        channel_id: Option<u64>,
+    ) -> Result<bool, Error> {
+        let exists = sqlx::query(
+            "SELECT 1 FROM WatchedShow where showId = ? and userId <=> ? and channelId <=> ?",
+        )
+        .bind(show_id)
+        .bind(user_id)
+        .bind(channel_id)
+        .fetch_optional(pool)
+        .await?;
+        Ok(exists.is_some())
+    }
+
+    /**
+     * followed shows whose next episode has aired and has not been notified yet
+     */
+    pub async fn find_due(pool: &MySqlPool, now: DateTime<Utc>) -> Result<Vec<WatchedShow>, Error> {
+        let shows = sqlx::query_as::<_, WatchedShow>(
+            r#"
+            SELECT * FROM WatchedShow
+            where nextAirstamp <= ?
+            and nextEpisodeId is not null
+            and not (lastNotifiedEpisodeId <=> nextEpisodeId)
+            ORDER BY nextAirstamp"#,
+        )
+        .bind(now)
+        .fetch_all(pool)
+        .await?;
+        Ok(shows)
+    }
+
+    /**
+     * the followed shows, except the ones with an aired episode still to notify, which are refreshed once notified
+     */
+    pub async fn find_show_ids(pool: &MySqlPool, now: DateTime<Utc>) -> Result<Vec<u64>, Error> {
+        let ids = sqlx::query_scalar::<_, u64>(
+            r#"
+            SELECT DISTINCT showId FROM WatchedShow
+            where showId not in (
+                SELECT showId FROM WatchedShow
+                where nextAirstamp <= ?
+                and nextEpisodeId is not null
+                and not (lastNotifiedEpisodeId <=> nextEpisodeId)
+            )"#,
+        )
+        .bind(now)
+        .fetch_all(pool)
+        .await?;
+        Ok(ids)
+    }
+
+    pub async fn insert(
+        pool: &MySqlPool,
+        show_id: u64,
+        show_name: &str,
+        user_id: Option<u64>,
+        channel_id: Option<u64>,
+        next_episode: Option<NextEpisode>,
+    ) -> Result<i64, Error> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO WatchedShow (`showId`, `showName`, `userId`, `channelId`, `nextEpisodeId`, `nextEpisodeTitle`, `nextAirstamp`, `refreshedAt`, `createdAt`)
+            VALUES(?, ?, ?, ?, ?, ?, ?, ?, ?)"#,
+        )
+        .bind(show_id)
+        .bind(show_name)
+        .bind(user_id)
+        .bind(channel_id)
+        .bind(next_episode.as_ref().map(|e| e.id))
+        .bind(next_episode.as_ref().map(|e| e.title.as_str()))
+        .bind(next_episode.as_ref().and_then(|e| e.airstamp))
+        .bind(Utc::now())
+        .bind(Utc::now())
+        .execute(pool)
+        .await?;
+        Ok(result.last_insert_id() as i64)
+    }
+
+    /**
+     * store the refreshed metadata of a show for all its followers
+     */
+    pub async fn update_show(
+        pool: &MySqlPool,
+        show_id: u64,
+        show_name: &str,
+        next_episode: Option<NextEpisode>,
+    ) -> Result<MySqlQueryResult, Error> {
+        let result = sqlx::query(
+            r#"
+            UPDATE WatchedShow
+            set showName = ?, nextEpisodeId = ?, nextEpisodeTitle = ?, nextAirstamp = ?, refreshedAt = ?
+            where showId = ?"#,
+        )
+        .bind(show_name)
+        .bind(next_episode.as_ref().map(|e| e.id))
+        .bind(next_episode.as_ref().map(|e| e.title.as_str()))
+        .bind(next_episode.as_ref().and_then(|e| e.airstamp))
+        .bind(Utc::now())
+        .bind(show_id)
+        .execute(pool)
+        .await?;
+        Ok(result)
+    }
+
+    pub async fn mark_notified(
+        pool: &MySqlPool,
+        id: i64,
+        episode_id: u64,
+    ) -> Result<MySqlQueryResult, Error> {
+        let result = sqlx::query("UPDATE WatchedShow set lastNotifiedEpisodeId = ? where id = ?")
+            .bind(episode_id)
+            .bind(id)
+            .execute(pool)
+            .await?;
+        Ok(result)
+    }
+
+    /**
+     * stop following a show, only if it is followed by the user or, when given, the channel
+     */
+    pub async fn delete(
+        pool: &MySqlPool,
+        id: i64,
+        user_id: u64,
+        channel_id: Option<u64>,
+    ) -> Result<MySqlQueryResult, Error> {
+        let result =
+            sqlx::query("DELETE FROM WatchedShow where id = ? and (userId = ? or channelId = ?)")
+                .bind(id)
+                .bind(user_id)
+                .bind(channel_id)
+                .execute(pool)
+                .await?;
+        Ok(result)
+    }
+}
@@ -1,16 +1,233 @@
+use crate::autocomplete::focused_option;
+use crate::autocomplete::Autocompleter;
+use crate::autocomplete::Choice;
+use crate::interactions::parse_custom_id;
+use crate::output::reply_ephemeral;
+use crate::output::send_channel_message;
+use crate::output::send_command_response;
+use crate::output::send_component_response;
+use crate::output::send_modal_response;
+use crate::paginator::Paginator;
+use crate::paginator::PAGINATOR_PREFIX;
 use crate::MessageCommand;
 use crate::SlashCommand;
 use serenity::async_trait;
 use serenity::client::Context;
 use serenity::client::EventHandler;
 use serenity::model::application::command::Command;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::model::application::interaction::autocomplete::AutocompleteInteraction;
+use serenity::model::application::interaction::message_component::MessageComponentInteraction;
+use serenity::model::application::interaction::modal::ModalSubmitInteraction;
 use serenity::model::application::interaction::Interaction;
+use serenity::model::application::interaction::InteractionResponseType;
+use serenity::model::application::interaction::MessageFlags;
 use serenity::model::channel::Message;
 use serenity::model::gateway::Ready;
+use std::sync::Arc;
+
+/// answer to components and modals no command handles anymore, e.g. sent before a restart
+const UNAVAILABLE_ACTION: &str = "Cette action n'est plus disponible.";
 
 pub struct Handler {
     pub slash_commands: Vec<Box<dyn SlashCommand>>,
     pub message_commands: Vec<Box<dyn MessageCommand>>,
+    pub paginator: Paginator,
+    pub autocompleter: Arc<Autocompleter>,
+}
+
+impl Handler {
+    fn find_command_by_custom_id(&self, custom_id: &str) -> Option<&dyn SlashCommand> {
+        let (prefix, _) = parse_custom_id(custom_id);
+        self.slash_commands
+            .iter()
+            .find(|c| c.custom_id_prefixes().contains(&prefix))
+            .map(|c| c.as_ref())
+    }
+
+    async fn handle_application_command(
+        &self,
+        ctx: &Context,
+        application_command: &ApplicationCommandInteraction,
+    ) {
+        println!(
+            "command received: {} from {}",
+            application_command.data.name, application_command.user.name
+        );
+
+        // acknowledge the command
+        application_command.defer(&ctx.http).await.unwrap();
+
+        for slash_command in &self.slash_commands {
+            let result = slash_command.handle(ctx, application_command);
+            match result.await {
+                Err(e) => println!(
+                    "error while executing command {} : {}",
+                    application_command.data.name, e
+                ),
+                Ok(None) => {}
+                Ok(Some(r)) => {
+                    if let Err(e) = send_command_response(
+                        ctx,
+                        application_command,
+                        &self.paginator,
+                        r,
+                        slash_command.overflow(),
+                    )
+                    .await
+                    {
+                        println!(
+                            "error while sending response to command {} : {}",
+                            application_command.data.name, e
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    async fn find_choices(&self, autocomplete: &AutocompleteInteraction) -> Vec<Choice> {
+        for slash_command in &self.slash_commands {
+            match slash_command.autocomplete(autocomplete).await {
+                Err(e) => println!(
+                    "error while autocompleting command {} : {}",
+                    autocomplete.data.name, e
+                ),
+                Ok(None) => {}
+                Ok(Some(choices)) => return choices,
+            }
+        }
+        Vec::new()
+    }
+
+    async fn handle_autocomplete(&self, ctx: &Context, autocomplete: &AutocompleteInteraction) {
+        let focused = match focused_option(&autocomplete.data.options) {
+            Some(o) => o,
+            None => return,
+        };
+        // suggestions may come from the guild data, e.g. quotes, so they are never shared with another guild
+        let scope = match autocomplete.guild_id {
+            Some(guild_id) => guild_id.to_string(),
+            None => format!("user{}", autocomplete.user.id),
+        };
+        let cache_key = format!(
+            "{}:{}:{}:{}",
+            scope,
+            autocomplete.data.name,
+            focused.name,
+            focused
+                .value
+                .as_ref()
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+        );
+
+        // the user typed something else in the meantime, only answer the latest request
+        if !self.autocompleter.debounce(autocomplete.user.id).await {
+            return;
+        }
+
+        let choices = match self.autocompleter.cached(&cache_key) {
+            Some(choices) => choices,
+            None => {
+                let choices = self.find_choices(autocomplete).await;
+                self.autocompleter.store(&cache_key, choices.clone());
+                choices
+            }
+        };
+
+        let result = autocomplete
+            .create_autocomplete_response(&ctx.http, |response| {
+                for (name, value) in choices {
+                    response.add_string_choice(name, value);
+                }
+                response
+            })
+            .await;
+        if let Err(e) = result {
+            println!(
+                "error while sending suggestions for command {} : {}",
+                autocomplete.data.name, e
+            );
+        }
+    }
+
+    async fn handle_component(&self, ctx: &Context, component: &MessageComponentInteraction) {
+        let (prefix, args) = parse_custom_id(&component.data.custom_id);
+        if prefix == PAGINATOR_PREFIX {
+            if let Err(e) = self.paginator.handle_click(ctx, component, &args).await {
+                println!("error while changing page : {}", e);
+            }
+            return;
+        }
+
+        let slash_command = match self.find_command_by_custom_id(&component.data.custom_id) {
+            Some(c) => c,
+            None => {
+                println!("no command for component {}", component.data.custom_id);
+                // Discord shows the interaction as failed if it is never answered
+                if let Err(e) = reply_ephemeral(ctx, component, UNAVAILABLE_ACTION).await {
+                    println!("error while answering unknown component : {}", e);
+                }
+                return;
+            }
+        };
+
+        match slash_command.handle_component(ctx, component).await {
+            Err(e) => println!(
+                "error while handling component {} : {}",
+                component.data.custom_id, e
+            ),
+            Ok(None) => {}
+            Ok(Some(r)) => {
+                if let Err(e) = send_component_response(ctx, component, &self.paginator, r).await {
+                    println!(
+                        "error while sending response to component {} : {}",
+                        component.data.custom_id, e
+                    );
+                }
+            }
+        }
+    }
+
+    async fn handle_modal(&self, ctx: &Context, modal: &ModalSubmitInteraction) {
+        let slash_command = match self.find_command_by_custom_id(&modal.data.custom_id) {
+            Some(c) => c,
+            None => {
+                println!("no command for modal {}", modal.data.custom_id);
+                let result = modal
+                    .create_interaction_response(&ctx.http, |response| {
+                        response
+                            .kind(InteractionResponseType::ChannelMessageWithSource)
+                            .interaction_response_data(|data| {
+                                data.content(UNAVAILABLE_ACTION)
+                                    .flags(MessageFlags::EPHEMERAL)
+                            })
+                    })
+                    .await;
+                if let Err(e) = result {
+                    println!("error while answering unknown modal : {}", e);
+                }
+                return;
+            }
+        };
+
+        match slash_command.handle_modal(ctx, modal).await {
+            Err(e) => println!(
+                "error while handling modal {} : {}",
+                modal.data.custom_id, e
+            ),
+            Ok(None) => {}
+            Ok(Some(r)) => {
+                if let Err(e) = send_modal_response(ctx, modal, &self.paginator, r).await {
+                    println!(
+                        "error while sending response to modal {} : {}",
+                        modal.data.custom_id, e
+                    );
+                }
+            }
+        }
+    }
 }
 
 #[async_trait]
@@ -26,7 +243,16 @@ impl EventHandler for Handler {
                 Err(e) => println!("error while executing message command : {}", e),
                 Ok(None) => {}
                 Ok(Some(r)) => {
-                    message.channel_id.say(&ctx.http, r).await.unwrap();
+                    if let Err(e) = send_channel_message(
+                        &ctx,
+                        message.channel_id,
+                        &r,
+                        message_command.overflow(),
+                    )
+                    .await
+                    {
+                        println!("error while sending message command response : {}", e);
+                    }
                 }
             }
         }
@@ -49,33 +275,19 @@ impl EventHandler for Handler {
     }
 
     async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
-        if let Interaction::ApplicationCommand(application_command) = interaction {
-            println!(
-                "command received: {} from {}",
-                application_command.data.name, application_command.user.name
-            );
-
-            // acknowledge the command
-            application_command.defer(&ctx.http).await.unwrap();
-
-            for slash_command in &self.slash_commands {
-                let result = slash_command.handle(&application_command);
-                match result.await {
-                    Err(e) => println!(
-                        "error while executing command {} : {}",
-                        application_command.data.name, e
-                    ),
-                    Ok(None) => {}
-                    Ok(Some(r)) => {
-                        application_command
-                            .edit_original_interaction_response(&ctx.http, |response| {
-                                response.content(r)
-                            })
-                            .await
-                            .unwrap();
-                    }
-                }
+        match interaction {
+            Interaction::ApplicationCommand(application_command) => {
+                self.handle_application_command(&ctx, &application_command)
+                    .await
+            }
+            Interaction::MessageComponent(component) => {
+                self.handle_component(&ctx, &component).await
+            }
+            Interaction::ModalSubmit(modal) => self.handle_modal(&ctx, &modal).await,
+            Interaction::Autocomplete(autocomplete) => {
+                self.handle_autocomplete(&ctx, &autocomplete).await
             }
+            _ => {}
         }
     }
 }
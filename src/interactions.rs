@@ -0,0 +1,123 @@
+use rand::Rng;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use std::time::Instant;
+
+const CUSTOM_ID_SEPARATOR: char = ':';
+
+/**
+ * build the custom id of a message component or modal, the prefix is used to route the interaction to its command
+ */
+pub fn build_custom_id(prefix: &str, args: &[&str]) -> String {
+    let mut parts = vec![prefix];
+    parts.extend_from_slice(args);
+    parts.join(&CUSTOM_ID_SEPARATOR.to_string())
+}
+
+/**
+ * split the custom id of a message component or modal into its prefix and its arguments
+ */
+pub fn parse_custom_id(custom_id: &str) -> (&str, Vec<&str>) {
+    let mut parts = custom_id.split(CUSTOM_ID_SEPARATOR);
+    let prefix = parts.next().unwrap_or_default();
+    (prefix, parts.collect())
+}
+
+/// state of interactive flows, kept in memory until it expires and lost when the bot restarts
+pub struct StateStore<T> {
+    ttl: Duration,
+    states: Mutex<HashMap<String, (Instant, T)>>,
+}
+
+impl<T: Clone> StateStore<T> {
+    pub fn new(ttl: Duration) -> StateStore<T> {
+        StateStore {
+            ttl,
+            states: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /**
+     * store a new state and return the key to put in the custom ids of the components
+     */
+    pub fn insert(&self, state: T) -> String {
+        let key = format!("{:x}", rand::thread_rng().gen::<u64>());
+        let mut states = self.states.lock().unwrap();
+        states.retain(|_, (expires_at, _)| *expires_at > Instant::now());
+        states.insert(key.clone(), (Instant::now() + self.ttl, state));
+        key
+    }
+
+    /**
+     * store a state under a chosen key, replacing the previous one
+     */
+    pub fn set(&self, key: &str, state: T) {
+        let mut states = self.states.lock().unwrap();
+        states.retain(|_, (expires_at, _)| *expires_at > Instant::now());
+        states.insert(key.to_string(), (Instant::now() + self.ttl, state));
+    }
+
+    pub fn get(&self, key: &str) -> Option<T> {
+        let states = self.states.lock().unwrap();
+        states
+            .get(key)
+            .filter(|(expires_at, _)| *expires_at > Instant::now())
+            .map(|(_, state)| state.clone())
+    }
+
+    pub fn remove(&self, key: &str) {
+        let mut states = self.states.lock().unwrap();
+        states.remove(key);
+    }
+
+    /**
+     * remove the states whose key matches a predicate
+     */
+    pub fn remove_matching(&self, predicate: impl Fn(&str) -> bool) {
+        let mut states = self.states.lock().unwrap();
+        states.retain(|key, _| !predicate(key));
+    }
+
+    /**
+     * replace an existing state, keeping its expiration date
+     */
+    pub fn update(&self, key: &str, state: T) {
+        let mut states = self.states.lock().unwrap();
+        if let Some(entry) = states.get_mut(key) {
+            entry.1 = state;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::StateStore;
+    use std::time::Duration;
+
+    #[test]
+    fn build_and_parse_custom_id() {
+        let custom_id = super::build_custom_id("quote", &["delete", "42"]);
+        assert_eq!(custom_id, "quote:delete:42");
+        assert_eq!(
+            super::parse_custom_id(&custom_id),
+            ("quote", vec!["delete", "42"])
+        );
+    }
+
+    #[test]
+    fn state_store_keeps_state() {
+        let store = StateStore::new(Duration::from_secs(60));
+        let key = store.insert(1);
+        assert_eq!(store.get(&key), Some(1));
+        store.update(&key, 2);
+        assert_eq!(store.get(&key), Some(2));
+    }
+
+    #[test]
+    fn state_store_expires_state() {
+        let store = StateStore::new(Duration::ZERO);
+        let key = store.insert(1);
+        assert_eq!(store.get(&key), None);
+    }
+}
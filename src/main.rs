@@ -1,19 +1,60 @@
+use crate::autocomplete::Autocompleter;
+use crate::backup::BackupCommand;
+use crate::backup::USAGE;
 use crate::commands::blague::BlagueCommand;
 use crate::commands::buzz::BuzzCommand;
 use crate::commands::connerie::ConnerieCommand;
+use crate::commands::connerie::ConnerieModeCommand;
+use crate::commands::connerie::CORPUS_CACHE_TTL;
 use crate::commands::eight_ball::EightBallCommand;
 use crate::commands::episodes::EpisodesCommand;
+use crate::commands::episodes::ShowCommand;
+use crate::commands::feed::FeedCommand;
+use crate::commands::feed::FeedPollJob;
+use crate::commands::feed::FEED_POLL_JOB_KIND;
+use crate::commands::feed::FEED_POLL_SCHEDULE;
+use crate::commands::film::FilmCommand;
 use crate::commands::google::GoogleCommand;
 use crate::commands::google_image::GoogleImageCommand;
 use crate::commands::horoscope::HoroscopeCommand;
+use crate::commands::markov::MarkovCommand;
+use crate::commands::markov::MarkovModel;
+use crate::commands::remind::RemindCommand;
+use crate::commands::remind::ReminderJob;
 use crate::commands::skandite::SkanditeCommand;
+use crate::commands::tv_schedule::TVScheduleCommand;
+use crate::commands::watch::WatchCommand;
+use crate::commands::watch::WatchNotifyJob;
+use crate::commands::watch::WatchRefreshJob;
+use crate::commands::watch::WATCH_NOTIFY_JOB_KIND;
+use crate::commands::watch::WATCH_NOTIFY_SCHEDULE;
+use crate::commands::watch::WATCH_REFRESH_JOB_KIND;
+use crate::commands::watch::WATCH_REFRESH_SCHEDULE;
 use crate::commands::youtube::YoutubeCommand;
 use crate::commands::MessageCommand;
+use crate::db::connerie_settings::ReplyMode;
+use crate::db::quote_book::QuoteBookLink;
 use crate::handler::Handler;
+use crate::interactions::StateStore;
+use crate::paginator::Paginator;
+use crate::scheduler::RecurringJob;
+use crate::scheduler::Scheduler;
+use crate::utils::archive::QuoteArchive;
 use crate::utils::google::GoogleSearcher;
+use crate::utils::movies::LocalMovieProvider;
+use crate::utils::movies::MovieProvider;
+use crate::utils::movies::MovieSource;
+use crate::utils::movies::TmdbProvider;
 use commands::meme::MemeCommand;
 use commands::quote::QuoteAddCommand;
 use commands::quote::QuoteCommand;
+use commands::quote::QuoteEndCommand;
+use commands::quote::QuoteOfTheDayJob;
+use commands::quote::QuoteStartCommand;
+use commands::quote::QuoteUserCommand;
+use commands::quote::QUOTE_CAPTURE_TTL;
+use commands::quote::QUOTE_OF_THE_DAY_JOB_KIND;
+use commands::quote::QUOTE_OF_THE_DAY_SCHEDULE;
 use commands::SlashCommand;
 use figment::providers::Env;
 use figment::providers::Format;
@@ -24,11 +65,19 @@ use serenity::client::Client;
 use serenity::framework::StandardFramework;
 use serenity::prelude::GatewayIntents;
 use sqlx::MySqlPool;
+use std::path::Path;
+use std::path::PathBuf;
 use std::sync::Arc;
 
+mod autocomplete;
+mod backup;
 mod commands;
 mod db;
 mod handler;
+mod interactions;
+mod output;
+mod paginator;
+mod scheduler;
 mod utils;
 
 #[derive(Deserialize)]
@@ -43,10 +92,70 @@ struct Config {
     blagues_api_token: String,
     imgflip_username: String,
     imgflip_password: String,
+    #[serde(default)]
+    quote_admin_role_ids: Vec<u64>,
+    /// guild receiving the quotes added before quotes were scoped to a guild
+    legacy_quote_guild_id: Option<u64>,
+    /// directory where the files of quoted messages are kept
+    #[serde(default = "default_quote_archive_dir")]
+    quote_archive_dir: String,
+    #[serde(default = "default_markov_model_path")]
+    markov_model_path: String,
+    /// how passive replies are picked in the guilds which did not choose: random, relevant or markov
+    #[serde(default)]
+    connerie_reply_mode: ReplyMode,
+    /// where /film finds the films: tmdb or local
+    #[serde(default)]
+    movie_provider: MovieSource,
+    /// JSON list of films used by the local movie provider
+    #[serde(default = "default_movie_local_path")]
+    movie_local_path: String,
+    #[serde(default = "default_movie_api_url")]
+    movie_api_url: String,
+    #[serde(default)]
+    movie_api_key: String,
+    #[serde(default = "default_movie_region")]
+    movie_region: String,
+    #[serde(default = "default_movie_language")]
+    movie_language: String,
+}
+
+fn default_quote_archive_dir() -> String {
+    "quote_archive".to_string()
+}
+
+fn default_markov_model_path() -> String {
+    "markov.json".to_string()
+}
+
+fn default_movie_local_path() -> String {
+    "movies.json".to_string()
+}
+
+fn default_movie_api_url() -> String {
+    "https://api.themoviedb.org/3".to_string()
+}
+
+fn default_movie_region() -> String {
+    "FR".to_string()
+}
+
+fn default_movie_language() -> String {
+    "fr-FR".to_string()
 }
 
 #[tokio::main]
 async fn main() {
+    // export and import commands run instead of the bot
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let backup_command = match BackupCommand::parse(&args).transpose() {
+        Ok(command) => command,
+        Err(e) => {
+            eprintln!("{:?}", e.context(USAGE));
+            std::process::exit(1);
+        }
+    };
+
     let config: Config = Figment::new()
         .merge(Toml::file("xzibot.toml"))
         .merge(Env::prefixed("XZIBOT_"))
@@ -54,12 +163,53 @@ async fn main() {
         .unwrap();
 
     let db_pool = Arc::new(MySqlPool::connect(&config.database_url).await.unwrap());
+    // exports and dry runs leave the database as it is
+    if backup_command.as_ref().is_none_or(BackupCommand::writes) {
+        sqlx::migrate!().run(db_pool.as_ref()).await.unwrap();
+        if let Some(guild_id) = config.legacy_quote_guild_id {
+            match QuoteBookLink::adopt_legacy_quotes(db_pool.as_ref(), guild_id).await {
+                Ok((0, _)) => {}
+                Ok((adopted, renumbered)) => println!(
+                    "{} legacy quotes adopted by guild {}, {} renumbered",
+                    adopted, guild_id, renumbered
+                ),
+                Err(e) => println!("error while adopting the legacy quotes : {}", e),
+            }
+        }
+    }
+
+    if let Some(command) = backup_command {
+        if let Err(e) = command.run(db_pool.as_ref()).await {
+            eprintln!("{:?}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
 
     let google_searcher = Arc::new(GoogleSearcher {
         google_key: config.google_key,
         google_cse_id: config.google_cse_id,
     });
 
+    let autocompleter = Arc::new(Autocompleter::default());
+    let movie_provider: Arc<dyn MovieProvider> = match config.movie_provider {
+        MovieSource::Tmdb => Arc::new(TmdbProvider {
+            api_url: config.movie_api_url,
+            api_key: config.movie_api_key,
+            region: config.movie_region,
+            language: config.movie_language,
+        }),
+        MovieSource::Local => Arc::new(
+            LocalMovieProvider::load(Path::new(&config.movie_local_path))
+                .expect("Error loading the local movies"),
+        ),
+    };
+    let markov = Arc::new(MarkovModel::new(PathBuf::from(config.markov_model_path)));
+    let quote_captures = Arc::new(StateStore::new(QUOTE_CAPTURE_TTL));
+    let quote_archive = Arc::new(QuoteArchive {
+        dir: PathBuf::from(config.quote_archive_dir),
+    });
+
     let slash_commands: Vec<Box<dyn SlashCommand>> = vec![
         Box::new(BlagueCommand {
             blagues_api_token: config.blagues_api_token,
@@ -70,9 +220,23 @@ async fn main() {
         Box::new(ConnerieCommand {
             bot_name: config.bot_name.clone(),
             db_pool: db_pool.clone(),
+            vocabulary: StateStore::new(CORPUS_CACHE_TTL),
+            relevance_index: StateStore::new(CORPUS_CACHE_TTL),
+            markov: markov.clone(),
+            default_reply_mode: config.connerie_reply_mode,
+        }),
+        Box::new(ConnerieModeCommand {
+            db_pool: db_pool.clone(),
+            default_reply_mode: config.connerie_reply_mode,
         }),
         Box::new(EightBallCommand {}),
         Box::new(EpisodesCommand {}),
+        Box::new(FeedCommand {
+            db_pool: db_pool.clone(),
+        }),
+        Box::new(FilmCommand {
+            provider: movie_provider,
+        }),
         Box::new(GoogleCommand {
             google_searcher: google_searcher.clone(),
         }),
@@ -80,6 +244,10 @@ async fn main() {
             google_searcher: google_searcher.clone(),
         }),
         Box::new(HoroscopeCommand {}),
+        Box::new(MarkovCommand {
+            db_pool: db_pool.clone(),
+            model: markov.clone(),
+        }),
         Box::new(MemeCommand {
             db_pool: db_pool.clone(),
             imgflip_username: config.imgflip_username,
@@ -87,9 +255,34 @@ async fn main() {
         }),
         Box::new(QuoteCommand {
             db_pool: db_pool.clone(),
+            archive: quote_archive.clone(),
+            autocompleter: autocompleter.clone(),
+            admin_role_ids: config.quote_admin_role_ids,
         }),
         Box::new(QuoteAddCommand {
             db_pool: db_pool.clone(),
+            archive: quote_archive.clone(),
+        }),
+        Box::new(QuoteUserCommand {
+            db_pool: db_pool.clone(),
+        }),
+        Box::new(QuoteStartCommand {
+            captures: quote_captures.clone(),
+        }),
+        Box::new(QuoteEndCommand {
+            db_pool: db_pool.clone(),
+            archive: quote_archive,
+            captures: quote_captures,
+        }),
+        Box::new(RemindCommand {
+            db_pool: db_pool.clone(),
+        }),
+        Box::new(ShowCommand {}),
+        Box::new(TVScheduleCommand {
+            db_pool: db_pool.clone(),
+        }),
+        Box::new(WatchCommand {
+            db_pool: db_pool.clone(),
         }),
         Box::new(YoutubeCommand {
             google_searcher: google_searcher.clone(),
@@ -100,6 +293,10 @@ async fn main() {
         Box::new(ConnerieCommand {
             bot_name: config.bot_name.clone(),
             db_pool: db_pool.clone(),
+            vocabulary: StateStore::new(CORPUS_CACHE_TTL),
+            relevance_index: StateStore::new(CORPUS_CACHE_TTL),
+            markov,
+            default_reply_mode: config.connerie_reply_mode,
         }),
         Box::new(SkanditeCommand {
             db_pool: db_pool.clone(),
@@ -110,6 +307,8 @@ async fn main() {
     let handler = Handler {
         slash_commands,
         message_commands,
+        paginator: Paginator::default(),
+        autocompleter,
     };
 
     let intents = GatewayIntents::GUILD_MESSAGES
@@ -122,6 +321,53 @@ async fn main() {
         .await
         .expect("Error creating client");
 
+    let scheduler = Scheduler {
+        db_pool: db_pool.clone(),
+        http: client.cache_and_http.http.clone(),
+        jobs: vec![
+            Box::new(ReminderJob {}),
+            Box::new(FeedPollJob {
+                db_pool: db_pool.clone(),
+            }),
+            Box::new(WatchNotifyJob {
+                db_pool: db_pool.clone(),
+            }),
+            Box::new(WatchRefreshJob {
+                db_pool: db_pool.clone(),
+            }),
+            Box::new(QuoteOfTheDayJob {
+                db_pool: db_pool.clone(),
+            }),
+        ],
+        recurring_jobs: vec![
+            RecurringJob {
+                name: FEED_POLL_JOB_KIND.to_string(),
+                kind: FEED_POLL_JOB_KIND.to_string(),
+                payload: String::new(),
+                schedule: FEED_POLL_SCHEDULE.to_string(),
+            },
+            RecurringJob {
+                name: WATCH_NOTIFY_JOB_KIND.to_string(),
+                kind: WATCH_NOTIFY_JOB_KIND.to_string(),
+                payload: String::new(),
+                schedule: WATCH_NOTIFY_SCHEDULE.to_string(),
+            },
+            RecurringJob {
+                name: WATCH_REFRESH_JOB_KIND.to_string(),
+                kind: WATCH_REFRESH_JOB_KIND.to_string(),
+                payload: String::new(),
+                schedule: WATCH_REFRESH_SCHEDULE.to_string(),
+            },
+            RecurringJob {
+                name: QUOTE_OF_THE_DAY_JOB_KIND.to_string(),
+                kind: QUOTE_OF_THE_DAY_JOB_KIND.to_string(),
+                payload: String::new(),
+                schedule: QUOTE_OF_THE_DAY_SCHEDULE.to_string(),
+            },
+        ],
+    };
+    scheduler.start();
+
     if let Err(why) = client.start().await {
         println!("An error occurred while running the client: {:?}", why);
     }
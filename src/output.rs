@@ -0,0 +1,426 @@
+use crate::paginator::Paginator;
+use anyhow::Error;
+use serenity::builder::CreateComponents;
+use serenity::builder::CreateEmbed;
+use serenity::client::Context;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::model::application::interaction::message_component::MessageComponentInteraction;
+use serenity::model::application::interaction::modal::ModalSubmitInteraction;
+use serenity::model::application::interaction::InteractionResponseType;
+use serenity::model::application::interaction::MessageFlags;
+use serenity::model::channel::AttachmentType;
+use serenity::model::id::ChannelId;
+use serenity::model::id::UserId;
+use std::borrow::Cow;
+
+/// maximum number of characters Discord accepts in a single message
+pub const MESSAGE_MAX_LENGTH: usize = 2000;
+/// Discord refuses larger uploads from bots in servers without boosts, for all the files of a message
+pub const MAX_UPLOAD_SIZE: usize = 8 * 1024 * 1024;
+/// Discord accepts this many files in a message
+const MAX_FILES_PER_MESSAGE: usize = 10;
+
+const ATTACHMENT_FILENAME: &str = "message.txt";
+const ATTACHMENT_NOTICE: &str = "Réponse trop longue, voir le fichier joint.";
+
+/// what to do with a response that does not fit in a single Discord message
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Overflow {
+    /// send the response as several consecutive messages
+    Split,
+    /// send the response as a text file attached to the message
+    Attachment,
+}
+
+/// response of a slash command
+pub enum CommandResponse {
+    /// a plain text message
+    Text(String),
+    /// several pages browsed with buttons
+    Pages(Vec<String>),
+    /// a message with interactive components, e.g. a select menu
+    Components(String, CreateComponents),
+    /// a rich embed
+    Embed(CreateEmbed),
+    /// a rich embed with interactive components, e.g. buttons
+    EmbedComponents(CreateEmbed, CreateComponents),
+    /// a response followed by a message holding files
+    WithFiles(Box<CommandResponse>, Vec<AttachmentType<'static>>),
+}
+
+impl From<String> for CommandResponse {
+    fn from(content: String) -> Self {
+        CommandResponse::Text(content)
+    }
+}
+
+impl From<&str> for CommandResponse {
+    fn from(content: &str) -> Self {
+        CommandResponse::Text(content.to_string())
+    }
+}
+
+/**
+ * cut content into chunks of at most max_length characters, preferably on line breaks, then on spaces
+ */
+pub fn split_content(content: &str, max_length: usize) -> Vec<String> {
+    let mut chunks: Vec<String> = Vec::new();
+    let mut remaining = content.trim_end();
+
+    while remaining.chars().count() > max_length {
+        let limit = remaining
+            .char_indices()
+            .nth(max_length)
+            .map(|(i, _)| i)
+            .unwrap_or(remaining.len());
+        let head = &remaining[..limit];
+        let cut = head
+            .rfind('\n')
+            .or_else(|| head.rfind(' '))
+            .filter(|i| *i > 0)
+            .unwrap_or(limit);
+
+        chunks.push(remaining[..cut].trim_end().to_string());
+        remaining = remaining[cut..].trim_start();
+    }
+
+    if !remaining.is_empty() || chunks.is_empty() {
+        chunks.push(remaining.to_string());
+    }
+    chunks
+}
+
+/**
+ * shorten content to at most max_length characters
+ */
+pub fn truncate(content: &str, max_length: usize) -> String {
+    if content.chars().count() <= max_length {
+        content.to_string()
+    } else {
+        let mut truncated: String = content.chars().take(max_length - 1).collect();
+        truncated.push('…');
+        truncated
+    }
+}
+
+fn as_attachment(content: &str) -> AttachmentType<'static> {
+    AttachmentType::Bytes {
+        data: Cow::Owned(content.as_bytes().to_vec()),
+        filename: ATTACHMENT_FILENAME.to_string(),
+    }
+}
+
+fn file_size(file: &AttachmentType) -> usize {
+    match file {
+        AttachmentType::Bytes { data, .. } => data.len(),
+        _ => 0,
+    }
+}
+
+/**
+ * group files into messages Discord accepts, within the number of files and the upload size of a message
+ */
+fn split_files(files: Vec<AttachmentType<'static>>) -> Vec<Vec<AttachmentType<'static>>> {
+    let mut groups: Vec<Vec<AttachmentType<'static>>> = Vec::new();
+    let mut group_size = 0;
+    for file in files {
+        let size = file_size(&file);
+        match groups.last_mut() {
+            Some(group)
+                if group.len() < MAX_FILES_PER_MESSAGE && group_size + size <= MAX_UPLOAD_SIZE =>
+            {
+                group_size += size;
+                group.push(file);
+            }
+            _ => {
+                group_size = size;
+                groups.push(vec![file]);
+            }
+        }
+    }
+    groups
+}
+
+/**
+ * replace the deferred response of a slash command, handling content longer than a Discord message
+ */
+pub async fn send_interaction_response(
+    ctx: &Context,
+    interaction: &ApplicationCommandInteraction,
+    content: &str,
+    overflow: Overflow,
+) -> Result<(), Error> {
+    if content.chars().count() <= MESSAGE_MAX_LENGTH {
+        interaction
+            .edit_original_interaction_response(&ctx.http, |response| response.content(content))
+            .await?;
+        return Ok(());
+    }
+
+    match overflow {
+        Overflow::Split => {
+            let chunks = split_content(content, MESSAGE_MAX_LENGTH);
+            let mut chunks = chunks.iter();
+            if let Some(first) = chunks.next() {
+                interaction
+                    .edit_original_interaction_response(&ctx.http, |response| {
+                        response.content(first)
+                    })
+                    .await?;
+            }
+            for chunk in chunks {
+                interaction
+                    .create_followup_message(&ctx.http, |followup| followup.content(chunk))
+                    .await?;
+            }
+        }
+        Overflow::Attachment => {
+            interaction
+                .edit_original_interaction_response(&ctx.http, |response| {
+                    response.content(ATTACHMENT_NOTICE)
+                })
+                .await?;
+            interaction
+                .create_followup_message(&ctx.http, |followup| {
+                    followup.add_file(as_attachment(content))
+                })
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+/// the single message displaying a response
+struct RenderedResponse {
+    content: String,
+    embeds: Vec<CreateEmbed>,
+    components: CreateComponents,
+}
+
+fn render(paginator: &Paginator, owner: UserId, response: CommandResponse) -> RenderedResponse {
+    let (content, embeds, components) = match response {
+        CommandResponse::Text(content) => (content, Vec::new(), CreateComponents::default()),
+        CommandResponse::Pages(pages) => {
+            let (content, components) = paginator.start(owner, pages);
+            (content, Vec::new(), components)
+        }
+        CommandResponse::Components(content, components) => (content, Vec::new(), components),
+        CommandResponse::Embed(embed) => (String::new(), vec![embed], CreateComponents::default()),
+        CommandResponse::EmbedComponents(embed, components) => {
+            (String::new(), vec![embed], components)
+        }
+        CommandResponse::WithFiles(response, _) => return render(paginator, owner, *response),
+    };
+    RenderedResponse {
+        content: truncate(&content, MESSAGE_MAX_LENGTH),
+        embeds,
+        components,
+    }
+}
+
+/**
+ * replace the deferred response of a slash command with the response it returned
+ */
+pub async fn send_command_response(
+    ctx: &Context,
+    interaction: &ApplicationCommandInteraction,
+    paginator: &Paginator,
+    response: CommandResponse,
+    overflow: Overflow,
+) -> Result<(), Error> {
+    let (response, files) = match response {
+        CommandResponse::WithFiles(response, files) => (*response, files),
+        response => (response, Vec::new()),
+    };
+
+    if let CommandResponse::Text(content) = &response {
+        send_interaction_response(ctx, interaction, content, overflow).await?;
+    } else {
+        let rendered = render(paginator, interaction.user.id, response);
+        interaction
+            .edit_original_interaction_response(&ctx.http, |response| {
+                response
+                    .content(rendered.content)
+                    .set_embeds(rendered.embeds)
+                    .components(|c| {
+                        *c = rendered.components;
+                        c
+                    })
+            })
+            .await?;
+    }
+
+    // files cannot be added when editing the deferred response
+    for group in split_files(files) {
+        interaction
+            .create_followup_message(&ctx.http, |followup| followup.add_files(group))
+            .await?;
+    }
+    Ok(())
+}
+
+/**
+ * replace the message holding a component with the response to the click
+ */
+pub async fn send_component_response(
+    ctx: &Context,
+    interaction: &MessageComponentInteraction,
+    paginator: &Paginator,
+    response: CommandResponse,
+) -> Result<(), Error> {
+    let rendered = render(paginator, interaction.user.id, response);
+    interaction
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::UpdateMessage)
+                .interaction_response_data(|data| {
+                    data.content(rendered.content)
+                        .set_embeds(rendered.embeds)
+                        .set_components(rendered.components)
+                })
+        })
+        .await?;
+    Ok(())
+}
+
+/**
+ * answer a modal submission, replacing the message that opened the modal if there is one
+ */
+pub async fn send_modal_response(
+    ctx: &Context,
+    interaction: &ModalSubmitInteraction,
+    paginator: &Paginator,
+    response: CommandResponse,
+) -> Result<(), Error> {
+    let kind = match interaction.message {
+        Some(_) => InteractionResponseType::UpdateMessage,
+        None => InteractionResponseType::ChannelMessageWithSource,
+    };
+    let rendered = render(paginator, interaction.user.id, response);
+    interaction
+        .create_interaction_response(&ctx.http, |response| {
+            response.kind(kind).interaction_response_data(|data| {
+                data.content(rendered.content)
+                    .set_embeds(rendered.embeds)
+                    .set_components(rendered.components)
+            })
+        })
+        .await?;
+    Ok(())
+}
+
+/**
+ * answer a message component with a message only visible to the user who used it
+ */
+pub async fn reply_ephemeral(
+    ctx: &Context,
+    interaction: &MessageComponentInteraction,
+    content: &str,
+) -> Result<(), Error> {
+    interaction
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|data| {
+                    data.content(content).flags(MessageFlags::EPHEMERAL)
+                })
+        })
+        .await?;
+    Ok(())
+}
+
+/**
+ * send a message to a channel, handling content longer than a Discord message
+ */
+pub async fn send_channel_message(
+    ctx: &Context,
+    channel_id: ChannelId,
+    content: &str,
+    overflow: Overflow,
+) -> Result<(), Error> {
+    if content.chars().count() <= MESSAGE_MAX_LENGTH {
+        channel_id.say(&ctx.http, content).await?;
+        return Ok(());
+    }
+
+    match overflow {
+        Overflow::Split => {
+            for chunk in split_content(content, MESSAGE_MAX_LENGTH) {
+                channel_id.say(&ctx.http, chunk).await?;
+            }
+        }
+        Overflow::Attachment => {
+            channel_id
+                .send_message(&ctx.http, |message| {
+                    message
+                        .content(ATTACHMENT_NOTICE)
+                        .add_file(as_attachment(content))
+                })
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serenity::model::channel::AttachmentType;
+    use std::borrow::Cow;
+
+    #[test]
+    fn split_short_content() {
+        assert_eq!(super::split_content("abc def", 10), vec!["abc def"]);
+    }
+
+    #[test]
+    fn split_on_line_breaks() {
+        assert_eq!(
+            super::split_content("abc def\nghi jkl", 10),
+            vec!["abc def", "ghi jkl"]
+        );
+    }
+
+    #[test]
+    fn split_on_spaces() {
+        assert_eq!(
+            super::split_content("abc def ghi jkl", 10),
+            vec!["abc def", "ghi jkl"]
+        );
+    }
+
+    #[test]
+    fn split_long_words() {
+        assert_eq!(
+            super::split_content("abcdefghijkl", 5),
+            vec!["abcde", "fghij", "kl"]
+        );
+    }
+
+    #[test]
+    fn truncate_long_content() {
+        assert_eq!(super::truncate("abcdef", 4), "abc…");
+        assert_eq!(super::truncate("abcd", 4), "abcd");
+    }
+
+    #[test]
+    fn split_counts_characters_not_bytes() {
+        assert_eq!(super::split_content("éééééé", 3), vec!["ééé", "ééé"]);
+    }
+
+    #[test]
+    fn split_files_by_size() {
+        let file = |size: usize| AttachmentType::Bytes {
+            data: Cow::Owned(vec![0; size]),
+            filename: "file".to_string(),
+        };
+        let megabyte = 1024 * 1024;
+        let groups =
+            super::split_files(vec![file(5 * megabyte), file(3 * megabyte), file(megabyte)]);
+        let lengths: Vec<usize> = groups.iter().map(Vec::len).collect();
+        assert_eq!(lengths, [2, 1]);
+
+        let groups = super::split_files((0..12).map(|_| file(1)).collect());
+        let lengths: Vec<usize> = groups.iter().map(Vec::len).collect();
+        assert_eq!(lengths, [10, 2]);
+    }
+}
@@ -0,0 +1,173 @@
+use crate::interactions::build_custom_id;
+use crate::interactions::StateStore;
+use crate::output::reply_ephemeral;
+use crate::output::truncate;
+use crate::output::MESSAGE_MAX_LENGTH;
+use anyhow::Error;
+use serenity::builder::CreateComponents;
+use serenity::client::Context;
+use serenity::model::application::component::ButtonStyle;
+use serenity::model::application::interaction::message_component::MessageComponentInteraction;
+use serenity::model::application::interaction::InteractionResponseType;
+use serenity::model::id::UserId;
+use std::time::Duration;
+
+pub const PAGINATOR_PREFIX: &str = "paginator";
+const PAGINATOR_TTL: Duration = Duration::from_secs(900);
+const PREVIOUS_ACTION: &str = "previous";
+const COUNTER_ACTION: &str = "counter";
+const NEXT_ACTION: &str = "next";
+/// answer to a click once the pages are gone, after their expiration or a restart of the bot
+const EXPIRED: &str = "Cette liste a expiré, relancez la commande pour la parcourir.";
+
+#[derive(Clone)]
+struct PaginatorState {
+    owner: UserId,
+    pages: Vec<String>,
+    current: usize,
+}
+
+impl PaginatorState {
+    fn content(&self) -> String {
+        let page = self
+            .pages
+            .get(self.current)
+            .map(|p| p.as_str())
+            .unwrap_or_default();
+        truncate(page, MESSAGE_MAX_LENGTH)
+    }
+
+    fn navigate(&mut self, action: &str) {
+        match action {
+            PREVIOUS_ACTION => self.current = self.current.saturating_sub(1),
+            NEXT_ACTION => self.current = (self.current + 1).min(self.pages.len() - 1),
+            _ => {}
+        }
+    }
+}
+
+/// browse a list of pages with previous/next buttons, only usable by the user who invoked the command
+///
+/// the pages are only kept in memory, the buttons stop working when they expire or when the bot restarts
+pub struct Paginator {
+    states: StateStore<PaginatorState>,
+}
+
+impl Default for Paginator {
+    fn default() -> Self {
+        Paginator {
+            states: StateStore::new(PAGINATOR_TTL),
+        }
+    }
+}
+
+impl Paginator {
+    /**
+     * return the content of the first page and the buttons to browse the others
+     */
+    pub fn start(&self, owner: UserId, pages: Vec<String>) -> (String, CreateComponents) {
+        let state = PaginatorState {
+            owner,
+            pages,
+            current: 0,
+        };
+        if state.pages.len() <= 1 {
+            return (state.content(), CreateComponents::default());
+        }
+
+        let content = state.content();
+        let key = self.states.insert(state.clone());
+        (content, build_components(&key, &state))
+    }
+
+    /**
+     * handle a click on one of the buttons of a paginated message
+     */
+    pub async fn handle_click(
+        &self,
+        ctx: &Context,
+        interaction: &MessageComponentInteraction,
+        args: &[&str],
+    ) -> Result<(), Error> {
+        let (key, action) = match args {
+            [key, action] => (*key, *action),
+            _ => return reply_ephemeral(ctx, interaction, EXPIRED).await,
+        };
+
+        let mut state = match self.states.get(key) {
+            Some(state) if state.owner == interaction.user.id => state,
+            Some(_) => {
+                return reply_ephemeral(
+                    ctx,
+                    interaction,
+                    "Seul l'auteur de la commande peut changer de page.",
+                )
+                .await
+            }
+            None => return reply_ephemeral(ctx, interaction, EXPIRED).await,
+        };
+
+        state.navigate(action);
+        self.states.update(key, state.clone());
+        interaction
+            .create_interaction_response(&ctx.http, |response| {
+                response
+                    .kind(InteractionResponseType::UpdateMessage)
+                    .interaction_response_data(|data| {
+                        data.content(state.content())
+                            .set_components(build_components(key, &state))
+                    })
+            })
+            .await?;
+        Ok(())
+    }
+}
+
+fn build_components(key: &str, state: &PaginatorState) -> CreateComponents {
+    let mut components = CreateComponents::default();
+    components.create_action_row(|row| {
+        row.create_button(|button| {
+            button
+                .custom_id(build_custom_id(PAGINATOR_PREFIX, &[key, PREVIOUS_ACTION]))
+                .label("◀")
+                .style(ButtonStyle::Secondary)
+                .disabled(state.current == 0)
+        })
+        .create_button(|button| {
+            button
+                .custom_id(build_custom_id(PAGINATOR_PREFIX, &[key, COUNTER_ACTION]))
+                .label(format!("{}/{}", state.current + 1, state.pages.len()))
+                .style(ButtonStyle::Secondary)
+                .disabled(true)
+        })
+        .create_button(|button| {
+            button
+                .custom_id(build_custom_id(PAGINATOR_PREFIX, &[key, NEXT_ACTION]))
+                .label("▶")
+                .style(ButtonStyle::Secondary)
+                .disabled(state.current + 1 >= state.pages.len())
+        })
+    });
+    components
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PaginatorState;
+    use serenity::model::id::UserId;
+
+    #[test]
+    fn navigate_stays_within_bounds() {
+        let mut state = PaginatorState {
+            owner: UserId(1),
+            pages: vec!["a".to_string(), "b".to_string()],
+            current: 0,
+        };
+        state.navigate(super::PREVIOUS_ACTION);
+        assert_eq!(state.current, 0);
+        state.navigate(super::NEXT_ACTION);
+        state.navigate(super::NEXT_ACTION);
+        assert_eq!(state.current, 1);
+        assert_eq!(state.content(), "b");
+    }
+}
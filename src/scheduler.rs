@@ -0,0 +1,181 @@
+use crate::db::scheduled_job::ScheduledJob;
+use anyhow::anyhow;
+use anyhow::Error;
+use chrono::{DateTime, Utc};
+use cron::Schedule;
+use serenity::async_trait;
+use serenity::http::Http;
+use sqlx::MySqlPool;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+const TICK_INTERVAL: Duration = Duration::from_secs(30);
+/// a job that only runs once is dropped after failing this many times
+const MAX_ATTEMPTS: i32 = 5;
+const FIRST_RETRY_DELAY_SECONDS: i64 = 60;
+
+#[async_trait]
+pub trait Job: Send + Sync {
+    /// identifies the rows of the ScheduledJob table handled by this job
+    fn kind(&self) -> &'static str;
+
+    async fn run(&self, http: &Http, payload: &str) -> Result<(), Error>;
+}
+
+/// a job running on a cron schedule (with seconds, e.g. "0 */5 * * * *"), registered at startup
+pub struct RecurringJob {
+    pub name: String,
+    pub kind: String,
+    pub payload: String,
+    pub schedule: String,
+}
+
+pub struct Scheduler {
+    pub db_pool: Arc<MySqlPool>,
+    pub http: Arc<Http>,
+    pub jobs: Vec<Box<dyn Job>>,
+    pub recurring_jobs: Vec<RecurringJob>,
+}
+
+impl Scheduler {
+    /**
+     * register the recurring jobs and run the jobs stored in the database when they are due
+     */
+    pub fn start(self) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            if let Err(e) = self.register_recurring_jobs().await {
+                println!("error while registering recurring jobs : {}", e);
+            }
+
+            let mut interval = tokio::time::interval(TICK_INTERVAL);
+            loop {
+                interval.tick().await;
+                if let Err(e) = self.run_due_jobs().await {
+                    println!("error while running scheduled jobs : {}", e);
+                }
+            }
+        })
+    }
+
+    async fn register_recurring_jobs(&self) -> Result<(), Error> {
+        for job in &self.recurring_jobs {
+            // keep the next run of an unchanged job so that restarting the bot does not delay it
+            let next_run = match ScheduledJob::find_by_name(&self.db_pool, &job.name).await? {
+                Some(existing) if existing.schedule.as_deref() == Some(job.schedule.as_str()) => {
+                    existing.next_run
+                }
+                _ => next_run(&job.schedule, Utc::now())?,
+            };
+            ScheduledJob::upsert_recurring(
+                &self.db_pool,
+                &job.name,
+                &job.kind,
+                &job.payload,
+                &job.schedule,
+                next_run,
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
+    async fn run_due_jobs(&self) -> Result<(), Error> {
+        for scheduled_job in ScheduledJob::find_due(&self.db_pool, Utc::now()).await? {
+            let label = scheduled_job
+                .name
+                .clone()
+                .unwrap_or_else(|| format!("{} #{}", scheduled_job.kind, scheduled_job.id));
+            // a job failing to be rescheduled must not prevent the following ones from running
+            if let Err(e) = self.run_job(&scheduled_job, &label).await {
+                println!("error while scheduling job {} : {}", label, e);
+            }
+        }
+        Ok(())
+    }
+
+    async fn run_job(&self, scheduled_job: &ScheduledJob, label: &str) -> Result<(), Error> {
+        let result = match self.jobs.iter().find(|j| j.kind() == scheduled_job.kind) {
+            None => Err(anyhow!("no job registered for kind {}", scheduled_job.kind)),
+            Some(job) => job.run(&self.http, &scheduled_job.payload).await,
+        };
+        if let Err(e) = &result {
+            println!("error while running job {} : {}", label, e);
+        }
+
+        let now = Utc::now();
+        match &scheduled_job.schedule {
+            // a recurring job is retried on its next run
+            Some(schedule) => {
+                ScheduledJob::reschedule(
+                    &self.db_pool,
+                    scheduled_job.id,
+                    now,
+                    next_run(schedule, now)?,
+                )
+                .await?;
+            }
+            None if result.is_ok() => {
+                ScheduledJob::delete(&self.db_pool, scheduled_job.id).await?;
+            }
+            None => {
+                let attempts = scheduled_job.attempts + 1;
+                if attempts >= MAX_ATTEMPTS {
+                    println!("giving up job {} after {} attempts", label, attempts);
+                    ScheduledJob::delete(&self.db_pool, scheduled_job.id).await?;
+                } else {
+                    ScheduledJob::retry(
+                        &self.db_pool,
+                        scheduled_job.id,
+                        attempts,
+                        now + retry_delay(attempts),
+                    )
+                    .await?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/**
+ * delay before running a failed job again, doubling after every attempt
+ */
+fn retry_delay(attempts: i32) -> chrono::Duration {
+    chrono::Duration::seconds(FIRST_RETRY_DELAY_SECONDS << (attempts - 1).clamp(0, 16))
+}
+
+/**
+ * next date matching a cron schedule
+ */
+pub fn next_run(schedule: &str, after: DateTime<Utc>) -> Result<DateTime<Utc>, Error> {
+    Schedule::from_str(schedule)?
+        .after(&after)
+        .next()
+        .ok_or_else(|| anyhow!("schedule {} never runs", schedule))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+    use tokio_test::assert_ok;
+
+    #[test]
+    fn next_run() {
+        let after = Utc.with_ymd_and_hms(2023, 1, 1, 10, 2, 30).unwrap();
+        let next = assert_ok!(super::next_run("0 */5 * * * *", after));
+        assert_eq!(next, Utc.with_ymd_and_hms(2023, 1, 1, 10, 5, 0).unwrap());
+    }
+
+    #[test]
+    fn retry_delay() {
+        assert_eq!(super::retry_delay(1), chrono::Duration::minutes(1));
+        assert_eq!(super::retry_delay(3), chrono::Duration::minutes(4));
+    }
+
+    #[test]
+    fn invalid_schedule() {
+        assert!(super::next_run("every day", Utc::now()).is_err());
+    }
+}
@@ -0,0 +1,126 @@
+use crate::output::truncate;
+use crate::output::MAX_UPLOAD_SIZE;
+use anyhow::Error;
+use serenity::model::channel::Attachment;
+use serenity::model::channel::Embed;
+use std::path::PathBuf;
+
+/// larger files could not be uploaded again when displaying the quote
+pub const MAX_ARCHIVED_FILE_SIZE: u64 = MAX_UPLOAD_SIZE as u64;
+const EMBED_SUMMARY_LENGTH: usize = 200;
+
+/// local copies of the files of quoted messages
+pub struct QuoteArchive {
+    pub dir: PathBuf,
+}
+
+/**
+ * a file name safe to use on disk, made of letters, digits, dots, dashes and underscores
+ */
+pub fn sanitize_filename(filename: &str) -> String {
+    let sanitized: String = filename
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '.' || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    match sanitized.trim_start_matches('.') {
+        "" => "file".to_string(),
+        s => s.to_string(),
+    }
+}
+
+/**
+ * one line describing a link preview or a rich embed of a message
+ */
+pub fn summarize_embed(embed: &Embed) -> Option<String> {
+    let mut parts: Vec<String> = Vec::new();
+    if let Some(title) = &embed.title {
+        parts.push(format!("**{}**", title));
+    }
+    if let Some(description) = &embed.description {
+        parts.push(truncate(description, EMBED_SUMMARY_LENGTH));
+    }
+    if let Some(url) = &embed.url {
+        parts.push(format!("<{}>", url));
+    }
+    if parts.is_empty() {
+        return None;
+    }
+    Some(format!("🔗 {}", parts.join(" — ")))
+}
+
+impl QuoteArchive {
+    /**
+     * download an attachment into the archive, returning its path relative to the archive directory
+     */
+    pub async fn store(
+        &self,
+        book_id: u64,
+        message_id: u64,
+        attachment: &Attachment,
+    ) -> Result<String, Error> {
+        let data = attachment.download().await?;
+        let relative = format!(
+            "{}/{}_{}_{}",
+            book_id,
+            message_id,
+            attachment.id.0,
+            sanitize_filename(&attachment.filename)
+        );
+        let path = self.dir.join(&relative);
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::write(&path, data).await?;
+        Ok(relative)
+    }
+
+    pub async fn read(&self, relative: &str) -> Result<Vec<u8>, Error> {
+        Ok(tokio::fs::read(self.dir.join(relative)).await?)
+    }
+
+    pub async fn remove(&self, relative: &str) -> Result<(), Error> {
+        Ok(tokio::fs::remove_file(self.dir.join(relative)).await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serenity::model::channel::Embed;
+
+    #[test]
+    fn sanitize_filename() {
+        assert_eq!(
+            super::sanitize_filename("capture écran.png"),
+            "capture__cran.png"
+        );
+        assert_eq!(
+            super::sanitize_filename("../../etc/passwd"),
+            "_.._etc_passwd"
+        );
+        assert_eq!(super::sanitize_filename(".."), "file");
+    }
+
+    #[test]
+    fn summarize_embed() {
+        let embed: Embed = serde_json::from_value(serde_json::json!({
+            "type": "link",
+            "title": "Un article",
+            "description": "Le résumé",
+            "url": "https://a.b/article"
+        }))
+        .unwrap();
+        assert_eq!(
+            super::summarize_embed(&embed).as_deref(),
+            Some("🔗 **Un article** — Le résumé — <https://a.b/article>")
+        );
+
+        let empty: Embed = serde_json::from_value(serde_json::json!({ "type": "image" })).unwrap();
+        assert_eq!(super::summarize_embed(&empty), None);
+    }
+}
@@ -0,0 +1,216 @@
+use crate::utils::net::agent;
+use ab_glyph::{FontRef, PxScale};
+use anyhow::Error;
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageFormat, Rgba, RgbaImage};
+use imageproc::drawing::{draw_filled_rect_mut, draw_text_mut, text_size};
+use imageproc::rect::Rect;
+use std::io::{Cursor, Read};
+
+const REGULAR_FONT: &[u8] = include_bytes!("../../assets/fonts/DejaVuSans.ttf");
+const BOLD_FONT: &[u8] = include_bytes!("../../assets/fonts/DejaVuSans-Bold.ttf");
+
+const WIDTH: u32 = 800;
+const PADDING: u32 = 40;
+const AVATAR_SIZE: u32 = 96;
+const BAR_WIDTH: u32 = 6;
+const AUTHOR_SCALE: f32 = 34.0;
+const TEXT_SCALE: f32 = 28.0;
+const FOOTER_SCALE: f32 = 20.0;
+const LINE_SPACING: f32 = 1.35;
+/// longer quotes are cut so that the card stays readable
+const MAX_LINES: usize = 24;
+
+const BACKGROUND: Rgba<u8> = Rgba([43, 45, 49, 255]);
+const ACCENT: Rgba<u8> = Rgba([88, 101, 242, 255]);
+const TEXT_COLOR: Rgba<u8> = Rgba([242, 243, 245, 255]);
+const MUTED_COLOR: Rgba<u8> = Rgba([148, 155, 164, 255]);
+
+/// what is drawn on the image of a quote
+pub struct QuoteCard<'a> {
+    pub author: &'a str,
+    pub avatar: Option<DynamicImage>,
+    pub text: &'a str,
+    /// number and date of the quote
+    pub footer: &'a str,
+}
+
+/**
+ * cut a text into lines no wider than max_width, on spaces and on its own line breaks,
+ * words too long for a line being cut anywhere
+ */
+fn wrap(text: &str, max_width: u32, width_of: impl Fn(&str) -> u32) -> Vec<String> {
+    let mut lines = Vec::new();
+    for paragraph in text.lines() {
+        let mut line = String::new();
+        for word in paragraph.split_whitespace() {
+            let candidate = if line.is_empty() {
+                word.to_string()
+            } else {
+                format!("{} {}", line, word)
+            };
+            if width_of(&candidate) <= max_width {
+                line = candidate;
+                continue;
+            }
+            if !line.is_empty() {
+                lines.push(std::mem::take(&mut line));
+            }
+            for c in word.chars() {
+                line.push(c);
+                if line.chars().count() > 1 && width_of(&line) > max_width {
+                    line.pop();
+                    lines.push(std::mem::replace(&mut line, c.to_string()));
+                }
+            }
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+/**
+ * the avatar cut as a disc
+ */
+fn round_avatar(avatar: &DynamicImage) -> RgbaImage {
+    let mut avatar = avatar
+        .resize_to_fill(AVATAR_SIZE, AVATAR_SIZE, FilterType::Triangle)
+        .to_rgba8();
+    let radius = AVATAR_SIZE as f32 / 2.0;
+    for (x, y, pixel) in avatar.enumerate_pixels_mut() {
+        let dx = x as f32 + 0.5 - radius;
+        let dy = y as f32 + 0.5 - radius;
+        if dx * dx + dy * dy > radius * radius {
+            pixel[3] = 0;
+        }
+    }
+    avatar
+}
+
+/**
+ * download an image, e.g. an avatar
+ */
+pub fn download_image(url: &str) -> Result<DynamicImage, Error> {
+    let mut data = Vec::new();
+    agent()
+        .get(url)
+        .call()?
+        .into_reader()
+        .read_to_end(&mut data)?;
+    Ok(image::load_from_memory(&data)?)
+}
+
+/**
+ * render a quote as a PNG image
+ */
+pub fn render(card: &QuoteCard) -> Result<Vec<u8>, Error> {
+    let regular = FontRef::try_from_slice(REGULAR_FONT)?;
+    let bold = FontRef::try_from_slice(BOLD_FONT)?;
+    let text_scale = PxScale::from(TEXT_SCALE);
+    let line_height = (TEXT_SCALE * LINE_SPACING) as u32;
+
+    let text_left = PADDING + BAR_WIDTH + 20;
+    let mut lines = wrap(card.text, WIDTH - text_left - PADDING, |s| {
+        text_size(text_scale, &regular, s).0
+    });
+    if lines.len() > MAX_LINES {
+        lines.truncate(MAX_LINES);
+        if let Some(last) = lines.last_mut() {
+            last.push_str(" …");
+        }
+    }
+
+    let header_height = AVATAR_SIZE + 30;
+    let text_height = line_height * lines.len() as u32;
+    let height = PADDING + header_height + text_height + 30 + FOOTER_SCALE as u32 + PADDING;
+    let mut image = RgbaImage::from_pixel(WIDTH, height, BACKGROUND);
+
+    let author_left = match &card.avatar {
+        Some(avatar) => {
+            image::imageops::overlay(
+                &mut image,
+                &round_avatar(avatar),
+                PADDING as i64,
+                PADDING as i64,
+            );
+            PADDING + AVATAR_SIZE + 24
+        }
+        None => PADDING,
+    };
+    let author_scale = PxScale::from(AUTHOR_SCALE);
+    let author_top = PADDING + (AVATAR_SIZE - text_size(author_scale, &bold, card.author).1) / 2;
+    draw_text_mut(
+        &mut image,
+        TEXT_COLOR,
+        author_left as i32,
+        author_top as i32,
+        author_scale,
+        &bold,
+        card.author,
+    );
+
+    let text_top = PADDING + header_height;
+    draw_filled_rect_mut(
+        &mut image,
+        Rect::at(PADDING as i32, text_top as i32).of_size(BAR_WIDTH, text_height.max(1)),
+        ACCENT,
+    );
+    for (i, line) in lines.iter().enumerate() {
+        draw_text_mut(
+            &mut image,
+            TEXT_COLOR,
+            text_left as i32,
+            (text_top + line_height * i as u32) as i32,
+            text_scale,
+            &regular,
+            line,
+        );
+    }
+
+    draw_text_mut(
+        &mut image,
+        MUTED_COLOR,
+        PADDING as i32,
+        (text_top + text_height + 30) as i32,
+        PxScale::from(FOOTER_SCALE),
+        &regular,
+        card.footer,
+    );
+
+    let mut png = Vec::new();
+    image.write_to(&mut Cursor::new(&mut png), ImageFormat::Png)?;
+    Ok(png)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{render, wrap, QuoteCard};
+
+    #[test]
+    fn wrap_text() {
+        // one pixel per character
+        let width_of = |s: &str| s.chars().count() as u32;
+        assert_eq!(
+            wrap("le chat est sur\nla table", 10, width_of),
+            vec!["le chat", "est sur", "la table"]
+        );
+        assert_eq!(
+            wrap("abcdefghijklmnop qr", 6, width_of),
+            vec!["abcdef", "ghijkl", "mnop", "qr"]
+        );
+        assert_eq!(wrap("a\n\nb", 6, width_of), vec!["a", "", "b"]);
+    }
+
+    #[test]
+    fn render_png() {
+        let png = render(&QuoteCard {
+            author: "Xzibit",
+            avatar: Some(image::DynamicImage::new_rgba8(32, 32)),
+            text: "Yo dawg, I heard you like quotes",
+            footer: "Citation n°1 · 19/10/2026",
+        })
+        .unwrap();
+        let image = image::load_from_memory(&png).unwrap();
+        assert_eq!(image.width(), super::WIDTH);
+    }
+}
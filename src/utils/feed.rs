@@ -0,0 +1,264 @@
+use crate::output::truncate;
+use crate::output::MESSAGE_MAX_LENGTH;
+use crate::utils::text::strip_html;
+use anyhow::Error;
+use feed_rs::model::Entry;
+use regex::Captures;
+use regex::Regex;
+use std::sync::OnceLock;
+
+/// format of the messages posted for new entries when the subscription does not define one
+pub const DEFAULT_TEMPLATE: &str = "**{feed}** {title} - {link}";
+const SUMMARY_EXCERPT_LENGTH: usize = 200;
+const RULE_SEPARATOR: char = '\n';
+
+/// the placeholders of a template, compiled on first use
+static PLACEHOLDER: OnceLock<Regex> = OnceLock::new();
+
+/// a keyword, or a regex when written between slashes, matched against an entry
+pub enum FilterRule {
+    Keyword(String),
+    Regex(Regex),
+}
+
+impl FilterRule {
+    fn parse(rule: &str) -> Result<FilterRule, Error> {
+        if rule.len() > 2 && rule.starts_with('/') && rule.ends_with('/') {
+            let regex = Regex::new(&format!("(?i){}", &rule[1..rule.len() - 1]))?;
+            Ok(FilterRule::Regex(regex))
+        } else {
+            Ok(FilterRule::Keyword(rule.to_lowercase()))
+        }
+    }
+
+    fn matches(&self, text: &str) -> bool {
+        match self {
+            FilterRule::Keyword(keyword) => text.to_lowercase().contains(keyword),
+            FilterRule::Regex(regex) => regex.is_match(text),
+        }
+    }
+}
+
+/**
+ * parse stored filter rules, one rule per line
+ */
+pub fn parse_rules(rules: &str) -> Result<Vec<FilterRule>, Error> {
+    rules
+        .split(RULE_SEPARATOR)
+        .map(|r| r.trim())
+        .filter(|r| !r.is_empty())
+        .map(FilterRule::parse)
+        .collect()
+}
+
+/**
+ * split rules typed by a user on commas, except the commas inside a regex such as /a{1,3}/
+ */
+fn split_rules(input: &str) -> Vec<&str> {
+    let mut rules = Vec::new();
+    let mut start = 0;
+    for (i, c) in input.char_indices() {
+        if c != ',' {
+            continue;
+        }
+        let rule = input[start..i].trim();
+        let in_regex = rule.starts_with('/')
+            && !(rule.len() > 1 && rule.ends_with('/') && !rule.ends_with("\\/"));
+        if !in_regex {
+            rules.push(rule);
+            start = i + 1;
+        }
+    }
+    rules.push(input[start..].trim());
+    rules.retain(|r| !r.is_empty());
+    rules
+}
+
+/**
+ * convert rules typed by a user, separated by commas, to their stored form
+ */
+pub fn normalize_rules(input: &str) -> Result<String, Error> {
+    let rules = split_rules(input);
+    for rule in &rules {
+        FilterRule::parse(rule)?;
+    }
+    Ok(rules.join(&RULE_SEPARATOR.to_string()))
+}
+
+/**
+ * an entry is accepted if it matches one of the include rules (or there are none) and none of the exclude rules
+ */
+pub fn accepts(entry: &Entry, include: &[FilterRule], exclude: &[FilterRule]) -> bool {
+    let mut texts: Vec<String> = Vec::new();
+    if let Some(title) = &entry.title {
+        texts.push(title.content.clone());
+    }
+    if let Some(summary) = &entry.summary {
+        texts.push(strip_html(&summary.content));
+    }
+    for category in &entry.categories {
+        texts.push(
+            category
+                .label
+                .clone()
+                .unwrap_or_else(|| category.term.clone()),
+        );
+    }
+
+    let matches = |rule: &FilterRule| texts.iter().any(|t| rule.matches(t));
+    (include.is_empty() || include.iter().any(matches)) && !exclude.iter().any(matches)
+}
+
+/**
+ * fill the placeholders of a template: {feed}, {title}, {link}, {author}, {published} and {summary},
+ * the message being shortened to what Discord accepts
+ */
+pub fn render_template(template: &str, feed_title: &str, entry: &Entry) -> Option<String> {
+    let title = entry.title.as_ref()?;
+    let link = entry.links.first()?;
+
+    let author = entry
+        .authors
+        .first()
+        .map(|a| a.name.as_str())
+        .unwrap_or_default();
+    let published = entry
+        .published
+        .map(|d| d.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_default();
+    let summary = entry
+        .summary
+        .as_ref()
+        .map(|s| truncate(&strip_html(&s.content), SUMMARY_EXCERPT_LENGTH))
+        .unwrap_or_default();
+
+    // a single pass, so that placeholders written in the entry itself are left as they are
+    let placeholder = PLACEHOLDER
+        .get_or_init(|| Regex::new(r"\{(feed|title|link|author|published|summary)\}").unwrap());
+    let template = template.replace("\\n", "\n");
+    let rendered = placeholder.replace_all(&template, |captures: &Captures| {
+        match &captures[1] {
+            "feed" => feed_title,
+            "title" => &title.content,
+            "link" => &link.href,
+            "author" => author,
+            "published" => &published,
+            _ => &summary,
+        }
+        .to_string()
+    });
+    Some(truncate(&rendered, MESSAGE_MAX_LENGTH))
+}
+
+/**
+ * url of the image of an entry, from its media or enclosures
+ */
+pub fn entry_image(entry: &Entry) -> Option<String> {
+    entry.media.iter().find_map(|media| {
+        media
+            .content
+            .iter()
+            .find(|c| {
+                c.content_type
+                    .as_ref()
+                    .map(|t| t.type_().as_str() == "image")
+                    .unwrap_or(false)
+            })
+            .and_then(|c| c.url.as_ref().map(|u| u.to_string()))
+            .or_else(|| media.thumbnails.first().map(|t| t.image.uri.clone()))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use feed_rs::parser;
+
+    fn parse_entry(item: &str) -> feed_rs::model::Entry {
+        let xml = format!(
+            r#"<?xml version="1.0"?>
+            <rss version="2.0" xmlns:media="http://search.yahoo.com/mrss/"><channel><title>Feed</title>{}</channel></rss>"#,
+            item
+        );
+        parser::parse(xml.as_bytes())
+            .unwrap()
+            .entries
+            .into_iter()
+            .next()
+            .unwrap()
+    }
+
+    #[test]
+    fn filter_entries() {
+        let entry = parse_entry(
+            r#"<item><title>Un nouveau film</title><link>http://a.b/1</link>
+            <description>&lt;p&gt;Sortie au cinéma&lt;/p&gt;</description><category>Cinéma</category></item>"#,
+        );
+        let none = super::parse_rules("").unwrap();
+        let cinema = super::parse_rules("cinéma").unwrap();
+        let regex = super::parse_rules(&super::normalize_rules("/^un .* film$/").unwrap()).unwrap();
+        let tele = super::parse_rules("télé").unwrap();
+
+        assert!(super::accepts(&entry, &none, &none));
+        assert!(super::accepts(&entry, &cinema, &none));
+        assert!(super::accepts(&entry, &regex, &none));
+        assert!(!super::accepts(&entry, &tele, &none));
+        assert!(!super::accepts(&entry, &none, &cinema));
+    }
+
+    #[test]
+    fn normalize_invalid_regex() {
+        assert!(super::normalize_rules("foot, /(/").is_err());
+        assert_eq!(
+            super::normalize_rules("foot, rugby").unwrap(),
+            "foot\nrugby"
+        );
+        assert_eq!(
+            super::normalize_rules("foot, /a{1,3}/ , /b,/,rugby").unwrap(),
+            "foot\n/a{1,3}/\n/b,/\nrugby"
+        );
+    }
+
+    #[test]
+    fn render_template() {
+        let entry = parse_entry(
+            r#"<item><title>Titre</title><link>http://a.b/1</link><author>moi@a.b (Moi)</author>
+            <description>&lt;b&gt;Résumé&lt;/b&gt; de l'article</description></item>"#,
+        );
+        assert_eq!(
+            super::render_template(super::DEFAULT_TEMPLATE, "EXCLU!", &entry).unwrap(),
+            "**EXCLU!** Titre - http://a.b/1"
+        );
+        assert_eq!(
+            super::render_template("{title}\\n{summary}", "Feed", &entry).unwrap(),
+            "Titre\nRésumé de l'article"
+        );
+
+        let entry = parse_entry(
+            r#"<item><title>Le {link} du {summary}</title><link>http://a.b/1</link></item>"#,
+        );
+        assert_eq!(
+            super::render_template("{title} - {link}", "Feed", &entry).unwrap(),
+            "Le {link} du {summary} - http://a.b/1"
+        );
+
+        let entry = parse_entry(&format!(
+            r#"<item><title>Titre</title><link>http://a.b/1</link><description>{}</description></item>"#,
+            "a".repeat(300)
+        ));
+        let template = "{summary}\\n".repeat(20);
+        let rendered = super::render_template(&template, "Feed", &entry).unwrap();
+        assert_eq!(rendered.chars().count(), super::MESSAGE_MAX_LENGTH);
+    }
+
+    #[test]
+    fn entry_image_from_enclosure() {
+        let entry = parse_entry(
+            r#"<item><title>Titre</title><link>http://a.b/1</link>
+            <enclosure url="http://a.b/image.jpg" type="image/jpeg" length="1"/></item>"#,
+        );
+        assert_eq!(
+            super::entry_image(&entry).as_deref(),
+            Some("http://a.b/image.jpg")
+        );
+    }
+}
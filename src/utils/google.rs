@@ -29,6 +29,15 @@ impl GoogleSearcher {
         terms: String,
         mode: SearchMode,
     ) -> Result<Option<GoogleSearchItem>, Error> {
+        let item = self.search_all(terms, mode)?.into_iter().next();
+        Ok(item)
+    }
+
+    pub fn search_all(
+        &self,
+        terms: String,
+        mode: SearchMode,
+    ) -> Result<Vec<GoogleSearchItem>, Error> {
         let mut url = Url::parse("https://www.googleapis.com/customsearch/v1")?;
         url.query_pairs_mut()
             .append_pair("key", &self.google_key)
@@ -38,13 +47,11 @@ impl GoogleSearcher {
             url.query_pairs_mut().append_pair("searchType", "image");
         }
 
-        let item = ureq::get(url.as_str())
+        let items = ureq::get(url.as_str())
             .call()?
             .into_json::<GoogleSearch>()?
             .items
-            .unwrap_or_default()
-            .into_iter()
-            .next();
-        Ok(item)
+            .unwrap_or_default();
+        Ok(items)
     }
 }
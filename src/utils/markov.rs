@@ -0,0 +1,261 @@
+use crate::utils::search::fold;
+use crate::utils::text::tokenize;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// marks the beginning and the end of a sentence, words are never empty
+const BOUNDARY: &str = "";
+const KEY_SEPARATOR: &str = "\u{1f}";
+
+/// sentences of words, each word chosen after the `order` previous ones as often as it followed them in the corpus
+#[derive(Serialize, Deserialize)]
+#[serde(from = "SavedChain")]
+pub struct MarkovChain {
+    order: usize,
+    /// last Connerie learned, the following ones are learned when the chain is loaded
+    pub last_learned_id: i64,
+    /// previous words joined by KEY_SEPARATOR -> next word -> count
+    transitions: HashMap<String, HashMap<String, u32>>,
+    /// word folded like the seeds -> states holding it, rebuilt when loaded
+    #[serde(skip)]
+    states_by_word: HashMap<String, HashSet<String>>,
+    /// state -> word preceding the words of the state -> count, rebuilt when loaded
+    #[serde(skip)]
+    previous: HashMap<String, HashMap<String, u32>>,
+}
+
+/// what is saved of a chain, the indexes are computed from the transitions
+#[derive(Deserialize)]
+struct SavedChain {
+    order: usize,
+    last_learned_id: i64,
+    transitions: HashMap<String, HashMap<String, u32>>,
+}
+
+impl From<SavedChain> for MarkovChain {
+    fn from(saved: SavedChain) -> Self {
+        let mut chain = MarkovChain::new(saved.order);
+        chain.last_learned_id = saved.last_learned_id;
+        for (key, next) in saved.transitions {
+            let state: Vec<&str> = key.split(KEY_SEPARATOR).collect();
+            for (word, count) in next {
+                chain.add_transition(&state, &word, count);
+            }
+        }
+        chain
+    }
+}
+
+impl MarkovChain {
+    pub fn new(order: usize) -> MarkovChain {
+        MarkovChain {
+            order,
+            last_learned_id: 0,
+            transitions: HashMap::new(),
+            states_by_word: HashMap::new(),
+            previous: HashMap::new(),
+        }
+    }
+
+    fn add_transition(&mut self, state: &[&str], word: &str, count: u32) {
+        let key = state.join(KEY_SEPARATOR);
+        if !self.transitions.contains_key(&key) {
+            for folded in state.iter().flat_map(|w| tokenize(&fold(w))) {
+                self.states_by_word
+                    .entry(folded)
+                    .or_default()
+                    .insert(key.clone());
+            }
+        }
+        *self
+            .transitions
+            .entry(key)
+            .or_default()
+            .entry(word.to_string())
+            .or_insert(0) += count;
+
+        // the state reached by this transition can be walked back to the first word of this one
+        if let Some((first, rest)) = state.split_first() {
+            if word != BOUNDARY {
+                let reached = rest.iter().copied().chain([word]).collect::<Vec<&str>>();
+                *self
+                    .previous
+                    .entry(reached.join(KEY_SEPARATOR))
+                    .or_default()
+                    .entry(first.to_string())
+                    .or_insert(0) += count;
+            }
+        }
+    }
+
+    pub fn learn(&mut self, text: &str) {
+        let words: Vec<&str> = text.split_whitespace().collect();
+        if words.is_empty() {
+            return;
+        }
+
+        let mut state = vec![BOUNDARY; self.order];
+        for word in words.into_iter().chain([BOUNDARY]) {
+            self.add_transition(&state, word, 1);
+            state.remove(0);
+            state.push(word);
+        }
+    }
+
+    /**
+     * the states holding one of the seeds, ignoring case, accents and punctuation
+     */
+    fn seeded_states(&self, seeds: &[&str]) -> Vec<&String> {
+        let mut states: Vec<&String> = seeds
+            .iter()
+            .flat_map(|s| tokenize(&fold(s)))
+            .filter_map(|s| self.states_by_word.get(&s))
+            .flatten()
+            .collect();
+        // the same order for the same chain, whatever the order of the sets
+        states.sort_unstable();
+        states.dedup();
+        states
+    }
+
+    /**
+     * the words leading to a state from the beginning of a sentence, each one chosen as often as it
+     * preceded the following ones in the corpus, at most max_words of them
+     */
+    fn words_before<'a>(
+        &'a self,
+        state: &[&'a str],
+        max_words: usize,
+        rng: &mut impl Rng,
+    ) -> Vec<&'a str> {
+        let mut before = Vec::new();
+        let mut state = state.to_vec();
+        while state.first().is_some_and(|w| *w != BOUNDARY) && before.len() < max_words {
+            let previous: Vec<(&String, &u32)> = match self.previous.get(&state.join(KEY_SEPARATOR))
+            {
+                Some(previous) => previous.iter().collect(),
+                None => break,
+            };
+            let word = match previous.choose_weighted(rng, |(_, count)| **count) {
+                Ok((word, _)) => word.as_str(),
+                Err(_) => break,
+            };
+            if word != BOUNDARY {
+                before.push(word);
+            }
+            state.pop();
+            state.insert(0, word);
+        }
+        before.reverse();
+        before
+    }
+
+    /**
+     * a new sentence of at most max_words words, containing one of the seeds if some are given,
+     * none if the chain is empty or knows none of the seeds
+     */
+    pub fn generate(&self, seeds: &[&str], max_words: usize, rng: &mut impl Rng) -> Option<String> {
+        let (mut state, mut words): (Vec<&str>, Vec<&str>) = if seeds.is_empty() {
+            (vec![BOUNDARY; self.order], Vec::new())
+        } else {
+            // the sentence starts at a beginning of the corpus, not in the middle of a phrase
+            let key = self.seeded_states(seeds).choose(rng).copied()?;
+            let state: Vec<&str> = key.split(KEY_SEPARATOR).collect();
+            let words = self.words_before(&state, max_words.saturating_sub(self.order), rng);
+            (state, words)
+        };
+        words.extend(state.iter().copied().filter(|w| !w.is_empty()));
+
+        while words.len() < max_words {
+            let next = match self.transitions.get(&state.join(KEY_SEPARATOR)) {
+                Some(next) => next,
+                None => break,
+            };
+            let total: u32 = next.values().sum();
+            let mut pick = rng.gen_range(0..total);
+            let word = next
+                .iter()
+                .find(|(_, count)| {
+                    if pick < **count {
+                        true
+                    } else {
+                        pick -= **count;
+                        false
+                    }
+                })
+                .map(|(word, _)| word.as_str())?;
+            if word == BOUNDARY {
+                break;
+            }
+            words.push(word);
+            state.remove(0);
+            state.push(word);
+        }
+
+        if words.is_empty() {
+            None
+        } else {
+            Some(words.join(" "))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MarkovChain;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn generate_learned_sentence() {
+        let mut chain = MarkovChain::new(2);
+        chain.learn("le chat dort sur le canapé");
+        let mut rng = StdRng::seed_from_u64(42);
+        assert_eq!(
+            chain.generate(&[], 50, &mut rng).as_deref(),
+            Some("le chat dort sur le canapé")
+        );
+        assert_eq!(
+            chain.generate(&[], 3, &mut rng).as_deref(),
+            Some("le chat dort")
+        );
+    }
+
+    #[test]
+    fn generate_from_seed() {
+        let mut chain = MarkovChain::new(1);
+        chain.learn("il fait beau");
+        chain.learn("vivement Noël, c'est demain");
+        let mut rng = StdRng::seed_from_u64(42);
+        for _ in 0..10 {
+            assert_eq!(
+                chain.generate(&["noel"], 50, &mut rng).as_deref(),
+                Some("vivement Noël, c'est demain")
+            );
+        }
+
+        let mut chain = MarkovChain::new(2);
+        chain.learn("on dit que le père Noël arrive ce soir");
+        chain.learn("le père Noël arrive demain");
+        for _ in 0..10 {
+            let sentence = chain.generate(&["noel"], 50, &mut rng).unwrap();
+            assert!(
+                sentence.starts_with("on dit que le père") || sentence.starts_with("le père"),
+                "{}",
+                sentence
+            );
+        }
+        assert_eq!(chain.generate(&["pluie"], 50, &mut rng), None);
+
+        // the indexes are rebuilt when a saved chain is loaded
+        let saved = serde_json::to_string(&chain).unwrap();
+        let chain: MarkovChain = serde_json::from_str(&saved).unwrap();
+        for _ in 0..10 {
+            let sentence = chain.generate(&["noel"], 50, &mut rng).unwrap();
+            assert!(sentence.contains("Noël"), "{}", sentence);
+        }
+        assert_eq!(MarkovChain::new(2).generate(&[], 50, &mut rng), None);
+    }
+}
@@ -1,6 +1,16 @@
 use linkify::LinkFinder;
 
+pub mod archive;
+pub mod card;
+pub mod feed;
 pub mod google;
+pub mod markov;
+pub mod movies;
+pub mod net;
+pub mod relevance;
+pub mod search;
+pub mod text;
+pub mod tvmaze;
 
 /**
  * extract the first url of a string
@@ -8,7 +18,7 @@ pub mod google;
 pub fn extract_url(input: &str) -> Option<&str> {
     let finder = LinkFinder::new();
     let links: Vec<_> = finder.links(input).collect();
-    links.get(0).map(|l| l.as_str())
+    links.first().map(|l| l.as_str())
 }
 
 #[cfg(test)]
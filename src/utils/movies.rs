@@ -0,0 +1,288 @@
+use crate::utils::net::agent;
+use anyhow::Error;
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use serde::Deserialize;
+use std::path::Path;
+use url::Url;
+
+const TMDB_IMAGE_URL: &str = "https://image.tmdb.org/t/p/w500";
+
+/// how a film was released
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReleaseKind {
+    Theatrical,
+    Digital,
+    Physical,
+    Tv,
+}
+
+#[derive(Clone, Deserialize)]
+pub struct ReleaseDate {
+    pub kind: ReleaseKind,
+    pub date: NaiveDate,
+}
+
+#[derive(Clone, Deserialize)]
+pub struct Movie {
+    pub title: String,
+    pub year: Option<i32>,
+    /// in minutes
+    pub runtime: Option<u32>,
+    #[serde(default)]
+    pub genres: Vec<String>,
+    pub synopsis: Option<String>,
+    /// out of 10
+    pub rating: Option<f32>,
+    pub vote_count: Option<u32>,
+    pub poster_url: Option<String>,
+    pub imdb_id: Option<String>,
+    /// release dates in the configured region
+    #[serde(default)]
+    pub release_dates: Vec<ReleaseDate>,
+}
+
+/// the movie provider chosen in the configuration
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MovieSource {
+    #[default]
+    Tmdb,
+    /// films listed in movie_local_path
+    Local,
+}
+
+/// a source of film metadata
+pub trait MovieProvider: Send + Sync {
+    /// the film best matching a search
+    fn search(&self, query: &str) -> Result<Option<Movie>, Error>;
+}
+
+#[derive(Deserialize)]
+struct TmdbSearch {
+    results: Vec<TmdbSearchResult>,
+}
+
+#[derive(Deserialize)]
+struct TmdbSearchResult {
+    id: u64,
+}
+
+#[derive(Deserialize)]
+struct TmdbMovie {
+    title: String,
+    release_date: Option<String>,
+    runtime: Option<u32>,
+    #[serde(default)]
+    genres: Vec<TmdbGenre>,
+    overview: Option<String>,
+    vote_average: Option<f32>,
+    vote_count: Option<u32>,
+    poster_path: Option<String>,
+    imdb_id: Option<String>,
+    release_dates: Option<TmdbReleaseDates>,
+}
+
+#[derive(Deserialize)]
+struct TmdbGenre {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct TmdbReleaseDates {
+    results: Vec<TmdbCountryReleaseDates>,
+}
+
+#[derive(Deserialize)]
+struct TmdbCountryReleaseDates {
+    iso_3166_1: String,
+    release_dates: Vec<TmdbReleaseDate>,
+}
+
+#[derive(Deserialize)]
+struct TmdbReleaseDate {
+    #[serde(rename = "type")]
+    kind: u8,
+    release_date: DateTime<Utc>,
+}
+
+/// The Movie Database (https://www.themoviedb.org) API
+pub struct TmdbProvider {
+    pub api_url: String,
+    pub api_key: String,
+    /// ISO 3166-1 code of the country whose release dates are displayed
+    pub region: String,
+    pub language: String,
+}
+
+impl TmdbProvider {
+    fn url(&self, path: &str, query: &[(&str, &str)]) -> Result<Url, Error> {
+        let mut url = Url::parse(&format!("{}{}", self.api_url.trim_end_matches('/'), path))?;
+        url.query_pairs_mut()
+            .append_pair("api_key", &self.api_key)
+            .append_pair("language", &self.language)
+            .extend_pairs(query);
+        Ok(url)
+    }
+
+    fn convert(&self, movie: TmdbMovie) -> Movie {
+        let release_date = movie
+            .release_date
+            .as_deref()
+            .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok());
+
+        let mut release_dates: Vec<ReleaseDate> = movie
+            .release_dates
+            .map(|r| r.results)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|r| r.iso_3166_1.eq_ignore_ascii_case(&self.region))
+            .flat_map(|r| r.release_dates)
+            .filter_map(|r| {
+                // 1 is a premiere and 2 a limited theatrical release, not meaningful for the public
+                let kind = match r.kind {
+                    3 => ReleaseKind::Theatrical,
+                    4 => ReleaseKind::Digital,
+                    5 => ReleaseKind::Physical,
+                    6 => ReleaseKind::Tv,
+                    _ => return None,
+                };
+                Some(ReleaseDate {
+                    kind,
+                    date: r.release_date.date_naive(),
+                })
+            })
+            .collect();
+        release_dates.sort_by_key(|r| r.date);
+
+        Movie {
+            title: movie.title,
+            year: release_date.map(|d| d.year()),
+            runtime: movie.runtime.filter(|r| *r > 0),
+            genres: movie.genres.into_iter().map(|g| g.name).collect(),
+            synopsis: movie.overview.filter(|o| !o.is_empty()),
+            rating: movie
+                .vote_average
+                .filter(|_| movie.vote_count.unwrap_or(0) > 0),
+            vote_count: movie.vote_count,
+            poster_url: movie
+                .poster_path
+                .map(|p| format!("{}{}", TMDB_IMAGE_URL, p)),
+            imdb_id: movie.imdb_id.filter(|i| !i.is_empty()),
+            release_dates,
+        }
+    }
+}
+
+impl MovieProvider for TmdbProvider {
+    fn search(&self, query: &str) -> Result<Option<Movie>, Error> {
+        let url = self.url(
+            "/search/movie",
+            &[("query", query), ("region", &self.region)],
+        )?;
+        let search = agent()
+            .get(url.as_str())
+            .call()?
+            .into_json::<TmdbSearch>()?;
+        let id = match search.results.first() {
+            Some(r) => r.id,
+            None => return Ok(None),
+        };
+
+        let url = self.url(
+            &format!("/movie/{}", id),
+            &[("append_to_response", "release_dates")],
+        )?;
+        let movie = agent().get(url.as_str()).call()?.into_json::<TmdbMovie>()?;
+        Ok(Some(self.convert(movie)))
+    }
+}
+
+/// films listed in a JSON file, to run the bot without an API key or test without network access
+pub struct LocalMovieProvider {
+    pub movies: Vec<Movie>,
+}
+
+impl LocalMovieProvider {
+    pub fn load(path: &Path) -> Result<LocalMovieProvider, Error> {
+        let movies = serde_json::from_slice(&std::fs::read(path)?)?;
+        Ok(LocalMovieProvider { movies })
+    }
+}
+
+impl MovieProvider for LocalMovieProvider {
+    /// the first film whose title contains the search, ignoring case
+    fn search(&self, query: &str) -> Result<Option<Movie>, Error> {
+        let query = query.to_lowercase();
+        Ok(self
+            .movies
+            .iter()
+            .find(|m| m.title.to_lowercase().contains(&query))
+            .cloned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LocalMovieProvider;
+    use super::MovieProvider;
+    use super::ReleaseKind;
+    use super::TmdbProvider;
+
+    #[test]
+    fn convert_tmdb_movie() {
+        let provider = TmdbProvider {
+            api_url: String::new(),
+            api_key: String::new(),
+            region: "FR".to_string(),
+            language: "fr-FR".to_string(),
+        };
+        let movie = serde_json::from_str(
+            r#"{
+            "title": "Inception", "release_date": "2010-07-15", "runtime": 148,
+            "genres": [{"id": 28, "name": "Action"}], "overview": "Dom Cobb...",
+            "vote_average": 8.4, "vote_count": 35000, "poster_path": "/poster.jpg", "imdb_id": "tt1375666",
+            "release_dates": {"results": [
+                {"iso_3166_1": "US", "release_dates": [{"type": 3, "release_date": "2010-07-16T00:00:00.000Z"}]},
+                {"iso_3166_1": "FR", "release_dates": [
+                    {"type": 4, "release_date": "2010-12-08T00:00:00.000Z"},
+                    {"type": 1, "release_date": "2010-07-08T00:00:00.000Z"},
+                    {"type": 3, "release_date": "2010-07-21T00:00:00.000Z"}
+                ]}
+            ]}
+        }"#,
+        )
+        .unwrap();
+
+        let movie = provider.convert(movie);
+        assert_eq!(movie.year, Some(2010));
+        assert_eq!(
+            movie.poster_url.as_deref(),
+            Some("https://image.tmdb.org/t/p/w500/poster.jpg")
+        );
+        let kinds: Vec<ReleaseKind> = movie.release_dates.iter().map(|r| r.kind).collect();
+        assert_eq!(kinds, vec![ReleaseKind::Theatrical, ReleaseKind::Digital]);
+    }
+
+    #[test]
+    fn search_local_movies() {
+        let provider = LocalMovieProvider {
+            movies: serde_json::from_str(
+                r#"[
+                {"title": "Inception", "year": 2010,
+                 "release_dates": [{"kind": "theatrical", "date": "2010-07-21"}]},
+                {"title": "Interstellar", "year": 2014}
+            ]"#,
+            )
+            .unwrap(),
+        };
+        let movie = provider.search("INTER").unwrap().unwrap();
+        assert_eq!(movie.title, "Interstellar");
+        assert!(movie.genres.is_empty());
+        assert_eq!(
+            provider.search("inception").unwrap().unwrap().release_dates[0].kind,
+            ReleaseKind::Theatrical
+        );
+        assert!(provider.search("nothing").unwrap().is_none());
+    }
+}
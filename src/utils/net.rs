@@ -0,0 +1,113 @@
+use std::io;
+use std::net::{IpAddr, SocketAddr, ToSocketAddrs};
+use std::sync::OnceLock;
+use std::time::Duration;
+use ureq::{Agent, AgentBuilder};
+
+/// requests give up after this long, a slow site must not hold a blocking thread for good
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+
+static AGENT: OnceLock<Agent> = OnceLock::new();
+static PUBLIC_AGENT: OnceLock<Agent> = OnceLock::new();
+
+/**
+ * the HTTP client of the APIs the bot uses
+ */
+pub fn agent() -> &'static Agent {
+    AGENT.get_or_init(|| AgentBuilder::new().timeout(REQUEST_TIMEOUT).build())
+}
+
+/**
+ * the HTTP client of the URLs given by users, which can only reach public addresses
+ */
+pub fn public_agent() -> &'static Agent {
+    PUBLIC_AGENT.get_or_init(|| {
+        AgentBuilder::new()
+            .timeout(REQUEST_TIMEOUT)
+            .resolver(PublicResolver)
+            .build()
+    })
+}
+
+/// resolves host names like the system does, refusing the ones pointing to the bot's own network
+pub struct PublicResolver;
+
+impl ureq::Resolver for PublicResolver {
+    fn resolve(&self, netloc: &str) -> io::Result<Vec<SocketAddr>> {
+        let addrs: Vec<SocketAddr> = netloc.to_socket_addrs()?.collect();
+        match addrs.iter().find(|a| !is_public(a.ip())) {
+            Some(addr) => Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                format!(
+                    "{} resolves to the non public address {}",
+                    netloc,
+                    addr.ip()
+                ),
+            )),
+            None => Ok(addrs),
+        }
+    }
+}
+
+/**
+ * whether an address can be reached from the internet, and not only from the bot's host or network
+ */
+pub fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            !(ip.is_private()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_multicast()
+                || ip.is_documentation()
+                || a == 0
+                // shared address space of carrier grade NAT, 100.64.0.0/10
+                || (a == 100 && (b & 0xc0) == 64))
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public(IpAddr::V4(ip)),
+            None => {
+                let first = ip.segments()[0];
+                !(ip.is_loopback()
+                    || ip.is_unspecified()
+                    || ip.is_multicast()
+                    // unique local, fc00::/7, and link local, fe80::/10
+                    || (first & 0xfe00) == 0xfc00
+                    || (first & 0xffc0) == 0xfe80)
+            }
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::IpAddr;
+
+    fn is_public(ip: &str) -> bool {
+        super::is_public(ip.parse::<IpAddr>().unwrap())
+    }
+
+    #[test]
+    fn private_addresses() {
+        assert!(is_public("93.184.216.34"));
+        assert!(is_public("2606:2800:220:1:248:1893:25c8:1946"));
+        for ip in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "::1",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:127.0.0.1",
+        ] {
+            assert!(!is_public(ip), "{}", ip);
+        }
+    }
+}
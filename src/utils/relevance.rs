@@ -0,0 +1,152 @@
+use crate::utils::search::fold;
+use crate::utils::text::is_stop_word;
+use crate::utils::text::tokenize;
+use rand::Rng;
+use std::collections::HashMap;
+
+/// the reply is picked among this many best matches
+const CANDIDATES: usize = 5;
+
+/**
+ * the meaningful words of a text, folded so that accents and case do not matter
+ */
+fn terms(text: &str) -> Vec<String> {
+    tokenize(text)
+        .into_iter()
+        .filter(|w| w.chars().count() > 1 && !is_stop_word(w))
+        .map(|w| fold(&w))
+        .collect()
+}
+
+/// texts scored against a message with TF-IDF, the words shared by few texts weighing more
+pub struct RelevanceIndex {
+    texts: Vec<String>,
+    /// frequency of each term in each text
+    frequencies: Vec<HashMap<String, u32>>,
+    /// inverse document frequency of each term
+    idf: HashMap<String, f64>,
+}
+
+impl RelevanceIndex {
+    pub fn new(texts: Vec<String>) -> RelevanceIndex {
+        let frequencies: Vec<HashMap<String, u32>> = texts
+            .iter()
+            .map(|text| {
+                let mut frequencies = HashMap::new();
+                for term in terms(text) {
+                    *frequencies.entry(term).or_insert(0) += 1;
+                }
+                frequencies
+            })
+            .collect();
+
+        let mut document_frequencies: HashMap<&String, usize> = HashMap::new();
+        for term in frequencies.iter().flat_map(|f| f.keys()) {
+            *document_frequencies.entry(term).or_insert(0) += 1;
+        }
+        let count = texts.len() as f64;
+        let idf = document_frequencies
+            .into_iter()
+            .map(|(term, df)| (term.clone(), (count / df as f64).ln()))
+            .collect();
+
+        RelevanceIndex {
+            texts,
+            frequencies,
+            idf,
+        }
+    }
+
+    /**
+     * the texts sharing words with the message, best first, the message itself excluded
+     */
+    fn top_matches(&self, message: &str, limit: usize) -> Vec<(usize, f64)> {
+        let mut query = terms(message);
+        query.sort();
+        query.dedup();
+        let mut matches: Vec<(usize, f64)> = self
+            .frequencies
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| self.texts[*i].trim() != message.trim())
+            .filter_map(|(i, frequencies)| {
+                let length: u32 = frequencies.values().sum();
+                let score: f64 = query
+                    .iter()
+                    .filter_map(|t| Some(*frequencies.get(t)? as f64 * self.idf.get(t)?))
+                    .sum::<f64>()
+                    / (length.max(1) as f64).sqrt();
+                (score > 0.0).then_some((i, score))
+            })
+            .collect();
+        matches.sort_by(|(i1, s1), (i2, s2)| s2.total_cmp(s1).then(i1.cmp(i2)));
+        matches.truncate(limit);
+        matches
+    }
+
+    /**
+     * one of the texts most related to the message, more likely the better it matches,
+     * none if no text shares a meaningful word with it
+     */
+    pub fn pick(&self, message: &str, rng: &mut impl Rng) -> Option<&str> {
+        let matches = self.top_matches(message, CANDIDATES);
+        let total: f64 = matches.iter().map(|(_, s)| s).sum();
+        if total <= 0.0 {
+            return None;
+        }
+        let mut pick = rng.gen_range(0.0..total);
+        for (i, score) in &matches {
+            if pick < *score {
+                return Some(&self.texts[*i]);
+            }
+            pick -= score;
+        }
+        matches.last().map(|(i, _)| self.texts[*i].as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RelevanceIndex;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn index() -> RelevanceIndex {
+        RelevanceIndex::new(
+            [
+                "le chat est sur la table",
+                "les pizzas de chez Mario sont les meilleures",
+                "la pizza à l'ananas c'est non",
+                "il fait beau sur la table",
+                "le chat de la voisine a mangé ma pizza",
+            ]
+            .iter()
+            .map(|t| t.to_string())
+            .collect(),
+        )
+    }
+
+    #[test]
+    fn score_matches() {
+        let index = index();
+        assert_eq!(index.top_matches("un chat et une pizza", 5)[0].0, 4);
+        let matches = index.top_matches("Mario fait des pizzas", 5);
+        assert_eq!(matches[0].0, 1);
+        assert!(matches.iter().all(|(i, _)| *i != 0));
+        assert!(index.top_matches("c'est la vie", 5).is_empty());
+    }
+
+    #[test]
+    fn pick_related_text() {
+        let index = index();
+        let mut rng = StdRng::seed_from_u64(42);
+        for _ in 0..10 {
+            let text = index.pick("Une pizza à l'ananas ?", &mut rng).unwrap();
+            assert!(text.contains("pizza"), "{}", text);
+        }
+        // the message itself is never replied
+        let text = index.pick("le chat est sur la table", &mut rng).unwrap();
+        assert_ne!(text, "le chat est sur la table");
+        assert_eq!(index.pick("rien à voir", &mut rng), None);
+    }
+}
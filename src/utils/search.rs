@@ -0,0 +1,219 @@
+use crate::utils::text::tokenize;
+use unidecode::unidecode;
+
+/// words kept before the first match of an excerpt
+const EXCERPT_CONTEXT_WORDS: usize = 5;
+
+/// a search typed by a user: words, "quoted phrases" and -excluded words or phrases
+#[derive(Debug, Default, PartialEq)]
+pub struct SearchQuery {
+    pub terms: Vec<String>,
+    pub phrases: Vec<String>,
+    pub excluded: Vec<String>,
+}
+
+/**
+ * lowercase a text and remove its accents
+ */
+pub fn fold(text: &str) -> String {
+    unidecode(text).to_lowercase()
+}
+
+/**
+ * a clause of a MySQL boolean mode search, only made of words so that the user cannot inject operators
+ */
+fn boolean_clause(operator: char, value: &str, prefix: bool) -> Option<String> {
+    let words = tokenize(value);
+    match words.len() {
+        0 => None,
+        1 => Some(format!(
+            "{}{}{}",
+            operator,
+            words[0],
+            if prefix { "*" } else { "" }
+        )),
+        _ => Some(format!("{}\"{}\"", operator, words.join(" "))),
+    }
+}
+
+impl SearchQuery {
+    pub fn parse(input: &str) -> SearchQuery {
+        let mut query = SearchQuery::default();
+        let mut chars = input.chars().peekable();
+        loop {
+            while chars.next_if(|c| c.is_whitespace()).is_some() {}
+            if chars.peek().is_none() {
+                break;
+            }
+
+            let excluded = chars.next_if_eq(&'-').is_some();
+            let quoted = chars.next_if_eq(&'"').is_some();
+            let mut value = String::new();
+            while let Some(c) = chars.next_if(|c| {
+                if quoted {
+                    *c != '"'
+                } else {
+                    !c.is_whitespace()
+                }
+            }) {
+                value.push(c);
+            }
+            if quoted {
+                chars.next();
+            }
+
+            let value = value.trim().to_string();
+            if value.is_empty() {
+                continue;
+            }
+            if excluded {
+                query.excluded.push(value);
+            } else if quoted {
+                query.phrases.push(value);
+            } else {
+                query.terms.push(value);
+            }
+        }
+        query
+    }
+
+    /**
+     * the query in MySQL boolean mode syntax, none if nothing is required to match
+     */
+    pub fn to_boolean_mode(&self) -> Option<String> {
+        let required: Vec<String> = self
+            .terms
+            .iter()
+            .filter_map(|t| boolean_clause('+', t, true))
+            .chain(
+                self.phrases
+                    .iter()
+                    .filter_map(|p| boolean_clause('+', p, false)),
+            )
+            .collect();
+        if required.is_empty() {
+            return None;
+        }
+
+        let excluded = self
+            .excluded
+            .iter()
+            .filter_map(|e| boolean_clause('-', e, false));
+        Some(
+            required
+                .into_iter()
+                .chain(excluded)
+                .collect::<Vec<_>>()
+                .join(" "),
+        )
+    }
+
+    /**
+     * the folded words of each term and phrase
+     */
+    fn patterns(&self) -> Vec<Vec<String>> {
+        self.terms
+            .iter()
+            .chain(self.phrases.iter())
+            .map(|v| tokenize(&fold(v)))
+            .filter(|p| !p.is_empty())
+            .collect()
+    }
+
+    /**
+     * the part of a text around its first match, with the matching words in bold
+     */
+    pub fn excerpt(&self, text: &str, max_length: usize) -> String {
+        let chunks: Vec<&str> = text.split_whitespace().collect();
+        let words: Vec<(usize, String)> = chunks
+            .iter()
+            .enumerate()
+            .flat_map(|(i, c)| tokenize(&fold(c)).into_iter().map(move |w| (i, w)))
+            .collect();
+
+        // the last word of a pattern may be the beginning of a longer word, as in the database search
+        let mut highlighted = vec![false; chunks.len()];
+        for pattern in self.patterns() {
+            for start in 0..words.len() {
+                let matches = pattern.iter().enumerate().all(|(k, p)| {
+                    words.get(start + k).is_some_and(|(_, w)| {
+                        w == p || (k == pattern.len() - 1 && w.starts_with(p.as_str()))
+                    })
+                });
+                if matches {
+                    for (chunk, _) in &words[start..start + pattern.len()] {
+                        highlighted[*chunk] = true;
+                    }
+                }
+            }
+        }
+
+        let first = highlighted.iter().position(|h| *h).unwrap_or(0);
+        let start = first.saturating_sub(EXCERPT_CONTEXT_WORDS);
+        let mut excerpt = if start > 0 {
+            "… ".to_string()
+        } else {
+            String::new()
+        };
+        for (i, chunk) in chunks.iter().enumerate().skip(start) {
+            let word = if highlighted[i] {
+                format!("**{}**", chunk)
+            } else {
+                chunk.to_string()
+            };
+            if i > first && excerpt.chars().count() + word.chars().count() + 2 > max_length {
+                excerpt.push_str(" …");
+                break;
+            }
+            if i > start {
+                excerpt.push(' ');
+            }
+            excerpt.push_str(&word);
+        }
+        excerpt
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SearchQuery;
+
+    #[test]
+    fn parse_query() {
+        let query = SearchQuery::parse(r#"noël "le père" -sapin -"pas cher""#);
+        assert_eq!(query.terms, vec!["noël"]);
+        assert_eq!(query.phrases, vec!["le père"]);
+        assert_eq!(query.excluded, vec!["sapin", "pas cher"]);
+        assert_eq!(
+            query.to_boolean_mode().as_deref(),
+            Some(r#"+noël* +"le père" -sapin -"pas cher""#)
+        );
+    }
+
+    #[test]
+    fn boolean_mode_operators_are_removed() {
+        let query = SearchQuery::parse(r#"100% +a_b* (c) "d"#);
+        assert_eq!(
+            query.to_boolean_mode().as_deref(),
+            Some(r#"+100* +"a b" +c* +d"#)
+        );
+        assert_eq!(SearchQuery::parse("-seul").to_boolean_mode(), None);
+        assert_eq!(SearchQuery::parse("  ").to_boolean_mode(), None);
+    }
+
+    #[test]
+    fn excerpt() {
+        let query = SearchQuery::parse(r#"noel "pere fouettard""#);
+        assert_eq!(
+            query.excerpt("Joyeux Noël à tous, sauf au Père Fouettard !", 100),
+            "Joyeux **Noël** à tous, sauf au **Père** **Fouettard** !"
+        );
+        assert_eq!(
+            query.excerpt(
+                "un deux trois quatre cinq six sept noëlle huit neuf dix",
+                30
+            ),
+            "… trois quatre cinq six sept **noëlle** …"
+        );
+    }
+}
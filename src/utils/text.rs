@@ -0,0 +1,86 @@
+use scraper::Html;
+use std::collections::HashMap;
+
+/// common French words that carry no meaning on their own
+const STOP_WORDS: &[&str] = &[
+    "a", "ai", "aie", "aient", "aies", "ait", "alors", "as", "au", "aucun", "aussi", "autre",
+    "aux", "avais", "avait", "avant", "avec", "avoir", "bah", "bas", "ben", "bien", "bon", "c",
+    "ca", "ça", "car", "ce", "ceci", "cela", "celle", "celui", "ces", "cet", "cette", "ceux",
+    "chez", "ci", "comme", "comment", "d", "dans", "de", "des", "deja", "déjà", "donc", "dont",
+    "du", "elle", "elles", "en", "encore", "es", "est", "et", "etc", "été", "être", "eu", "fait",
+    "faire", "fais", "faut", "il", "ils", "j", "je", "juste", "l", "la", "le", "les", "leur",
+    "leurs", "lui", "m", "ma", "mais", "me", "meme", "même", "mes", "moi", "mon", "n", "ne", "ni",
+    "non", "nos", "notre", "nous", "on", "ont", "ou", "où", "oui", "par", "parce", "pas", "peu",
+    "peut", "plus", "pour", "pourquoi", "qu", "quand", "que", "quel", "quelle", "qui", "quoi", "s",
+    "sa", "sans", "se", "sera", "ses", "si", "sinon", "son", "sont", "sous", "suis", "sur", "t",
+    "ta", "te", "tes", "toi", "ton", "tous", "tout", "toute", "tres", "très", "tu", "un", "une",
+    "va", "vais", "vers", "voila", "voilà", "vos", "votre", "vous", "y",
+];
+
+/**
+ * text content of an html fragment, with whitespace collapsed
+ */
+pub fn strip_html(html: &str) -> String {
+    let fragment = Html::parse_fragment(html);
+    let text: Vec<&str> = fragment.root_element().text().collect();
+    text.join(" ")
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+/**
+ * split a text into lowercase words
+ */
+pub fn tokenize(input: &str) -> Vec<String> {
+    input
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(|w| w.to_lowercase())
+        .collect()
+}
+
+pub fn is_stop_word(word: &str) -> bool {
+    STOP_WORDS.contains(&word)
+}
+
+/**
+ * words of the texts sorted from the most to the least frequent, ignoring stop words and words shorter than min_length
+ */
+pub fn frequent_words(texts: &[String], min_length: usize) -> Vec<String> {
+    let mut frequencies: HashMap<String, usize> = HashMap::new();
+    for text in texts {
+        for word in tokenize(text) {
+            if word.chars().count() >= min_length && !is_stop_word(&word) {
+                *frequencies.entry(word).or_insert(0) += 1;
+            }
+        }
+    }
+
+    let mut words: Vec<(String, usize)> = frequencies.into_iter().collect();
+    words.sort_by(|(w1, c1), (w2, c2)| c2.cmp(c1).then_with(|| w1.cmp(w2)));
+    words.into_iter().map(|(w, _)| w).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn tokenize() {
+        assert_eq!(
+            super::tokenize("L'été, c'est Noël !"),
+            vec!["l", "été", "c", "est", "noël"]
+        );
+    }
+
+    #[test]
+    fn frequent_words() {
+        let texts = vec![
+            "les chats et les chiens".to_string(),
+            "les chiens aboient".to_string(),
+        ];
+        assert_eq!(
+            super::frequent_words(&texts, 4),
+            vec!["chiens", "aboient", "chats"]
+        );
+    }
+}
@@ -0,0 +1,368 @@
+use crate::utils::net::agent;
+use anyhow::anyhow;
+use anyhow::Error;
+use chrono::{DateTime, NaiveDate, Utc};
+use chrono_humanize::HumanTime;
+use serde::Deserialize;
+use url::Url;
+
+const TVMAZE_API_URL: &str = "https://api.tvmaze.com";
+/// the best match of a search is taken without asking when its score is this many times the next one
+const SCORE_LEAD_RATIO: f64 = 1.5;
+
+#[derive(Deserialize)]
+pub struct TVMazeShow {
+    pub id: u64,
+    pub name: String,
+    pub status: Option<String>,
+    pub premiered: Option<String>,
+    pub summary: Option<String>,
+    pub network: Option<TVMazeNetwork>,
+    #[serde(rename = "webChannel")]
+    pub web_channel: Option<TVMazeNetwork>,
+    pub schedule: Option<TVMazeSchedule>,
+    #[serde(default)]
+    pub externals: TVMazeExternals,
+    #[serde(rename = "_embedded")]
+    pub embedded: Option<TVMazeEmbedded>,
+}
+
+#[derive(Deserialize)]
+struct TVMazeShowMatch {
+    score: f64,
+    show: TVMazeShow,
+}
+
+#[derive(Deserialize)]
+pub struct TVMazeNetwork {
+    pub name: String,
+    pub country: Option<TVMazeCountry>,
+}
+
+#[derive(Deserialize)]
+pub struct TVMazeCountry {
+    pub name: String,
+}
+
+#[derive(Deserialize)]
+pub struct TVMazeSchedule {
+    pub time: String,
+    pub days: Vec<String>,
+}
+
+#[derive(Deserialize, Default)]
+pub struct TVMazeExternals {
+    pub imdb: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct TVMazeEmbedded {
+    pub episodes: Vec<TVMazeEpisode>,
+}
+
+#[derive(Deserialize)]
+pub struct TVMazeEpisode {
+    pub id: u64,
+    pub name: String,
+    pub season: u32,
+    pub number: Option<u32>,
+    pub airstamp: Option<DateTime<Utc>>,
+    pub airtime: Option<String>,
+    pub summary: Option<String>,
+    /// only present in schedules
+    pub show: Option<Box<TVMazeShow>>,
+}
+
+/// result of a show search
+pub enum ShowLookup {
+    /// a single show matches, with its episodes
+    Found(Box<TVMazeShow>),
+    /// several shows match, without their episodes
+    Ambiguous(Vec<TVMazeShow>),
+    NotFound,
+}
+
+impl TVMazeShow {
+    pub fn episodes(&self) -> &[TVMazeEpisode] {
+        self.embedded
+            .as_ref()
+            .map(|e| e.episodes.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// the network airing the show, or its streaming service
+    pub fn channel(&self) -> Option<&TVMazeNetwork> {
+        self.network.as_ref().or(self.web_channel.as_ref())
+    }
+
+    /**
+     * name with the premiere year and network, to tell apart shows with the same name
+     */
+    pub fn label(&self) -> String {
+        let mut details: Vec<&str> = Vec::new();
+        if let Some(premiered) = &self.premiered {
+            details.push(premiered.get(..4).unwrap_or(premiered));
+        }
+        if let Some(channel) = self.channel() {
+            details.push(&channel.name);
+        }
+        if details.is_empty() {
+            self.name.clone()
+        } else {
+            format!("{} ({})", self.name, details.join(", "))
+        }
+    }
+}
+
+fn api_url(path: &str, query: &[(&str, &str)]) -> Result<Url, Error> {
+    let mut url = Url::parse(TVMAZE_API_URL)?.join(path)?;
+    url.query_pairs_mut().extend_pairs(query);
+    Ok(url)
+}
+
+/**
+ * the show best matching a search, with its episodes
+ */
+pub fn search_show(query: &str) -> Result<Option<TVMazeShow>, Error> {
+    let url = api_url(
+        "/singlesearch/shows",
+        &[("q", query), ("embed", "episodes")],
+    )?;
+    match agent().get(url.as_str()).call() {
+        Ok(r) => Ok(Some(r.into_json::<TVMazeShow>()?)),
+        Err(ureq::Error::Status(404, _)) => Ok(None),
+        Err(e) => Err(anyhow!("{}", e)),
+    }
+}
+
+/**
+ * every show matching a search, without their episodes
+ */
+pub fn search_shows(query: &str) -> Result<Vec<TVMazeShow>, Error> {
+    Ok(search_matches(query)?.into_iter().map(|m| m.show).collect())
+}
+
+fn search_matches(query: &str) -> Result<Vec<TVMazeShowMatch>, Error> {
+    let url = api_url("/search/shows", &[("q", query)])?;
+    let matches = agent()
+        .get(url.as_str())
+        .call()?
+        .into_json::<Vec<TVMazeShowMatch>>()?;
+    Ok(matches)
+}
+
+/**
+ * the shows a search can refer to, best first: the shows named exactly like the search if there are some,
+ * else the best match alone if it is well ahead of the others, else every match
+ */
+fn candidates(query: &str, mut matches: Vec<TVMazeShowMatch>) -> Vec<TVMazeShow> {
+    let query = query.trim().to_lowercase();
+    if matches.iter().any(|m| m.show.name.to_lowercase() == query) {
+        matches.retain(|m| m.show.name.to_lowercase() == query);
+    } else if let [first, second, ..] = matches.as_slice() {
+        if first.score >= second.score * SCORE_LEAD_RATIO {
+            matches.truncate(1);
+        }
+    }
+    matches.into_iter().map(|m| m.show).collect()
+}
+
+/**
+ * find the show matching a search, a show id can be given as "#123" (the value of the suggestions)
+ */
+pub fn lookup_show(query: &str) -> Result<ShowLookup, Error> {
+    if let Some(id) = show_id(query) {
+        return Ok(ShowLookup::Found(Box::new(fetch_show(id)?)));
+    }
+
+    let mut shows = candidates(query, search_matches(query)?);
+    match shows.len() {
+        0 => Ok(ShowLookup::NotFound),
+        1 => Ok(ShowLookup::Found(Box::new(fetch_show(shows.remove(0).id)?))),
+        _ => Ok(ShowLookup::Ambiguous(shows)),
+    }
+}
+
+/**
+ * the id of a show picked in an autocompletion, e.g. "#123"
+ */
+pub fn show_id(query: &str) -> Option<u64> {
+    query.strip_prefix('#').and_then(|i| i.parse::<u64>().ok())
+}
+
+/**
+ * a show and its episodes
+ */
+pub fn fetch_show(id: u64) -> Result<TVMazeShow, Error> {
+    let url = api_url(&format!("/shows/{}", id), &[("embed", "episodes")])?;
+    let show = agent()
+        .get(url.as_str())
+        .call()?
+        .into_json::<TVMazeShow>()?;
+    Ok(show)
+}
+
+/**
+ * episodes aired on a date in a country (ISO 3166-1 code), with their show
+ */
+pub fn fetch_schedule(country: &str, date: NaiveDate) -> Result<Vec<TVMazeEpisode>, Error> {
+    let date = date.format("%Y-%m-%d").to_string();
+    let url = api_url("/schedule", &[("country", country), ("date", &date)])?;
+    let episodes = agent()
+        .get(url.as_str())
+        .call()?
+        .into_json::<Vec<TVMazeEpisode>>()?;
+    Ok(episodes)
+}
+
+/**
+ * the last aired episode and the next one to air
+ */
+pub fn find_previous_and_next_episodes(
+    episodes: &[TVMazeEpisode],
+) -> (Option<&TVMazeEpisode>, Option<&TVMazeEpisode>) {
+    let mut previous: Option<&TVMazeEpisode> = None;
+    let mut next: Option<&TVMazeEpisode> = None;
+
+    let now = Utc::now();
+    for episode in episodes {
+        if let Some(d) = episode.airstamp {
+            if d < now {
+                previous = Some(episode);
+            } else if next.is_none() {
+                next = Some(episode);
+            }
+        }
+    }
+
+    (previous, next)
+}
+
+/**
+ * the episode following an aired one, which can air at the same time, or the next one to air if none aired
+ */
+pub fn find_episode_after(
+    episodes: &[TVMazeEpisode],
+    aired: Option<(u64, DateTime<Utc>)>,
+) -> Option<&TVMazeEpisode> {
+    let (aired_id, aired_at) = match aired {
+        Some(aired) => aired,
+        None => return find_previous_and_next_episodes(episodes).1,
+    };
+    // episodes airing at the same time, like double premieres, come after the aired one in the list
+    let aired_position = episodes.iter().position(|e| e.id == aired_id);
+    episodes
+        .iter()
+        .enumerate()
+        .find(|(i, e)| {
+            e.airstamp.is_some_and(|d| {
+                d > aired_at || (d == aired_at && aired_position.is_some_and(|p| *i > p))
+            })
+        })
+        .map(|(_, e)| e)
+}
+
+/**
+ * episode code and name, e.g. "S01E02 - Pilot"
+ */
+pub fn episode_title(episode: &TVMazeEpisode) -> String {
+    match episode.number {
+        Some(number) => format!("S{:02}E{:02} - {}", episode.season, number, episode.name),
+        None => format!("S{:02} Spécial - {}", episode.season, episode.name),
+    }
+}
+
+pub fn build_episode_line(episode: &TVMazeEpisode) -> String {
+    match episode.airstamp {
+        None => format!("{} (?)", episode_title(episode)),
+        Some(date) => {
+            let formatted_airdate = date.format("%Y-%m-%d %H:%M").to_string();
+            let human_airdate = format!("{}", HumanTime::from(date));
+            format!(
+                "{} ({}, {})",
+                episode_title(episode),
+                formatted_airdate,
+                human_airdate
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    fn show_names(query: &str, matches: &str) -> Vec<String> {
+        let matches = serde_json::from_str(matches).unwrap();
+        super::candidates(query, matches)
+            .into_iter()
+            .map(|s| s.name)
+            .collect()
+    }
+
+    #[test]
+    fn candidates() {
+        let matches = r#"[
+            {"score": 0.9, "show": {"id": 1, "name": "The Office"}},
+            {"score": 0.9, "show": {"id": 2, "name": "The Office"}},
+            {"score": 0.5, "show": {"id": 3, "name": "The Office Mix"}}
+        ]"#;
+        assert_eq!(
+            show_names("the office", matches),
+            ["The Office", "The Office"]
+        );
+        let matches = r#"[
+            {"score": 0.9, "show": {"id": 1, "name": "Breaking Bad"}},
+            {"score": 0.3, "show": {"id": 2, "name": "Breaking"}}
+        ]"#;
+        assert_eq!(show_names("breaking ba", matches), ["Breaking Bad"]);
+        let matches = r#"[
+            {"score": 0.9, "show": {"id": 1, "name": "Doctor Who"}},
+            {"score": 0.8, "show": {"id": 2, "name": "Doctor Who (2005)"}}
+        ]"#;
+        assert_eq!(show_names("doctor wh", matches).len(), 2);
+    }
+
+    #[test]
+    fn episode_title() {
+        let episode = super::TVMazeEpisode {
+            id: 1,
+            name: "Pilot".to_string(),
+            season: 1,
+            number: Some(2),
+            airstamp: None,
+            airtime: None,
+            summary: None,
+            show: None,
+        };
+        assert_eq!(super::episode_title(&episode), "S01E02 - Pilot");
+        assert_eq!(super::build_episode_line(&episode), "S01E02 - Pilot (?)");
+    }
+
+    #[test]
+    fn episode_after() {
+        let episode = |id: u64, airstamp: &str| super::TVMazeEpisode {
+            id,
+            name: format!("Episode {}", id),
+            season: 1,
+            number: Some(id as u32),
+            airstamp: Some(airstamp.parse().unwrap()),
+            airtime: None,
+            summary: None,
+            show: None,
+        };
+        let episodes = [
+            episode(1, "2020-01-01T20:00:00Z"),
+            episode(2, "2020-01-01T20:00:00Z"),
+            episode(3, "2020-01-08T20:00:00Z"),
+        ];
+        let after = |id: u64| {
+            super::find_episode_after(
+                &episodes,
+                Some((id, episodes[id as usize - 1].airstamp.unwrap())),
+            )
+            .map(|e| e.id)
+        };
+        assert_eq!(after(1), Some(2));
+        assert_eq!(after(2), Some(3));
+        assert_eq!(after(3), None);
+    }
+}